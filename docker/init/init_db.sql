@@ -14,6 +14,8 @@ GRANT ALL PRIVILEGES ON DATABASE mealpedant TO mealpedant;
 
 \c mealpedant
 
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
 CREATE TABLE IF NOT EXISTS ip_address (
 	ip_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
 	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
@@ -39,6 +41,7 @@ CREATE TABLE IF NOT EXISTS registered_user (
 	full_name TEXT NOT NULL,
 	email TEXT UNIQUE NOT NULL,
 	active BOOLEAN NOT NULL DEFAULT false,
+	meal_reminder BOOLEAN NOT NULL DEFAULT true,
 	password_hash TEXT NOT NULL,
 	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
 	ip_id BIGINT REFERENCES ip_address(ip_id) NOT NULL,
@@ -110,9 +113,18 @@ GRANT ALL ON meal_description TO mealpedant;
 
 GRANT USAGE, SELECT ON SEQUENCE meal_description_meal_description_id_seq TO mealpedant;
 
+CREATE INDEX meal_description_tsvector ON meal_description USING GIN (to_tsvector('english', description));
+
+CREATE INDEX meal_description_trigram ON meal_description USING GIN (description gin_trgm_ops);
+
 CREATE TABLE IF NOT EXISTS meal_person (
 	meal_person_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
-	person TEXT UNIQUE NOT NULL CHECK (person IN ('Dave', 'Jack'))
+	person TEXT UNIQUE NOT NULL,
+	initial TEXT UNIQUE NOT NULL CHECK (initial ~ '^[A-Z]$'),
+	visibility TEXT NOT NULL DEFAULT 'authenticated' CHECK (visibility IN ('public', 'authenticated', 'admin')),
+	linked_user_id BIGINT REFERENCES registered_user(registered_user_id) ON DELETE SET NULL,
+	-- drawn from the individual_meal_audit sequence whenever the person's visibility or initial changes, so every one of their meals is sent as a change
+	visibility_audit_id BIGINT
 );
 
 GRANT ALL ON meal_person TO mealpedant;
@@ -120,12 +132,12 @@ GRANT ALL ON meal_person TO mealpedant;
 GRANT USAGE, SELECT ON SEQUENCE meal_person_meal_person_id_seq TO mealpedant;
 
 
--- Create the two people entries here, this could be an enum instead
+-- Create the two original people entries here, further people can be managed via the admin api
 INSERT INTO
-	meal_person (person)
+	meal_person (person, initial, visibility)
 VALUES
-	('Dave'),
-	('Jack');
+	('Dave', 'D', 'authenticated'),
+	('Jack', 'J', 'public');
 
 CREATE TABLE IF NOT EXISTS meal_photo (
 	meal_photo_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
@@ -139,6 +151,23 @@ GRANT ALL ON meal_photo TO mealpedant;
 
 GRANT USAGE, SELECT ON SEQUENCE meal_photo_meal_photo_id_seq TO mealpedant;
 
+CREATE TABLE IF NOT EXISTS meal_venue (
+	meal_venue_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	name TEXT NOT NULL CHECK (name = trim(name) AND char_length(name) BETWEEN 1 AND 64),
+	city TEXT NOT NULL CHECK (city = trim(city) AND char_length(city) BETWEEN 1 AND 64),
+	venue_type TEXT NOT NULL CHECK (venue_type IN ('restaurant', 'takeaway', 'cafe', 'pub', 'other')),
+	latitude DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90),
+	longitude DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180),
+	registered_user_id BIGINT NOT NULL REFERENCES registered_user(registered_user_id),
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+	CHECK ((latitude IS NULL) = (longitude IS NULL)),
+	UNIQUE (name, city)
+);
+
+GRANT ALL ON meal_venue TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE meal_venue_meal_venue_id_seq TO mealpedant;
+
 CREATE TABLE IF NOT EXISTS meal_date (
 	meal_date_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
 	registered_user_id BIGINT NOT NULL REFERENCES registered_user(registered_user_id),
@@ -159,16 +188,59 @@ CREATE TABLE IF NOT EXISTS individual_meal (
 	restaurant BOOLEAN,
 	takeaway BOOLEAN,
 	vegetarian BOOLEAN,
-	meal_photo_id BIGINT REFERENCES meal_photo(meal_photo_id),
+	visibility TEXT CHECK (visibility IN ('public', 'authenticated', 'admin')),
 	registered_user_id BIGINT NOT NULL REFERENCES registered_user(registered_user_id),
-	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
-	UNIQUE (meal_date, meal_person)
+	meal_slot TEXT NOT NULL DEFAULT 'dinner' CHECK (meal_slot IN ('breakfast', 'lunch', 'dinner')),
+	meal_venue_id BIGINT REFERENCES meal_venue(meal_venue_id),
+	deleted_at TIMESTAMPTZ,
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
 );
 
 GRANT ALL ON individual_meal TO mealpedant;
 
 GRANT USAGE, SELECT ON SEQUENCE individual_meal_individual_meal_id_seq TO mealpedant;
 
+CREATE INDEX IF NOT EXISTS individual_meal_meal_venue_id ON individual_meal(meal_venue_id);
+
+-- A deleted meal is kept in the trash, and doesn't block a new meal in the same slot
+CREATE UNIQUE INDEX IF NOT EXISTS individual_meal_meal_date_id_meal_person_id_meal_slot_key ON individual_meal(meal_date_id, meal_person_id, meal_slot) WHERE deleted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS individual_meal_deleted_at ON individual_meal(deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS meal_tag (
+	meal_tag_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	tag TEXT UNIQUE NOT NULL CHECK (tag = lower(trim(tag)) AND char_length(tag) BETWEEN 1 AND 32),
+	registered_user_id BIGINT NOT NULL REFERENCES registered_user(registered_user_id),
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+GRANT ALL ON meal_tag TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE meal_tag_meal_tag_id_seq TO mealpedant;
+
+CREATE TABLE IF NOT EXISTS individual_meal_tag (
+	individual_meal_id BIGINT NOT NULL REFERENCES individual_meal(individual_meal_id) ON DELETE CASCADE,
+	meal_tag_id BIGINT NOT NULL REFERENCES meal_tag(meal_tag_id) ON DELETE CASCADE,
+	PRIMARY KEY (individual_meal_id, meal_tag_id)
+);
+
+GRANT ALL ON individual_meal_tag TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_tag_meal_tag_id ON individual_meal_tag(meal_tag_id);
+
+CREATE TABLE IF NOT EXISTS individual_meal_photo (
+	individual_meal_id BIGINT NOT NULL REFERENCES individual_meal(individual_meal_id) ON DELETE CASCADE,
+	meal_photo_id BIGINT NOT NULL REFERENCES meal_photo(meal_photo_id),
+	position SMALLINT NOT NULL CHECK (position >= 0),
+	caption TEXT CHECK (caption = trim(caption) AND char_length(caption) BETWEEN 1 AND 256),
+	PRIMARY KEY (individual_meal_id, position),
+	UNIQUE (individual_meal_id, meal_photo_id)
+);
+
+GRANT ALL ON individual_meal_photo TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_photo_meal_photo_id ON individual_meal_photo(meal_photo_id);
+
 CREATE TABLE IF NOT EXISTS login_history (
 	login_history_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
 	registered_user_id BIGINT REFERENCES registered_user(registered_user_id) ON DELETE CASCADE,
@@ -387,6 +459,10 @@ CREATE TABLE IF NOT EXISTS individual_meal_audit (
 
 GRANT ALL ON individual_meal_audit TO mealpedant;
 
+GRANT USAGE, SELECT ON SEQUENCE individual_meal_audit_individual_meal_audit_id_seq TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_audit_individual_meal_id ON individual_meal_audit(((new_values->>'individual_meal_id')::BIGINT));
+
 GRANT USAGE, SELECT ON SEQUENCE individual_meal_individual_meal_id_seq TO mealpedant;
 
 CREATE FUNCTION individual_meal_modify_func() RETURNS TRIGGER AS $ body $ BEGIN IF tg_op = 'UPDATE' THEN
@@ -459,6 +535,95 @@ INSERT
 UPDATE
 	OR DELETE ON individual_meal FOR EACH ROW EXECUTE PROCEDURE individual_meal_modify_func();
 
+-- individual meal photo audit, a meal revision's photos are the audited rows at the time of the revision
+CREATE TABLE IF NOT EXISTS individual_meal_photo_audit (
+	individual_meal_photo_audit_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	table_name TEXT NOT NULL,
+	user_name TEXT,
+	action_timestamp TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	action TEXT NOT NULL CHECK (action IN ('i', 'd', 'u')),
+	old_values jsonb,
+	new_values jsonb,
+	difference jsonb,
+	query TEXT
+);
+
+GRANT ALL ON individual_meal_photo_audit TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE individual_meal_photo_audit_individual_meal_photo_audit_id_seq TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_photo_audit_individual_meal_id ON individual_meal_photo_audit(((COALESCE(new_values, old_values)->>'individual_meal_id')::BIGINT));
+
+CREATE FUNCTION individual_meal_photo_modify_func() RETURNS TRIGGER AS $ body $ BEGIN IF tg_op = 'UPDATE' THEN
+INSERT into
+	individual_meal_photo_audit (
+		table_name,
+		user_name,
+		action,
+		old_values,
+		new_values,
+		difference,
+		query
+	)
+VALUES
+	(
+		tg_table_name :: TEXT,
+		current_user,
+		'u',
+		to_jsonb(OLD),
+		to_jsonb(NEW),
+		to_jsonb(OLD) - to_jsonb(NEW),
+		current_query()
+	);
+
+RETURN new;
+
+ELSIF tg_op = 'DELETE' THEN
+INSERT into
+	individual_meal_photo_audit (
+		table_name,
+		user_name,
+		action,
+		old_values,
+		query
+	)
+VALUES
+	(
+		tg_table_name :: TEXT,
+		current_user,
+		'd',
+		to_jsonb(OLD),
+		current_query()
+	);
+
+RETURN old;
+
+ELSIF tg_op = 'INSERT' THEN
+INSERT into
+	individual_meal_photo_audit (table_name, user_name, action, new_values, query)
+VALUES
+	(
+		tg_table_name :: TEXT,
+		current_user,
+		'i',
+		to_jsonb(NEW),
+		current_query()
+	);
+
+RETURN new;
+
+END IF;
+
+END;
+
+$ body $ LANGUAGE plpgsql;
+
+CREATE TRIGGER individual_meal_photo_audit_trig BEFORE
+INSERT
+	OR
+UPDATE
+	OR DELETE ON individual_meal_photo FOR EACH ROW EXECUTE PROCEDURE individual_meal_photo_modify_func();
+
 /**
  ** New tables
  */
@@ -548,6 +713,21 @@ INSERT
 UPDATE
 	OR DELETE ON meal_category FOR EACH ROW EXECUTE PROCEDURE meal_category_modify_func();
 
+-- meal category admin renames and merges
+CREATE TABLE IF NOT EXISTS meal_category_change (
+	meal_category_change_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	action TEXT NOT NULL CHECK (action IN ('rename', 'merge')),
+	from_category TEXT NOT NULL,
+	to_category TEXT NOT NULL,
+	meal_count BIGINT NOT NULL,
+	registered_user_id BIGINT REFERENCES registered_user(registered_user_id) ON DELETE SET NULL,
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+GRANT ALL ON meal_category_change TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE meal_category_change_meal_category_change_id_seq TO mealpedant;
+
 -- meal date audit
 CREATE TABLE IF NOT EXISTS meal_date_audit (
 	meal_date_audit_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
@@ -10,14 +10,6 @@ UPDATE meal_photo
 SET photo_converted = REPLACE(photo_converted, '.jpeg', '.jpg')
 WHERE photo_converted LIKE '%.jpeg';
 
-\echo "meal_photo remove unused photos"
-DELETE FROM meal_photo mp
-WHERE NOT EXISTS (
-	SELECT *
-	FROM individual_meal im
-	WHERE im.meal_photo_id= mp.meal_photo_id
-);
-
 \echo "login_attempt registered_user_id NOT NULL"
 ALTER TABLE login_attempt 
 ALTER COLUMN registered_user_id SET NOT NULL;
@@ -28,4 +20,311 @@ ALTER COLUMN login_attempt_number SET NOT NULL;
 
 \echo "registered_user active NOT NULL"
 ALTER TABLE registered_user 
-ALTER COLUMN active SET NOT NULL;
\ No newline at end of file
+ALTER COLUMN active SET NOT NULL;
+
+\echo "meal_person remove Dave/Jack constraint"
+ALTER TABLE meal_person
+DROP CONSTRAINT IF EXISTS meal_person_person_check;
+
+\echo "meal_person add initial and visibility"
+ALTER TABLE meal_person
+ADD COLUMN IF NOT EXISTS initial TEXT;
+UPDATE meal_person
+SET initial = LEFT(person, 1)
+WHERE initial IS NULL;
+ALTER TABLE meal_person
+ALTER COLUMN initial SET NOT NULL;
+
+-- Each constraint is added on its own, so that one already existing doesn't stop the other being added
+DO $$
+BEGIN
+	IF NOT EXISTS (
+		SELECT 1
+		FROM pg_constraint
+		WHERE conrelid = 'meal_person'::regclass AND conname = 'meal_person_initial_key'
+	) THEN
+		ALTER TABLE meal_person
+		ADD CONSTRAINT meal_person_initial_key UNIQUE (initial);
+	END IF;
+END $$;
+
+DO $$
+BEGIN
+	IF NOT EXISTS (
+		SELECT 1
+		FROM pg_constraint
+		WHERE conrelid = 'meal_person'::regclass AND conname = 'meal_person_initial_check'
+	) THEN
+		ALTER TABLE meal_person
+		ADD CONSTRAINT meal_person_initial_check CHECK (initial ~ '^[A-Z]$');
+	END IF;
+END $$;
+
+-- Only Jack was public before visibility existed, the backfill must only happen once, else it would undo any later change to his visibility
+DO $$
+BEGIN
+	IF NOT EXISTS (
+		SELECT 1
+		FROM information_schema.columns
+		WHERE table_name = 'meal_person' AND column_name = 'visibility'
+	) THEN
+		ALTER TABLE meal_person
+		ADD COLUMN visibility TEXT NOT NULL DEFAULT 'authenticated' CHECK (visibility IN ('public', 'authenticated', 'admin'));
+		UPDATE meal_person
+		SET visibility = 'public'
+		WHERE person = 'Jack';
+	END IF;
+END $$;
+
+ALTER TABLE meal_person
+DROP COLUMN IF EXISTS public;
+
+\echo "individual_meal add visibility"
+ALTER TABLE individual_meal
+ADD COLUMN IF NOT EXISTS visibility TEXT CHECK (visibility IN ('public', 'authenticated', 'admin'));
+
+\echo "meal_description search indexes"
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX IF NOT EXISTS meal_description_tsvector ON meal_description USING GIN (to_tsvector('english', description));
+CREATE INDEX IF NOT EXISTS meal_description_trigram ON meal_description USING GIN (description gin_trgm_ops);
+
+\echo "meal_category_change table"
+CREATE TABLE IF NOT EXISTS meal_category_change (
+	meal_category_change_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	action TEXT NOT NULL CHECK (action IN ('rename', 'merge')),
+	from_category TEXT NOT NULL,
+	to_category TEXT NOT NULL,
+	meal_count BIGINT NOT NULL,
+	registered_user_id BIGINT REFERENCES registered_user(registered_user_id) ON DELETE SET NULL,
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+GRANT ALL ON meal_category_change TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE meal_category_change_meal_category_change_id_seq TO mealpedant;
+
+\echo "registered_user add meal_reminder"
+ALTER TABLE registered_user
+ADD COLUMN IF NOT EXISTS meal_reminder BOOLEAN NOT NULL DEFAULT true;
+
+\echo "meal_person add linked_user_id"
+ALTER TABLE meal_person
+ADD COLUMN IF NOT EXISTS linked_user_id BIGINT REFERENCES registered_user(registered_user_id) ON DELETE SET NULL;
+
+\echo "meal_tag and individual_meal_tag tables"
+CREATE TABLE IF NOT EXISTS meal_tag (
+	meal_tag_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	tag TEXT UNIQUE NOT NULL CHECK (tag = lower(trim(tag)) AND char_length(tag) BETWEEN 1 AND 32),
+	registered_user_id BIGINT NOT NULL REFERENCES registered_user(registered_user_id),
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+GRANT ALL ON meal_tag TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE meal_tag_meal_tag_id_seq TO mealpedant;
+
+CREATE TABLE IF NOT EXISTS individual_meal_tag (
+	individual_meal_id BIGINT NOT NULL REFERENCES individual_meal(individual_meal_id) ON DELETE CASCADE,
+	meal_tag_id BIGINT NOT NULL REFERENCES meal_tag(meal_tag_id) ON DELETE CASCADE,
+	PRIMARY KEY (individual_meal_id, meal_tag_id)
+);
+
+GRANT ALL ON individual_meal_tag TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_tag_meal_tag_id ON individual_meal_tag(meal_tag_id);
+
+\echo "individual_meal add meal_slot"
+ALTER TABLE individual_meal
+ADD COLUMN IF NOT EXISTS meal_slot TEXT NOT NULL DEFAULT 'dinner' CHECK (meal_slot IN ('breakfast', 'lunch', 'dinner'));
+
+-- The unique slot per person per date is the partial index added with deleted_at
+ALTER TABLE individual_meal
+DROP CONSTRAINT IF EXISTS individual_meal_meal_date_id_meal_person_id_key;
+
+\echo "individual_meal_photo table, replacing individual_meal meal_photo_id"
+CREATE TABLE IF NOT EXISTS individual_meal_photo (
+	individual_meal_id BIGINT NOT NULL REFERENCES individual_meal(individual_meal_id) ON DELETE CASCADE,
+	meal_photo_id BIGINT NOT NULL REFERENCES meal_photo(meal_photo_id),
+	position SMALLINT NOT NULL CHECK (position >= 0),
+	caption TEXT CHECK (caption = trim(caption) AND char_length(caption) BETWEEN 1 AND 256),
+	PRIMARY KEY (individual_meal_id, position),
+	UNIQUE (individual_meal_id, meal_photo_id)
+);
+
+GRANT ALL ON individual_meal_photo TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_photo_meal_photo_id ON individual_meal_photo(meal_photo_id);
+
+-- The backfill can only run while individual_meal still has its meal_photo_id column
+DO $$
+BEGIN
+	IF EXISTS (
+		SELECT 1
+		FROM information_schema.columns
+		WHERE table_name = 'individual_meal' AND column_name = 'meal_photo_id'
+	) THEN
+		INSERT INTO individual_meal_photo(individual_meal_id, meal_photo_id, position)
+		SELECT individual_meal_id, meal_photo_id, 0 FROM individual_meal WHERE meal_photo_id IS NOT NULL
+		ON CONFLICT DO NOTHING;
+		ALTER TABLE individual_meal
+		DROP COLUMN meal_photo_id;
+	END IF;
+END $$;
+
+\echo "meal_photo remove unused photos"
+DELETE FROM meal_photo mp
+WHERE NOT EXISTS (
+	SELECT *
+	FROM individual_meal_photo imp
+	WHERE imp.meal_photo_id = mp.meal_photo_id
+);
+
+\echo "meal_venue table, and individual_meal add meal_venue_id"
+CREATE TABLE IF NOT EXISTS meal_venue (
+	meal_venue_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	name TEXT NOT NULL CHECK (name = trim(name) AND char_length(name) BETWEEN 1 AND 64),
+	city TEXT NOT NULL CHECK (city = trim(city) AND char_length(city) BETWEEN 1 AND 64),
+	venue_type TEXT NOT NULL CHECK (venue_type IN ('restaurant', 'takeaway', 'cafe', 'pub', 'other')),
+	latitude DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90),
+	longitude DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180),
+	registered_user_id BIGINT NOT NULL REFERENCES registered_user(registered_user_id),
+	timestamp TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+	CHECK ((latitude IS NULL) = (longitude IS NULL)),
+	UNIQUE (name, city)
+);
+
+GRANT ALL ON meal_venue TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE meal_venue_meal_venue_id_seq TO mealpedant;
+
+ALTER TABLE individual_meal
+ADD COLUMN IF NOT EXISTS meal_venue_id BIGINT REFERENCES meal_venue(meal_venue_id);
+
+CREATE INDEX IF NOT EXISTS individual_meal_meal_venue_id ON individual_meal(meal_venue_id);
+
+\echo "individual_meal add deleted_at, slot unique only for non-deleted meals"
+ALTER TABLE individual_meal
+ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+ALTER TABLE individual_meal
+DROP CONSTRAINT IF EXISTS individual_meal_meal_date_id_meal_person_id_meal_slot_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS individual_meal_meal_date_id_meal_person_id_meal_slot_key ON individual_meal(meal_date_id, meal_person_id, meal_slot) WHERE deleted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS individual_meal_deleted_at ON individual_meal(deleted_at) WHERE deleted_at IS NOT NULL;
+
+\echo "individual_meal_audit index on individual_meal_id"
+CREATE INDEX IF NOT EXISTS individual_meal_audit_individual_meal_id ON individual_meal_audit(((new_values->>'individual_meal_id')::BIGINT));
+
+\echo "individual_meal_photo_audit table, and trigger"
+CREATE TABLE IF NOT EXISTS individual_meal_photo_audit (
+	individual_meal_photo_audit_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+	table_name TEXT NOT NULL,
+	user_name TEXT,
+	action_timestamp TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	action TEXT NOT NULL CHECK (action IN ('i', 'd', 'u')),
+	old_values jsonb,
+	new_values jsonb,
+	difference jsonb,
+	query TEXT
+);
+
+GRANT ALL ON individual_meal_photo_audit TO mealpedant;
+
+GRANT USAGE, SELECT ON SEQUENCE individual_meal_photo_audit_individual_meal_photo_audit_id_seq TO mealpedant;
+
+CREATE INDEX IF NOT EXISTS individual_meal_photo_audit_individual_meal_id ON individual_meal_photo_audit(((COALESCE(new_values, old_values)->>'individual_meal_id')::BIGINT));
+
+-- Record the current photos of every meal, as of the meal's latest revision, only if nothing has been audited yet
+INSERT INTO individual_meal_photo_audit (table_name, user_name, action_timestamp, action, new_values, query)
+SELECT
+	'individual_meal_photo',
+	current_user,
+	COALESCE((
+		SELECT MAX(ima.action_timestamp)
+		FROM individual_meal_audit ima
+		WHERE (ima.new_values->>'individual_meal_id')::BIGINT = imp.individual_meal_id
+	), CURRENT_TIMESTAMP),
+	'i',
+	to_jsonb(imp),
+	'migration'
+FROM
+	individual_meal_photo imp
+WHERE
+	NOT EXISTS (SELECT 1 FROM individual_meal_photo_audit);
+
+CREATE OR REPLACE FUNCTION individual_meal_photo_modify_func() RETURNS TRIGGER AS $body$ BEGIN IF tg_op = 'UPDATE' THEN
+INSERT into
+	individual_meal_photo_audit (
+		table_name,
+		user_name,
+		action,
+		old_values,
+		new_values,
+		difference,
+		query
+	)
+VALUES
+	(
+		tg_table_name :: TEXT,
+		current_user,
+		'u',
+		to_jsonb(OLD),
+		to_jsonb(NEW),
+		to_jsonb(OLD) - to_jsonb(NEW),
+		current_query()
+	);
+
+RETURN new;
+
+ELSIF tg_op = 'DELETE' THEN
+INSERT into
+	individual_meal_photo_audit (
+		table_name,
+		user_name,
+		action,
+		old_values,
+		query
+	)
+VALUES
+	(
+		tg_table_name :: TEXT,
+		current_user,
+		'd',
+		to_jsonb(OLD),
+		current_query()
+	);
+
+RETURN old;
+
+ELSIF tg_op = 'INSERT' THEN
+INSERT into
+	individual_meal_photo_audit (table_name, user_name, action, new_values, query)
+VALUES
+	(
+		tg_table_name :: TEXT,
+		current_user,
+		'i',
+		to_jsonb(NEW),
+		current_query()
+	);
+
+RETURN new;
+
+END IF;
+
+END;
+
+$body$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER individual_meal_photo_audit_trig BEFORE
+INSERT
+	OR
+UPDATE
+	OR DELETE ON individual_meal_photo FOR EACH ROW EXECUTE PROCEDURE individual_meal_photo_modify_func();
+
+\echo "meal_person add visibility_audit_id"
+ALTER TABLE meal_person
+ADD COLUMN IF NOT EXISTS visibility_audit_id BIGINT;
+
+GRANT USAGE, SELECT ON SEQUENCE individual_meal_audit_individual_meal_audit_id_seq TO mealpedant;
@@ -334,11 +334,11 @@ lh.session_name = $3";
     }
 
     impl ActivePhoto {
-        /// Check if a given image name is currently attached to any individual meal
+        /// Check if a given image name is currently in the photos of any individual meal
         pub async fn in_use(postgres: &PgPool, photoname: &PhotoName) -> Result<bool, ApiError> {
             Ok(sqlx::query!(
-                "SELECT individual_meal_id FROM individual_meal im
-                LEFT JOIN meal_photo mp USING (meal_photo_id)
+                "SELECT imp.individual_meal_id FROM individual_meal_photo imp
+                JOIN meal_photo mp USING (meal_photo_id)
                 WHERE mp.photo_converted = $1 OR mp.photo_original = $1",
                 photoname.to_string()
             )
@@ -347,6 +347,7 @@ lh.session_name = $3";
             .is_some())
         }
 
+        /// Get every photo that is in use, a photo used by multiple meals is returned once per meal
         pub async fn get_all(postgres: &PgPool) -> Result<Vec<Self>, ApiError> {
             let query = "SELECT
     p.meal_photo_id,
@@ -357,7 +358,8 @@ lh.session_name = $3";
     md.date_of_meal AS meal_date
 FROM
     meal_photo p
-JOIN individual_meal im USING(meal_photo_id)
+JOIN individual_meal_photo imp USING(meal_photo_id)
+JOIN individual_meal im USING(individual_meal_id)
 JOIN
     meal_person mp
 ON
@@ -366,7 +368,7 @@ JOIN
     meal_date md
 ON
     im.meal_date_id = md.meal_date_id
-ORDER BY md.date_of_meal DESC";
+ORDER BY md.date_of_meal DESC, imp.position";
             Ok(sqlx::query_as::<_, Self>(query).fetch_all(postgres).await?)
         }
     }
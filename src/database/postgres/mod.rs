@@ -1,25 +1,48 @@
 mod admin;
 mod model_banned_email;
+mod model_category;
+mod model_description;
 mod model_food;
 mod model_ip_user_agent;
 mod model_login;
 mod model_meal;
+mod model_meal_revision;
+mod model_orphan;
+mod model_person;
 mod model_reset_password;
+mod model_stats;
+mod model_suggestion;
+mod model_tag;
 mod model_twofa;
 mod model_user;
+mod model_venue;
 
 use std::fmt;
 
 pub use admin::admin_queries;
 pub use model_banned_email::ModelBannedEmail;
-pub use model_food::{MealResponse, ModelDateMeal, ModelMissingFood};
+pub use model_category::ModelCategory;
+pub use model_description::{DESCRIPTION_SIMILARITY, ModelDescription};
+pub use model_food::{
+    ChangeCursor, MealChanges, MealEncoding, MealResponse, ModelDateMeal, ModelMealReminder,
+    ModelMissingFood,
+};
 pub use model_ip_user_agent::ModelUserAgentIp;
 pub use model_login::ModelLogin;
-pub use model_meal::ModelMeal;
+pub use model_meal::{
+    ModelMeal, ModelMealPhoto, ModelMealSearch, ModelMealTrash, SEARCH_PAGE_SIZE,
+};
+pub use model_meal_revision::ModelMealRevision;
+pub use model_orphan::ModelOrphans;
+pub use model_person::ModelPerson;
 pub use model_reset_password::ModelPasswordReset;
+pub use model_stats::StatsResponse;
+pub use model_suggestion::SuggestResponse;
+pub use model_tag::ModelTag;
 pub use model_twofa::{ModelTwoFA, ModelTwoFABackup};
 pub use model_user::ModelUser;
-use serde::{Deserialize, Serialize};
+pub use model_venue::{ModelVenue, ModelVenueVisits};
+use serde::Serialize;
 
 #[cfg(test)]
 pub use model_ip_user_agent::ReqUserAgentIp;
@@ -32,29 +55,149 @@ pub trait FromModel<T> {
     fn from_model(t: T) -> Result<Self::Item, ApiError>;
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
-pub enum Person {
-    Dave,
-    Jack,
-}
+/// The name of a person, only constructable if the person exists in the meal_person registry
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Person(String);
 
 impl fmt::Display for Person {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&str> for Person {
+    type Error = ApiError;
+    fn try_from(x: &str) -> Result<Self, ApiError> {
+        ModelPerson::from_name(x).map_or_else(
+            || Err(ApiError::Internal(S!("from person"))),
+            |person| Ok(Self(person.person)),
+        )
+    }
+}
+
+/// Who is able to see a person, or a single meal, ordered from least to most restrictive
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Public,
+    Authenticated,
+    Admin,
+}
+
+impl fmt::Display for Visibility {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let disp = match self {
-            Self::Dave => "Dave",
-            Self::Jack => "Jack",
+            Self::Public => "public",
+            Self::Authenticated => "authenticated",
+            Self::Admin => "admin",
         };
         write!(f, "{disp}")
     }
 }
 
-impl TryFrom<&str> for Person {
+impl TryFrom<&str> for Visibility {
+    type Error = ApiError;
+    fn try_from(x: &str) -> Result<Self, ApiError> {
+        match x {
+            "public" => Ok(Self::Public),
+            "authenticated" => Ok(Self::Authenticated),
+            "admin" => Ok(Self::Admin),
+            _ => Err(ApiError::Internal(S!("from visibility"))),
+        }
+    }
+}
+
+impl From<Option<&ModelUser>> for Visibility {
+    /// The visibility level of a client, based on their authentication status
+    fn from(user: Option<&ModelUser>) -> Self {
+        match user {
+            Some(user) if user.admin => Self::Admin,
+            Some(_) => Self::Authenticated,
+            None => Self::Public,
+        }
+    }
+}
+
+impl Visibility {
+    pub const ALL: [Self; 3] = [Self::Public, Self::Authenticated, Self::Admin];
+
+    /// All the visibilities that a client with this visibility level is able to see, as strings for use in postgres queries
+    pub fn allowed(self) -> Vec<String> {
+        Self::ALL
+            .into_iter()
+            .filter(|i| i <= &self)
+            .map(|i| i.to_string())
+            .collect()
+    }
+}
+
+/// Which meal of the day, a person can have one meal per slot per date
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum MealSlot {
+    Breakfast,
+    Lunch,
+    #[default]
+    Dinner,
+}
+
+impl fmt::Display for MealSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let disp = match self {
+            Self::Breakfast => "breakfast",
+            Self::Lunch => "lunch",
+            Self::Dinner => "dinner",
+        };
+        write!(f, "{disp}")
+    }
+}
+
+impl TryFrom<&str> for MealSlot {
+    type Error = ApiError;
+    fn try_from(x: &str) -> Result<Self, ApiError> {
+        match x {
+            "breakfast" => Ok(Self::Breakfast),
+            "lunch" => Ok(Self::Lunch),
+            "dinner" => Ok(Self::Dinner),
+            _ => Err(ApiError::Internal(S!("from meal slot"))),
+        }
+    }
+}
+
+/// The kind of place a venue is
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum VenueType {
+    Restaurant,
+    Takeaway,
+    Cafe,
+    Pub,
+    Other,
+}
+
+impl fmt::Display for VenueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let disp = match self {
+            Self::Restaurant => "restaurant",
+            Self::Takeaway => "takeaway",
+            Self::Cafe => "cafe",
+            Self::Pub => "pub",
+            Self::Other => "other",
+        };
+        write!(f, "{disp}")
+    }
+}
+
+impl TryFrom<&str> for VenueType {
     type Error = ApiError;
     fn try_from(x: &str) -> Result<Self, ApiError> {
         match x {
-            "Dave" => Ok(Self::Dave),
-            "Jack" => Ok(Self::Jack),
-            _ => Err(ApiError::Internal(S!("from person"))),
+            "restaurant" => Ok(Self::Restaurant),
+            "takeaway" => Ok(Self::Takeaway),
+            "cafe" => Ok(Self::Cafe),
+            "pub" => Ok(Self::Pub),
+            "other" => Ok(Self::Other),
+            _ => Err(ApiError::Internal(S!("from venue type"))),
         }
     }
 }
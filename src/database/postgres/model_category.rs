@@ -0,0 +1,141 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::api_error::ApiError;
+
+use super::ModelUser;
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelCategory {
+    pub meal_category_id: i64,
+    pub category: String,
+    /// Number of meals using the category
+    pub meals: i64,
+}
+
+impl ModelCategory {
+    /// Get every category, and the number of meals using it, ordered by category
+    pub async fn get_all(postgres: &PgPool) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mc.meal_category_id,
+    mc.category,
+    COUNT(im.individual_meal_id) AS "meals!"
+FROM
+    meal_category mc
+LEFT JOIN
+    individual_meal im USING(meal_category_id)
+GROUP BY
+    mc.meal_category_id
+ORDER BY
+    mc.category"#
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// Get a single category, category names are always uppercase
+    pub async fn get(postgres: &PgPool, category: &str) -> Result<Option<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mc.meal_category_id,
+    mc.category,
+    COUNT(im.individual_meal_id) AS "meals!"
+FROM
+    meal_category mc
+LEFT JOIN
+    individual_meal im USING(meal_category_id)
+WHERE
+    mc.category = upper($1)
+GROUP BY
+    mc.meal_category_id"#,
+            category
+        )
+        .fetch_optional(postgres)
+        .await?)
+    }
+
+    /// Record a rename or merge in the meal_category_change table
+    async fn insert_change(
+        transaction: &mut Transaction<'_, Postgres>,
+        action: &str,
+        from: &Self,
+        to_category: &str,
+        meal_count: i64,
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "
+INSERT INTO
+    meal_category_change(action, from_category, to_category, meal_count, registered_user_id)
+VALUES
+    ($1, $2, upper($3), $4, $5)",
+            action,
+            from.category,
+            to_category,
+            meal_count,
+            user.registered_user_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+        Ok(())
+    }
+
+    /// Rename a category, every meal using it is updated, as meals only reference the category id
+    pub async fn rename(
+        postgres: &PgPool,
+        from: &Self,
+        category: &str,
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        let mut transaction = postgres.begin().await?;
+        sqlx::query!(
+            "UPDATE meal_category SET category = upper($1) WHERE meal_category_id = $2",
+            category,
+            from.meal_category_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        Self::insert_change(&mut transaction, "rename", from, category, from.meals, user).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Move every meal from one category into another, and then remove the now unused category
+    pub async fn merge(
+        postgres: &PgPool,
+        from: &Self,
+        into: &Self,
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        let mut transaction = postgres.begin().await?;
+        let moved = sqlx::query!(
+            "UPDATE individual_meal SET meal_category_id = $1 WHERE meal_category_id = $2",
+            into.meal_category_id,
+            from.meal_category_id
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        sqlx::query!(
+            "DELETE FROM meal_category WHERE meal_category_id = $1",
+            from.meal_category_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        Self::insert_change(
+            &mut transaction,
+            "merge",
+            from,
+            &into.category,
+            i64::try_from(moved).unwrap_or(i64::MAX),
+            user,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
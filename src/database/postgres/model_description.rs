@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+
+use sqlx::PgPool;
+
+use crate::api_error::ApiError;
+
+use super::ModelUser;
+
+/// Default pg_trgm similarity threshold used to find similar descriptions
+pub const DESCRIPTION_SIMILARITY: f32 = 0.6;
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelDescription {
+    pub meal_description_id: i64,
+    pub description: String,
+    /// Number of meals using the description
+    pub meals: i64,
+}
+
+/// Two descriptions with a trigram similarity above the requested threshold
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+struct SimilarPair {
+    a: i64,
+    b: i64,
+}
+
+/// Find the root of a description in the union-find parent map, compressing the path as it goes
+fn find_root(parent: &mut HashMap<i64, i64>, id: i64) -> i64 {
+    let mut root = id;
+    while let Some(&next) = parent.get(&root)
+        && next != root
+    {
+        root = next;
+    }
+    parent.insert(id, root);
+    root
+}
+
+/// Group descriptions into clusters, any two descriptions connected by a chain of similar pairs are in the same cluster
+/// Clusters are ordered by number of descriptions, then alphabetically, and each cluster is ordered by most used
+fn cluster(
+    descriptions: Vec<ModelDescription>,
+    pairs: &[SimilarPair],
+) -> Vec<Vec<ModelDescription>> {
+    let mut parent = HashMap::new();
+    for pair in pairs {
+        let (a, b) = (
+            find_root(&mut parent, pair.a),
+            find_root(&mut parent, pair.b),
+        );
+        if a != b {
+            parent.insert(a.max(b), a.min(b));
+        }
+    }
+
+    let mut clusters: BTreeMap<i64, Vec<ModelDescription>> = BTreeMap::new();
+    for description in descriptions {
+        if parent.contains_key(&description.meal_description_id) {
+            let root = find_root(&mut parent, description.meal_description_id);
+            clusters.entry(root).or_default().push(description);
+        }
+    }
+    let mut clusters = clusters
+        .into_values()
+        .filter(|i| i.len() > 1)
+        .map(|mut i| {
+            i.sort_by(|a, b| {
+                b.meals
+                    .cmp(&a.meals)
+                    .then_with(|| a.description.cmp(&b.description))
+            });
+            i
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by(|a, b| {
+        b.len()
+            .cmp(&a.len())
+            .then_with(|| a[0].description.cmp(&b[0].description))
+    });
+    clusters
+}
+
+impl ModelDescription {
+    /// Get descriptions, and the number of meals using them, by exact description
+    pub async fn get_many(
+        postgres: &PgPool,
+        descriptions: &[String],
+    ) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mde.meal_description_id,
+    mde.description AS "description!",
+    COUNT(im.individual_meal_id) AS "meals!"
+FROM
+    meal_description mde
+LEFT JOIN
+    individual_meal im USING(meal_description_id)
+WHERE
+    mde.description = ANY($1)
+GROUP BY
+    mde.meal_description_id"#,
+            descriptions
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// Find clusters of similar descriptions, using pg_trgm similarity, threshold is between 0 and 1
+    pub async fn similar(postgres: &PgPool, threshold: f32) -> Result<Vec<Vec<Self>>, ApiError> {
+        let mut transaction = postgres.begin().await?;
+        // Set for this transaction only, so the % operator, and therefore the trigram index, can be used
+        sqlx::query_scalar!(
+            "SELECT set_config('pg_trgm.similarity_threshold', $1::TEXT, true)",
+            threshold.to_string()
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let pairs = sqlx::query_as!(
+            SimilarPair,
+            r#"
+SELECT
+    a.meal_description_id AS "a!",
+    b.meal_description_id AS "b!"
+FROM
+    meal_description a
+JOIN
+    meal_description b ON a.description % b.description
+    AND a.meal_description_id < b.meal_description_id"#
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let descriptions = sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mde.meal_description_id,
+    mde.description AS "description!",
+    COUNT(im.individual_meal_id) AS "meals!"
+FROM
+    meal_description mde
+LEFT JOIN
+    individual_meal im USING(meal_description_id)
+WHERE
+    mde.meal_description_id = ANY($1)
+GROUP BY
+    mde.meal_description_id"#,
+            &pairs.iter().flat_map(|i| [i.a, i.b]).collect::<Vec<_>>()
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(cluster(descriptions, &pairs))
+    }
+
+    /// Point every meal using any of the given descriptions to the canonical description, creating it if needed, then remove the now unused descriptions
+    pub async fn merge(
+        postgres: &PgPool,
+        canonical: &str,
+        descriptions: &[Self],
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        let mut transaction = postgres.begin().await?;
+        let canonical_id = if let Some(id) = sqlx::query_scalar!(
+            "SELECT meal_description_id FROM meal_description WHERE description = $1",
+            canonical
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        {
+            id
+        } else {
+            sqlx::query_scalar!(
+                "INSERT INTO meal_description(description, registered_user_id) VALUES($1, $2) RETURNING meal_description_id",
+                canonical,
+                user.registered_user_id
+            )
+            .fetch_one(&mut *transaction)
+            .await?
+        };
+
+        let ids = descriptions
+            .iter()
+            .map(|i| i.meal_description_id)
+            .filter(|i| *i != canonical_id)
+            .collect::<Vec<_>>();
+        sqlx::query!(
+            "UPDATE individual_meal SET meal_description_id = $1 WHERE meal_description_id = ANY($2)",
+            canonical_id,
+            &ids
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM meal_description WHERE meal_description_id = ANY($1)",
+            &ids
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+/// cargo watch -q -c -w src/ -x 'test db_postgres_model_description -- --test-threads=1 --nocapture'
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(id: i64, description: &str, meals: i64) -> ModelDescription {
+        ModelDescription {
+            meal_description_id: id,
+            description: description.to_owned(),
+            meals,
+        }
+    }
+
+    #[test]
+    fn db_postgres_model_description_cluster() {
+        let descriptions = vec![
+            description(1, "Chicken curry", 4),
+            description(2, "chicken curry ", 1),
+            description(3, "Chicken curri", 2),
+            description(4, "Beans on toast", 1),
+            description(5, "Beans on toast.", 3),
+            description(6, "Pasta", 1),
+        ];
+        // 1-2 and 2-3 chain into a single cluster
+        let pairs = [
+            SimilarPair { a: 2, b: 3 },
+            SimilarPair { a: 4, b: 5 },
+            SimilarPair { a: 1, b: 2 },
+        ];
+        let result = cluster(descriptions, &pairs);
+
+        assert_eq!(result.len(), 2);
+        let ids =
+            |i: &Vec<ModelDescription>| i.iter().map(|i| i.meal_description_id).collect::<Vec<_>>();
+        assert_eq!(ids(&result[0]), vec![1, 3, 2]);
+        assert_eq!(ids(&result[1]), vec![5, 4]);
+        assert!(cluster(vec![description(6, "Pasta", 1)], &[]).is_empty());
+    }
+}
@@ -1,23 +1,38 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    io::Write,
+};
 
 use blake3::Hash;
+use bytes::Bytes;
 use fred::{
     clients::Pool,
-    interfaces::{HashesInterface, KeysInterface},
+    interfaces::{HashesInterface, KeysInterface, LuaInterface},
+    types::{Expiration, SetOptions},
 };
 use jiff_sqlx::ToSqlx;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgPool, types::Json};
+use tracing::error;
+use ulid::Ulid;
 
 use crate::{
-    S,
+    C, S,
     api_error::ApiError,
-    database::redis::{HASH_FIELD, RedisKey},
+    database::redis::RedisKey,
     helpers::genesis_date,
-    hmap,
-    servers::oj::{DateMeal, MealInfo, MissingFood, none_or_zero},
+    servers::{
+        BodyFormat, ij,
+        oj::{
+            self, DateMeal, MealCounts, MealInfo, MissingFood, PersonMap, PersonMeal, none_or_zero,
+        },
+    },
+    sleep,
 };
 
+use super::{ModelMeal, ModelMealPhoto, ModelPerson, Visibility};
+
 #[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
 pub struct MealDescription {
     #[serde(rename = "i")]
@@ -28,35 +43,14 @@ pub struct MealDescription {
 
 impl MealDescription {
     /// Get all the meal descriptions as a hashmap, with the id as a key
-    /// If both is Some(()), search for meal descriptions from both Jack and Dave, else just Jack
+    /// Only search meals that are visible at the given visibility level
     pub async fn get(
         postgres: &PgPool,
-        both: Option<()>,
+        visibility: Visibility,
     ) -> Result<HashMap<i64, String>, ApiError> {
-        let query = match both {
-            Some(()) => {
-                sqlx::query_as!(
-                    Self,
-                    r#"
-SELECT DISTINCT
-    md.meal_description_id,
-    md.description AS "description!"
-FROM
-    meal_description md
-JOIN
-    individual_meal im USING(meal_description_id)
-JOIN
-    meal_person mpe USING(meal_person_id)
-ORDER BY
-    md.meal_description_id DESC"#
-                )
-                .fetch_all(postgres)
-                .await
-            }
-            None => {
-                sqlx::query_as!(
-                    Self,
-                    r#"
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
 SELECT DISTINCT
     md.meal_description_id,
     md.description AS "description!"
@@ -67,19 +61,17 @@ JOIN
 JOIN
     meal_person mpe USING(meal_person_id)
 WHERE
-    mpe.person = 'Jack'
+    COALESCE(im.visibility, mpe.visibility) = ANY($1)
+    AND im.deleted_at IS NULL
 ORDER BY
-    md.meal_description_id DESC"#
-                )
-                .fetch_all(postgres)
-                .await
-            }
-        };
-
-        Ok(query?
-            .into_iter()
-            .map(|i| (i.meal_description_id, i.description))
-            .collect::<HashMap<i64, String>>())
+    md.meal_description_id DESC"#,
+            &visibility.allowed()
+        )
+        .fetch_all(postgres)
+        .await?
+        .into_iter()
+        .map(|i| (i.meal_description_id, i.description))
+        .collect::<HashMap<i64, String>>())
     }
 }
 
@@ -92,17 +84,15 @@ pub struct MealCategory {
 }
 
 impl MealCategory {
-    /// Get all the meal categories as a hashmap, with the id as a key, and (name,count) as value
-    /// If both is Some(()), search for categories from both Jack and Dave, else just Jack
+    /// Get all the meal categories as a hashmap, with the id as a key, and name as value, the per person counts are built from the date meals
+    /// Only search meals that are visible at the given visibility level
     pub async fn get(
         postgres: &PgPool,
-        both: Option<()>,
+        visibility: Visibility,
     ) -> Result<HashMap<i64, String>, ApiError> {
-        let query = match both {
-            Some(()) => {
-                sqlx::query_as!(
-                    Self,
-                    r#"
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
 SELECT DISTINCT
     im.meal_category_id AS category_id,
     mc.category AS category
@@ -112,38 +102,58 @@ JOIN
     meal_category mc USING(meal_category_id)
 JOIN
     meal_person mpe USING(meal_person_id)
+WHERE
+    COALESCE(im.visibility, mpe.visibility) = ANY($1)
+    AND im.deleted_at IS NULL
 ORDER BY
-    category DESC"#
-                )
-                .fetch_all(postgres)
-                .await?
-            }
-            None => {
-                sqlx::query_as!(
-                    Self,
-                    r#"
+    category DESC"#,
+            &visibility.allowed()
+        )
+        .fetch_all(postgres)
+        .await?
+        .into_iter()
+        .map(|i| (i.category_id, i.category))
+        .collect::<HashMap<_, _>>())
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
+pub struct MealTag {
+    meal_tag_id: i64,
+    tag: String,
+}
+
+impl MealTag {
+    /// Get all the meal tags as a hashmap, with the id as a key
+    /// Only search meals that are visible at the given visibility level
+    pub async fn get(
+        postgres: &PgPool,
+        visibility: Visibility,
+    ) -> Result<HashMap<i64, String>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
 SELECT DISTINCT
-    im.meal_category_id AS category_id,
-    mc.category AS category
+    mt.meal_tag_id,
+    mt.tag
 FROM
-    individual_meal im
+    meal_tag mt
 JOIN
-    meal_category mc USING(meal_category_id)
+    individual_meal_tag imt USING(meal_tag_id)
+JOIN
+    individual_meal im USING(individual_meal_id)
 JOIN
     meal_person mpe USING(meal_person_id)
 WHERE
-    mpe.person = 'Jack'
-ORDER BY
-    category DESC"#
-                )
-                .fetch_all(postgres)
-                .await?
-            }
-        };
-        Ok(query
-            .into_iter()
-            .map(|i| (i.category_id, i.category))
-            .collect::<HashMap<_, _>>())
+    COALESCE(im.visibility, mpe.visibility) = ANY($1)
+    AND im.deleted_at IS NULL"#,
+            &visibility.allowed()
+        )
+        .fetch_all(postgres)
+        .await?
+        .into_iter()
+        .map(|i| (i.meal_tag_id, i.tag))
+        .collect::<HashMap<_, _>>())
     }
 }
 
@@ -154,7 +164,9 @@ pub struct ModelDateMeal {
     #[serde(rename = "c")]
     pub meal_category_id: i64,
     #[serde(rename = "p")]
-    pub person: String,
+    pub initial: String,
+    #[serde(rename = "s")]
+    pub meal_slot: String,
     #[serde(rename = "r", skip_serializing_if = "none_or_zero")]
     pub restaurant: Option<i32>,
     #[serde(rename = "t", skip_serializing_if = "none_or_zero")]
@@ -163,60 +175,46 @@ pub struct ModelDateMeal {
     pub vegetarian: Option<i32>,
     #[serde(rename = "e")]
     pub meal_description_id: i64,
-    #[serde(rename = "o", skip_serializing_if = "Option::is_none")]
-    pub photo_original: Option<String>,
-    #[serde(rename = "n", skip_serializing_if = "Option::is_none")]
-    pub photo_converted: Option<String>,
+    /// Ordered by position
+    #[serde(rename = "h")]
+    pub photos: Json<Vec<ModelMealPhoto>>,
+    #[serde(rename = "g", default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<i64>,
 }
 
 impl ModelDateMeal {
-    /// Get all date meals
-    /// if both is Some(()), search for meals from both Jack and Dave, else just Jack
+    /// Get all date meals that are visible at the given visibility level, original photos are never public
+    /// Optionally only get the meals for the given dates, yyyy-mm-dd
     /// the "x?" is a temporary fix due to a bug in the the sqlx query_as! macrock
-    pub async fn get_all(postgres: &PgPool, both: Option<()>) -> Result<Vec<Self>, ApiError> {
-        match both {
-            Some(()) => Ok(sqlx::query_as!(
-                Self,
-                r#"
-SELECT
-    md.date_of_meal::text AS "date_of_meal!",
-    im.meal_category_id,
-    mpe.person as person,
-    im.restaurant::INT,
-    im.takeaway::INT,
-    im.vegetarian::INT,
-    mde.meal_description_id,
-    mp.photo_converted AS "photo_converted?",
-    mp.photo_original AS "photo_original?"
-FROM
-    individual_meal im
-JOIN
-    meal_date md USING(meal_date_id)
-JOIN
-    meal_description mde USING(meal_description_id)
-JOIN
-    meal_person mpe USING(meal_person_id)
-LEFT JOIN
-    meal_photo mp USING(meal_photo_id)
-ORDER BY
-    date_of_meal DESC,
-    person"#
-            )
-            .fetch_all(postgres)
-            .await?),
-            None => Ok(sqlx::query_as!(
-                Self,
-                r#"
+    pub async fn get_all(
+        postgres: &PgPool,
+        visibility: Visibility,
+        dates: Option<&[String]>,
+    ) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
 SELECT
     md.date_of_meal::text AS "date_of_meal!",
     im.meal_category_id,
-    mpe.person as person,
+    mpe.initial,
+    im.meal_slot,
     im.restaurant::INT,
     im.takeaway::INT,
     im.vegetarian::INT,
     mde.meal_description_id,
-    mp.photo_converted AS "photo_converted?",
-    NULL AS "photo_original?"
+    COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'meal_photo_id', mp.meal_photo_id,
+            'photo_original', CASE WHEN $2 THEN mp.photo_original END,
+            'photo_converted', mp.photo_converted,
+            'caption', imp.caption
+        ) ORDER BY imp.position)
+        FROM individual_meal_photo imp
+        JOIN meal_photo mp USING(meal_photo_id)
+        WHERE imp.individual_meal_id = im.individual_meal_id
+    ), '[]') AS "photos!: Json<Vec<ModelMealPhoto>>",
+    ARRAY(SELECT imt.meal_tag_id FROM individual_meal_tag imt WHERE imt.individual_meal_id = im.individual_meal_id ORDER BY imt.meal_tag_id) AS "tags!"
 FROM
     individual_meal im
 JOIN
@@ -225,78 +223,270 @@ JOIN
     meal_description mde USING(meal_description_id)
 JOIN
     meal_person mpe USING(meal_person_id)
-LEFT JOIN
-    meal_photo mp USING(meal_photo_id)
 WHERE
-    person = 'Jack'
+    COALESCE(im.visibility, mpe.visibility) = ANY($1)
+    AND im.deleted_at IS NULL
+    AND ($3::TEXT[] IS NULL OR md.date_of_meal::TEXT = ANY($3))
 ORDER BY
-    date_of_meal DESC"#
-            )
-            .fetch_all(postgres)
-            .await?),
+    date_of_meal DESC,
+    initial"#,
+            &visibility.allowed(),
+            visibility > Visibility::Public,
+            dates
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+}
+
+/// Redis hash field of the meals cache ETag, stored alongside the meals themselves
+const ETAG_FIELD: &str = "etag";
+
+/// How long a cache rebuild may hold the lock, before another request is allowed to take over
+const REBUILD_LOCK_MS: i64 = 30_000;
+/// How often a request waiting on another request's rebuild checks for the new cache
+const REBUILD_POLL_MS: u64 = 25;
+/// Delete the rebuild lock only if it still holds the given token, the check and delete must be atomic, else the lock may expire, and be taken by another caller, in between
+const REBUILD_UNLOCK_SCRIPT: &str = "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('del', KEYS[1]) else return 0 end";
+/// How long to wait, after a cache delete, before re-warming the meals cache, so a burst of edits only triggers a single warm
+const WARM_DEBOUNCE_MS: u64 = 250;
+
+/// Brotli quality and window size, the meals are only compressed when the cache is rebuilt, so favour size over speed
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+/// The encodings that the meals response body is cached in, json in each content encoding, or one of the binary formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MealEncoding {
+    Identity,
+    Brotli,
+    Gzip,
+    MessagePack,
+    Cbor,
+}
+
+impl MealEncoding {
+    const ALL: [Self; 5] = [
+        Self::Identity,
+        Self::Brotli,
+        Self::Gzip,
+        Self::MessagePack,
+        Self::Cbor,
+    ];
+
+    /// The name of the encoding, as used by the Accept-Encoding header, and as the ETag suffix
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::MessagePack => "msgpack",
+            Self::Cbor => "cbor",
+        }
+    }
+
+    /// The Content-Encoding header value, None if the body isn't compressed
+    pub const fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Self::Brotli | Self::Gzip => Some(self.name()),
+            Self::Identity | Self::MessagePack | Self::Cbor => None,
+        }
+    }
+
+    /// The format of the body, before any compression
+    pub const fn format(self) -> BodyFormat {
+        match self {
+            Self::Identity | Self::Brotli | Self::Gzip => BodyFormat::Json,
+            Self::MessagePack => BodyFormat::MessagePack,
+            Self::Cbor => BodyFormat::Cbor,
+        }
+    }
+
+    /// The redis hash field of the meals cache that holds the body in this encoding
+    const fn field(self) -> &'static str {
+        match self {
+            Self::Identity => "json",
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::MessagePack => "msgpack",
+            Self::Cbor => "cbor",
+        }
+    }
+
+    /// Encode the serialized response body
+    fn encode(self, json: &[u8]) -> Result<Bytes, ApiError> {
+        match self {
+            Self::Identity => Ok(Bytes::copy_from_slice(json)),
+            Self::Brotli => {
+                let mut writer =
+                    brotli::CompressorWriter::new(vec![], 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+                writer.write_all(json)?;
+                Ok(Bytes::from(writer.into_inner()))
+            }
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+                encoder.write_all(json)?;
+                Ok(Bytes::from(encoder.finish()?))
+            }
+            Self::MessagePack | Self::Cbor => Ok(Bytes::from(self.format().transcode(json)?)),
         }
     }
 }
 
-#[allow(non_snake_case)]
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub struct MealResponse {
-    #[serde(rename = "d")]
-    meal_descriptions: HashMap<i64, String>,
-    #[serde(rename = "c")]
-    meal_categories: HashMap<i64, (String, i64)>,
-    #[serde(rename = "m")]
-    meals: Vec<ModelDateMeal>,
+/// The meals response body, already serialized, and encoded, as it's stored in the cache
+#[derive(Debug, Clone)]
+pub struct MealBody {
+    pub body: Bytes,
+    pub etag: String,
 }
 
+/// The cache, and database queries, of the meals response, the response itself is `oj::MealInfo`
+pub struct MealResponse;
+
 impl MealResponse {
     /// Get the redis key for the meals data
-    fn key(both: Option<()>) -> String {
-        match both {
-            Some(()) => RedisKey::AllMeals.to_string(),
-            None => RedisKey::JackMeals.to_string(),
-        }
+    fn key(visibility: Visibility) -> String {
+        RedisKey::Meals(visibility).to_string()
     }
     /// Get the redis key for the meals_hash
-    fn key_hash(both: Option<()>) -> String {
-        match both {
-            Some(()) => RedisKey::AllMealsHash.to_string(),
-            None => RedisKey::JackMealsHash.to_string(),
-        }
+    fn key_hash(visibility: Visibility) -> String {
+        RedisKey::MealsHash(visibility).to_string()
     }
-    /// Delete the cache of the meals and the meals_hash
-    /// This deletes all caches for all_meals, jack_all_meals, and the hash associated with each
-    pub async fn cache_delete(redis: &Pool) -> Result<(), ApiError> {
-        Ok(redis
-            .del((
-                Self::key(Some(())),
-                Self::key_hash(Some(())),
-                Self::key(None),
-                Self::key_hash(None),
-            ))
-            .await?)
+    /// Get the redis key for the cache rebuild lock
+    fn key_lock(visibility: Visibility) -> String {
+        RedisKey::MealsLock(visibility).to_string()
+    }
+    /// Get the current cache generation, which is increased on every cache delete
+    pub async fn generation(redis: &Pool) -> Result<Option<i64>, ApiError> {
+        Ok(redis.get(RedisKey::MealsGeneration.to_string()).await?)
+    }
+    /// Delete the cache of the meals, the meals_hash, the meal stats, and the photo visibilities
+    /// This deletes the caches for every visibility level, and the hash associated with each, then re-warms the meals caches in the background
+    /// The generation is increased before the delete, so that a rebuild already in progress either fails its generation check, or has its insert removed by this delete
+    pub async fn cache_delete(postgres: &PgPool, redis: &Pool) -> Result<(), ApiError> {
+        let generation = redis
+            .incr::<i64, String>(RedisKey::MealsGeneration.to_string())
+            .await?;
+        redis
+            .del::<(), _>(
+                Visibility::ALL
+                    .into_iter()
+                    .flat_map(|i| {
+                        [
+                            Self::key(i),
+                            Self::key_hash(i),
+                            RedisKey::Stats(i).to_string(),
+                        ]
+                    })
+                    .chain([RedisKey::PhotoVisibility.to_string()])
+                    .collect::<Vec<_>>(),
+            )
+            .await?;
+        Self::cache_warm(C!(postgres), C!(redis), generation);
+        Ok(())
     }
 
-    /// Check redis for meal cache, and return if present
-    async fn cache_get(redis: &Pool, both: Option<()>) -> Result<Option<MealInfo>, ApiError> {
-        match redis
-            .hget::<Option<String>, String, &str>(Self::key(both), HASH_FIELD)
-            .await?
-        {
-            Some(r) => Ok(Some(serde_json::from_str(&r)?)),
-            None => Ok(None),
+    /// Rebuild the meals cache of every visibility level in a background task, so the next request doesn't have to
+    /// The warm is debounced, if the cache is deleted again before, or during, the warm, then the newer warm takes over
+    fn cache_warm(postgres: PgPool, redis: Pool, generation: i64) {
+        tokio::spawn(async move {
+            sleep!(WARM_DEBOUNCE_MS);
+            for visibility in Visibility::ALL {
+                match Self::generation(&redis).await {
+                    Ok(Some(current)) if current == generation => (),
+                    Ok(_) => return,
+                    Err(e) => {
+                        error!("meals cache warm: {visibility}: {e:?}");
+                        return;
+                    }
+                }
+                if let Err(e) =
+                    Self::cache_fill(&postgres, &redis, visibility, MealEncoding::Identity).await
+                {
+                    error!("meals cache warm: {visibility}: {e:?}");
+                }
+            }
+        });
+    }
+
+    /// Fill a missing cache, only one caller at a time, per visibility level, will query postgres, the others wait for its new cache
+    /// The lock expires, so a rebuild that never finishes can't block the cache forever
+    /// The date_meals hash is only returned if this caller rebuilt the cache
+    async fn cache_fill(
+        postgres: &PgPool,
+        redis: &Pool,
+        visibility: Visibility,
+        encoding: MealEncoding,
+    ) -> Result<(MealBody, Option<String>), ApiError> {
+        let key_lock = Self::key_lock(visibility);
+        let token = Ulid::new().to_string();
+        loop {
+            let locked = redis
+                .set::<Option<String>, &str, &str>(
+                    &key_lock,
+                    &token,
+                    Some(Expiration::PX(REBUILD_LOCK_MS)),
+                    Some(SetOptions::NX),
+                    false,
+                )
+                .await?
+                .is_some();
+            if locked {
+                // Another caller may have finished a rebuild between the cache miss, and taking the lock
+                let output = async {
+                    if let Some(cache) = Self::cache_get(redis, visibility, encoding).await? {
+                        return Ok((cache, None));
+                    }
+                    let generation = Self::generation(redis).await?;
+                    let (body, hash) =
+                        Self::cache_build(postgres, redis, visibility, encoding, generation)
+                            .await?;
+                    Ok((body, Some(hash)))
+                }
+                .await;
+                // The lock is released whether or not the rebuild succeeded, an error is returned in preference to an unlock error
+                let unlocked = redis
+                    .eval::<i64, _, _, _>(REBUILD_UNLOCK_SCRIPT, &key_lock, &token)
+                    .await;
+                return output.and_then(|output| unlocked.map(|_| output).map_err(ApiError::from));
+            }
+            sleep!(REBUILD_POLL_MS);
+            if let Some(cache) = Self::cache_get(redis, visibility, encoding).await? {
+                return Ok((cache, None));
+            }
         }
     }
 
-    /// Insert meals cache
+    /// Check redis for the meal cache in the given encoding, and return if present
+    async fn cache_get(
+        redis: &Pool,
+        visibility: Visibility,
+        encoding: MealEncoding,
+    ) -> Result<Option<MealBody>, ApiError> {
+        // A cache in an outdated format, without the encoded bodies or an ETag, is treated as missing, and will be replaced
+        let (body, etag) = redis
+            .hmget::<(Option<Bytes>, Option<String>), String, Vec<&str>>(
+                Self::key(visibility),
+                vec![encoding.field(), ETAG_FIELD],
+            )
+            .await?;
+        Ok(body.zip(etag).map(|(body, etag)| MealBody { body, etag }))
+    }
+
+    /// Insert every encoding of the meals response body, along with its ETag, in a single write so that they can't get out of sync
     async fn cache_insert(
         redis: &Pool,
-        meals: &MealInfo,
-        both: Option<()>,
+        bodies: &[(MealEncoding, Bytes)],
+        etag: &str,
+        visibility: Visibility,
     ) -> Result<(), ApiError> {
-        Ok(redis
-            .hset(Self::key(both), hmap!(serde_json::to_string(&meals)?))
-            .await?)
+        let mut map = bodies
+            .iter()
+            .map(|(encoding, body)| (encoding.field(), C!(body)))
+            .collect::<HashMap<_, _>>();
+        map.insert(ETAG_FIELD, Bytes::from(etag.to_owned()));
+        Ok(redis.hset(Self::key(visibility), map).await?)
     }
 
     /// Generate a hash for the meals.date_meals, the other entries are unordered hashmaps, whereas date_meals is ordered
@@ -311,72 +501,389 @@ impl MealResponse {
         )
     }
 
+    /// Generate the ETag of the meals, from the date_meals hash and the sorted id maps
+    /// A renamed category, description, tag, or person changes the response, but not the date_meals hash
+    fn etag_generate(meals: &MealInfo, hash: &str) -> Result<String, ApiError> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(hash.as_bytes());
+        for map in [
+            &meals.meal_descriptions,
+            &meals.meal_categories,
+            &meals.meal_tags,
+        ] {
+            hasher
+                .update(serde_json::to_string(&map.iter().collect::<BTreeMap<_, _>>())?.as_bytes());
+        }
+        hasher.update(
+            serde_json::to_string(&meals.people.iter().collect::<BTreeMap<_, _>>())?.as_bytes(),
+        );
+        Ok(hasher.finalize().to_string())
+    }
+
     /// Insert the meals.date_meals hash into redis
-    async fn hash_insert(
-        redis: &Pool,
-        meals: &MealInfo,
-        both: Option<()>,
-    ) -> Result<String, ApiError> {
-        let hash = Self::hash_generate(meals)?.to_string();
+    async fn hash_insert(redis: &Pool, hash: &str, visibility: Visibility) -> Result<(), ApiError> {
         redis
-            .set::<(), String, &str>(Self::key_hash(both), &hash, None, None, false)
+            .set::<(), String, &str>(Self::key_hash(visibility), hash, None, None, false)
             .await?;
-        Ok(hash)
+        Ok(())
     }
 
     /// Return the meals.date_meals hash if present
     pub async fn get_hash(
         postgres: &PgPool,
         redis: &Pool,
-        both: Option<()>,
+        visibility: Visibility,
     ) -> Result<String, ApiError> {
-        if let Some(x) = redis.get(Self::key_hash(both)).await? {
+        if let Some(x) = redis.get(Self::key_hash(visibility)).await? {
+            return Ok(x);
+        }
+        if let (_, Some(hash)) =
+            Self::cache_fill(postgres, redis, visibility, MealEncoding::Identity).await?
+        {
+            return Ok(hash);
+        }
+        // Another caller rebuilt the cache, so its hash will be in redis, unless the cache has since been deleted
+        if let Some(x) = redis.get(Self::key_hash(visibility)).await? {
             Ok(x)
-        } else if let Some(cache) = Self::cache_get(redis, both).await? {
-            Self::hash_insert(redis, &cache, both).await
         } else {
-            let data = Self::get_all(postgres, redis, both).await?;
-            Self::hash_insert(redis, &data, both).await
+            let generation = Self::generation(redis).await?;
+            Ok(Self::cache_build(
+                postgres,
+                redis,
+                visibility,
+                MealEncoding::Identity,
+                generation,
+            )
+            .await?
+            .1)
+        }
+    }
+
+    /// Get the visibility required to view a photo, None if the photo isn't used by any meal
+    /// Cached in a redis hash, keyed by file name, which is deleted along with the meals cache
+    /// Only photos used by a meal are cached, else any client could grow the hash by requesting made up names
+    pub async fn photo_visibility(
+        postgres: &PgPool,
+        redis: &Pool,
+        photo: &ij::PhotoName,
+    ) -> Result<Option<Visibility>, ApiError> {
+        let key = RedisKey::PhotoVisibility.to_string();
+        let field = photo.to_string();
+        if let Some(cache) = redis.hget::<Option<String>, _, _>(&key, &field).await? {
+            return Ok(Some(Visibility::try_from(cache.as_str())?));
+        }
+        let generation = Self::generation(redis).await?;
+        let Some(visibility) = ModelMeal::photo_visibility(postgres, photo).await? else {
+            return Ok(None);
+        };
+        // As with the meals cache, a visibility read before a cache delete isn't left in the cache
+        if Self::generation(redis).await? == generation {
+            redis
+                .hset::<(), _, _>(&key, (C!(field), visibility.to_string()))
+                .await?;
+            if Self::generation(redis).await? != generation {
+                redis.hdel::<(), _, _>(&key, field).await?;
+            }
         }
+        Ok(Some(visibility))
     }
 
-    /// Return all the meals, will check cache first, if no cache, then inserts into cache
+    /// Return the response body of all the meals visible at the given visibility level, in the given encoding, and its ETag
+    /// Will check cache first, if no cache, then inserts into cache
     pub async fn get_all(
         postgres: &PgPool,
         redis: &Pool,
-        both: Option<()>,
-    ) -> Result<MealInfo, ApiError> {
-        if let Some(cache) = Self::cache_get(redis, both).await? {
+        visibility: Visibility,
+        encoding: MealEncoding,
+    ) -> Result<MealBody, ApiError> {
+        if let Some(cache) = Self::cache_get(redis, visibility, encoding).await? {
             Ok(cache)
         } else {
-            let mut date_meals: Vec<DateMeal> = vec![];
-
-            for i in ModelDateMeal::get_all(postgres, both)
+            Ok(Self::cache_fill(postgres, redis, visibility, encoding)
                 .await?
+                .0)
+        }
+    }
+
+    /// Get all the meals from postgres, and insert the serialized, and compressed, response body, its hash, and its ETag, into the cache
+    /// Returns the body in the given encoding, and the date_meals hash
+    /// The generation is the cache generation read before the rebuild started, the response is only cached if it's still current
+    async fn cache_build(
+        postgres: &PgPool,
+        redis: &Pool,
+        visibility: Visibility,
+        encoding: MealEncoding,
+        generation: Option<i64>,
+    ) -> Result<(MealBody, String), ApiError> {
+        let date_meals = DateMeal::group(ModelDateMeal::get_all(postgres, visibility, None).await?);
+        let meal_descriptions = MealInfo {
+            // A person may be hidden, but still have individual meals that are visible
+            people: ModelPerson::registry()
                 .into_iter()
-                .map(DateMeal::from)
-            {
-                if let Some(given) = date_meals.iter_mut().find(|x| x.date == i.date) {
-                    if let Some(j) = i.Jack {
-                        given.Jack = Some(j);
-                    }
-                    if let Some(d) = i.Dave {
-                        given.Dave = Some(d);
-                    }
-                } else {
-                    date_meals.push(i);
-                }
+                .filter(|i| {
+                    i.visibility() <= visibility
+                        || date_meals.iter().any(|x| x.has_person(&i.initial))
+                })
+                .map(|i| (i.initial, i.person))
+                .collect::<PersonMap>(),
+            meal_descriptions: MealDescription::get(postgres, visibility).await?,
+            meal_categories: MealCategory::get(postgres, visibility).await?,
+            meal_tags: MealTag::get(postgres, visibility).await?,
+            counts: MealCounts::count(&date_meals),
+            date_meals,
+        };
+
+        let hash = Self::hash_generate(&meal_descriptions)?.to_string();
+        let etag = Self::etag_generate(&meal_descriptions, &hash)?;
+        let json = serde_json::to_vec(&oj::OutgoingJson::new(meal_descriptions).0)?;
+        let bodies = tokio::task::spawn_blocking(move || {
+            MealEncoding::ALL
+                .into_iter()
+                .map(|i| Ok((i, i.encode(&json)?)))
+                .collect::<Result<Vec<_>, ApiError>>()
+        })
+        .await??;
+        // If the cache was deleted during the rebuild, this response may already be outdated, it's still returned, but not cached
+        // The generation is checked again after the insert, as the cache may have been deleted in between
+        if Self::generation(redis).await? == generation {
+            Self::hash_insert(redis, &hash, visibility).await?;
+            Self::cache_insert(redis, &bodies, &etag, visibility).await?;
+            if Self::generation(redis).await? != generation {
+                redis
+                    .del::<(), _>(vec![Self::key(visibility), Self::key_hash(visibility)])
+                    .await?;
             }
-            let meal_descriptions = MealInfo {
-                meal_descriptions: MealDescription::get(postgres, both).await?,
-                meal_categories: MealCategory::get(postgres, both).await?,
-                date_meals,
-            };
-
-            Self::cache_insert(redis, &meal_descriptions, both).await?;
-            Self::hash_insert(redis, &meal_descriptions, both).await?;
-            Ok(meal_descriptions)
         }
+
+        let body = bodies
+            .into_iter()
+            .find_map(|(i, body)| (i == encoding).then_some(body))
+            .unwrap_or_default();
+        Ok((MealBody { body, etag }, hash))
+    }
+}
+
+/// The last seen id of each of the meal audit tables, used by clients to only fetch changes
+/// As a string it is the four ids joined by a hyphen, e.g. "1204-16-840-210"
+#[derive(sqlx::FromRow, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangeCursor {
+    individual_meal: i64,
+    category: i64,
+    description: i64,
+    photo: i64,
+}
+
+impl fmt::Display for ChangeCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}-{}",
+            self.individual_meal, self.category, self.description, self.photo
+        )
+    }
+}
+
+impl TryFrom<&str> for ChangeCursor {
+    type Error = ApiError;
+    fn try_from(x: &str) -> Result<Self, ApiError> {
+        let ids = x
+            .split('-')
+            .map(|i| i.parse::<i64>().ok().filter(|i| i >= &0))
+            .collect::<Option<Vec<_>>>();
+        match ids.as_deref() {
+            Some(&[individual_meal, category, description, photo]) => Ok(Self {
+                individual_meal,
+                category,
+                description,
+                photo,
+            }),
+            _ => Err(ApiError::InvalidValue(S!("cursor"))),
+        }
+    }
+}
+
+impl ChangeCursor {
+    /// Get the current cursor, the max id of each audit table, only counting rows written before the oldest running transaction started
+    /// Ids are taken in insert order, not commit order, so a running transaction may still commit a row with a lower id than one that is already visible
+    async fn get(postgres: &PgPool) -> Result<Self, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+WITH snapshot AS (
+    SELECT age((pg_snapshot_xmin(pg_current_snapshot())::TEXT::BIGINT % 4294967296)::TEXT::XID) AS xmin_age
+)
+SELECT
+    GREATEST(
+        (SELECT COALESCE(MAX(individual_meal_audit_id), 0) FROM individual_meal_audit WHERE age(xmin) > s.xmin_age),
+        (SELECT COALESCE(MAX(visibility_audit_id), 0) FROM meal_person WHERE age(xmin) > s.xmin_age)
+    ) AS "individual_meal!",
+    (SELECT COALESCE(MAX(meal_category_audit_id), 0) FROM meal_category_audit WHERE age(xmin) > s.xmin_age) AS "category!",
+    (SELECT COALESCE(MAX(meal_description_audit_id), 0) FROM meal_description_audit WHERE age(xmin) > s.xmin_age) AS "description!",
+    (SELECT COALESCE(MAX(meal_photo_audit_id), 0) FROM meal_photo_audit WHERE age(xmin) > s.xmin_age) AS "photo!"
+FROM
+    snapshot s"#
+        )
+        .fetch_one(postgres)
+        .await?)
+    }
+
+    /// Get every date, yyyy-mm-dd, that has had a meal, or a meal photo, inserted, updated, or deleted since the cursor
+    /// A person's visibility or initial change draws an id from the individual_meal_audit sequence, so every date they have a meal on is included
+    /// A meal_date may have been deleted, so fall back to the date stored in the meal_date_audit
+    async fn changed_dates(self, postgres: &PgPool) -> Result<Vec<String>, ApiError> {
+        Ok(sqlx::query_scalar!(
+            r#"
+WITH changed_meal_date AS (
+    SELECT
+        (v.x->>'meal_date_id')::BIGINT AS meal_date_id
+    FROM
+        individual_meal_audit ima
+    CROSS JOIN LATERAL
+        (VALUES (ima.new_values), (ima.old_values)) v(x)
+    WHERE
+        ima.individual_meal_audit_id > $1
+        AND v.x IS NOT NULL
+    UNION
+    SELECT
+        im.meal_date_id
+    FROM
+        meal_photo_audit mpa
+    JOIN
+        individual_meal_photo imp ON imp.meal_photo_id = (COALESCE(mpa.new_values, mpa.old_values)->>'meal_photo_id')::BIGINT
+    JOIN
+        individual_meal im USING(individual_meal_id)
+    WHERE
+        mpa.meal_photo_audit_id > $2
+    UNION
+    SELECT
+        im.meal_date_id
+    FROM
+        meal_person mp
+    JOIN
+        individual_meal im USING(meal_person_id)
+    WHERE
+        mp.visibility_audit_id > $1
+)
+SELECT DISTINCT
+    COALESCE(
+        md.date_of_meal::TEXT,
+        (
+            SELECT
+                mda.old_values->>'date_of_meal'
+            FROM
+                meal_date_audit mda
+            WHERE
+                (mda.old_values->>'meal_date_id')::BIGINT = cmd.meal_date_id
+            ORDER BY
+                mda.meal_date_audit_id DESC
+            LIMIT 1
+        )
+    ) AS "date_of_meal!"
+FROM
+    changed_meal_date cmd
+LEFT JOIN
+    meal_date md USING(meal_date_id)"#,
+            self.individual_meal,
+            self.photo
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// Get the ids of all the categories and descriptions that have been inserted or updated since the cursor
+    async fn changed_ids(self, postgres: &PgPool) -> Result<(Vec<i64>, Vec<i64>), ApiError> {
+        let categories = sqlx::query_scalar!(
+            r#"
+SELECT
+    (new_values->>'meal_category_id')::BIGINT AS "id!"
+FROM
+    meal_category_audit
+WHERE
+    meal_category_audit_id > $1
+    AND action <> 'd'"#,
+            self.category
+        )
+        .fetch_all(postgres)
+        .await?;
+        let descriptions = sqlx::query_scalar!(
+            r#"
+SELECT
+    (new_values->>'meal_description_id')::BIGINT AS "id!"
+FROM
+    meal_description_audit
+WHERE
+    meal_description_audit_id > $1
+    AND action <> 'd'"#,
+            self.description
+        )
+        .fetch_all(postgres)
+        .await?;
+        Ok((categories, descriptions))
+    }
+}
+
+pub struct MealChanges;
+
+impl MealChanges {
+    /// Get all the changes since the given cursor, visible at the given visibility level
+    /// Each changed date is returned in full, or as deleted if it no longer has any visible meals
+    /// The cursor is read first, and stops short of any running transaction, so a change may be sent twice, but is never missed
+    pub async fn get(
+        postgres: &PgPool,
+        since: ChangeCursor,
+        visibility: Visibility,
+    ) -> Result<oj::MealChanges, ApiError> {
+        let cursor = ChangeCursor::get(postgres).await?;
+        let dates = since.changed_dates(postgres).await?;
+        let (category_ids, description_ids) = since.changed_ids(postgres).await?;
+
+        let date_meals = if dates.is_empty() {
+            vec![]
+        } else {
+            DateMeal::group(ModelDateMeal::get_all(postgres, visibility, Some(&dates)).await?)
+        };
+
+        let deleted = dates
+            .iter()
+            .map(|i| DateMeal::short_date(i))
+            .filter(|i| !date_meals.iter().any(|x| &x.date == i))
+            .collect::<Vec<_>>();
+
+        // A description or category may be new to the client, even if unchanged, if the visibility of a meal has changed
+        let used = |f: fn(&PersonMeal) -> i64| {
+            date_meals
+                .iter()
+                .flat_map(|i| i.meals().map(|(_, meal)| f(meal)))
+                .collect::<HashSet<_>>()
+        };
+        let used_descriptions = used(|i| i.meal_description_id);
+        let used_categories = used(|i| i.category_id);
+
+        Ok(oj::MealChanges {
+            people: ModelPerson::registry()
+                .into_iter()
+                .filter(|i| {
+                    i.visibility() <= visibility
+                        || date_meals.iter().any(|x| x.has_person(&i.initial))
+                })
+                .map(|i| (i.initial, i.person))
+                .collect::<PersonMap>(),
+            meal_descriptions: MealDescription::get(postgres, visibility)
+                .await?
+                .into_iter()
+                .filter(|(id, _)| used_descriptions.contains(id) || description_ids.contains(id))
+                .collect(),
+            meal_categories: MealCategory::get(postgres, visibility)
+                .await?
+                .into_iter()
+                .filter(|(id, _)| used_categories.contains(id) || category_ids.contains(id))
+                .collect(),
+            // Tags aren't audited, so a renamed tag can't be detected, but there are few enough to always send them all
+            meal_tags: MealTag::get(postgres, visibility).await?,
+            date_meals,
+            deleted,
+            cursor: cursor.to_string(),
+        })
     }
 }
 
@@ -388,55 +895,318 @@ pub struct ModelMissingFood {
 
 impl ModelMissingFood {
     /// sqlx/jiff_sqlx issue with this when using query_as!()
+    /// Only a missing dinner counts, breakfast and lunch are optional
+    /// A person's missing dates start at their first meal, so a newly added person isn't missing every date since the genesis date
     pub async fn get(postgres: &PgPool) -> Result<Vec<MissingFood>, ApiError> {
         let query = "
 WITH
-    all_dates
+    person_start
 AS
-    ( SELECT missing_date::date FROM generate_series($1, current_date - INTEGER '1', interval '1 day') AS missing_date)
-SELECT 
-    * , 'Jack' as person
+    ( SELECT im.meal_person_id, GREATEST($1, MIN(md.date_of_meal)) AS start_date FROM individual_meal im JOIN meal_date md USING(meal_date_id) WHERE im.deleted_at IS NULL GROUP BY im.meal_person_id)
+SELECT
+    ad.missing_date, mp.person
 FROM
-    all_dates
+    meal_person mp
+JOIN
+    person_start ps USING(meal_person_id)
+CROSS JOIN LATERAL
+    ( SELECT missing_date::date FROM generate_series(ps.start_date, current_date - INTEGER '1', interval '1 day') AS missing_date) ad
 WHERE
-    missing_date
-NOT IN
+    NOT EXISTS
     (
         SELECT
-            date_of_meal
+            im.individual_meal_id
         FROM
             individual_meal im
         JOIN meal_date md USING(meal_date_id)
-        JOIN meal_person mp USING(meal_person_id)
         WHERE
-            person = 'Jack'
+            md.date_of_meal = ad.missing_date
+            AND im.meal_person_id = mp.meal_person_id
+            AND im.meal_slot = 'dinner'
+            AND im.deleted_at IS NULL
     )
-UNION ALL
-SELECT 
-    * , 'Dave' as person
+ORDER BY
+    missing_date DESC, person ASC
+";
+        let data = sqlx::query_as::<_, Self>(query)
+            .bind(genesis_date().to_sqlx())
+            .fetch_all(postgres)
+            .await?;
+        let as_json = MissingFood::from_model(&data)?;
+        Ok(as_json)
+    }
+}
+
+/// A person's missing meals, and the user to remind about them
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelMealReminder {
+    pub full_name: String,
+    pub email: String,
+    pub person: String,
+    /// Newest first
+    pub missing_dates: Vec<String>,
+}
+
+impl ModelMealReminder {
+    /// Get the missing meals, from the last `days` days, of every person with a linked user who hasn't opted out of reminders
+    /// As with `ModelMissingFood`, a person's missing dates start at their first meal
+    pub async fn get(postgres: &PgPool, days: u16) -> Result<Vec<Self>, ApiError> {
+        let query = "
+WITH
+    person_start
+AS
+    ( SELECT im.meal_person_id, GREATEST($1, MIN(md.date_of_meal), current_date - $2::INTEGER) AS start_date FROM individual_meal im JOIN meal_date md USING(meal_date_id) WHERE im.deleted_at IS NULL GROUP BY im.meal_person_id)
+SELECT
+    ru.full_name,
+    ru.email,
+    mp.person,
+    array_agg(ad.missing_date::TEXT ORDER BY ad.missing_date DESC) AS missing_dates
 FROM
-    all_dates
+    meal_person mp
+JOIN
+    person_start ps USING(meal_person_id)
+JOIN
+    registered_user ru ON ru.registered_user_id = mp.linked_user_id
+CROSS JOIN LATERAL
+    ( SELECT missing_date::date FROM generate_series(ps.start_date, current_date - INTEGER '1', interval '1 day') AS missing_date) ad
 WHERE
-    missing_date
-NOT IN 
+    ru.active
+    AND ru.meal_reminder
+    AND NOT EXISTS
     (
         SELECT
-            date_of_meal
+            im.individual_meal_id
         FROM
             individual_meal im
         JOIN meal_date md USING(meal_date_id)
-        JOIN meal_person mp USING(meal_person_id)
-         WHERE
-            person = 'Dave'
-        )
+        WHERE
+            md.date_of_meal = ad.missing_date
+            AND im.meal_person_id = mp.meal_person_id
+            AND im.meal_slot = 'dinner'
+            AND im.deleted_at IS NULL
+    )
+GROUP BY
+    ru.registered_user_id, mp.meal_person_id
 ORDER BY
-    missing_date DESC, person ASC
+    mp.person
 ";
-        let data = sqlx::query_as::<_, Self>(query)
+        Ok(sqlx::query_as::<_, Self>(query)
             .bind(genesis_date().to_sqlx())
+            .bind(i32::from(days))
             .fetch_all(postgres)
-            .await?;
-        let as_json = MissingFood::from_model(&data)?;
-        Ok(as_json)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::gen_random_hex,
+        servers::api_tests::{base_url, setup, start_both_servers},
+    };
+
+    #[tokio::test]
+    /// A rebuild that started before a cache delete still returns its response, but doesn't leave it in the cache
+    async fn db_postgres_model_food_cache_build_interleaved_delete() {
+        let test_setup = setup().await;
+        let visibility = Visibility::Public;
+
+        let generation = MealResponse::generation(&test_setup.redis).await.unwrap();
+        MealResponse::cache_delete(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        let (body, hash) = MealResponse::cache_build(
+            &test_setup.postgres,
+            &test_setup.redis,
+            visibility,
+            MealEncoding::Identity,
+            generation,
+        )
+        .await
+        .unwrap();
+        assert!(!body.body.is_empty());
+        assert!(!hash.is_empty());
+
+        let exists: i64 = test_setup
+            .redis
+            .exists(vec![
+                MealResponse::key(visibility),
+                MealResponse::key_hash(visibility),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(exists, 0);
+
+        // A rebuild from the current generation is cached
+        let generation = MealResponse::generation(&test_setup.redis).await.unwrap();
+        MealResponse::cache_build(
+            &test_setup.postgres,
+            &test_setup.redis,
+            visibility,
+            MealEncoding::Identity,
+            generation,
+        )
+        .await
+        .unwrap();
+        let exists: i64 = test_setup
+            .redis
+            .exists(vec![
+                MealResponse::key(visibility),
+                MealResponse::key_hash(visibility),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(exists, 2);
+
+        // A delete after the insert removes it
+        MealResponse::cache_delete(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        let exists: i64 = test_setup
+            .redis
+            .exists(vec![
+                MealResponse::key(visibility),
+                MealResponse::key_hash(visibility),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(exists, 0);
+    }
+
+    #[tokio::test]
+    /// A cache fill releases its lock, but the unlock script never removes a lock held by another caller
+    async fn db_postgres_model_food_cache_fill_unlock() {
+        let test_setup = setup().await;
+        let visibility = Visibility::Public;
+        let key_lock = MealResponse::key_lock(visibility);
+
+        let (body, _) = MealResponse::cache_fill(
+            &test_setup.postgres,
+            &test_setup.redis,
+            visibility,
+            MealEncoding::Identity,
+        )
+        .await
+        .unwrap();
+        assert!(!body.body.is_empty());
+        let exists: i64 = test_setup.redis.exists(&key_lock).await.unwrap();
+        assert_eq!(exists, 0);
+
+        test_setup
+            .redis
+            .set::<(), _, _>(&key_lock, "other", None, None, false)
+            .await
+            .unwrap();
+        let deleted: i64 = test_setup
+            .redis
+            .eval(REBUILD_UNLOCK_SCRIPT, &key_lock, "token")
+            .await
+            .unwrap();
+        assert_eq!(deleted, 0);
+        let lock: Option<String> = test_setup.redis.get(&key_lock).await.unwrap();
+        assert_eq!(lock, Some(S!("other")));
+
+        let deleted: i64 = test_setup
+            .redis
+            .eval(REBUILD_UNLOCK_SCRIPT, &key_lock, "other")
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+    }
+
+    #[tokio::test]
+    /// A photo that isn't used by any meal isn't cached
+    async fn db_postgres_model_food_photo_visibility_unused() {
+        let test_setup = setup().await;
+        let photo =
+            ij::PhotoName::Converted(format!("{}01.jpg", Ulid::new().to_string().to_lowercase()));
+        let key = RedisKey::PhotoVisibility.to_string();
+
+        let result =
+            MealResponse::photo_visibility(&test_setup.postgres, &test_setup.redis, &photo)
+                .await
+                .unwrap();
+        assert!(result.is_none());
+        let cache: Option<String> = test_setup
+            .redis
+            .hget(&key, photo.to_string())
+            .await
+            .unwrap();
+        assert!(cache.is_none());
+    }
+
+    #[tokio::test]
+    /// A used photo's visibility is cached, and removed from the cache by a cache delete
+    async fn db_postgres_model_food_photo_visibility_cache() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let body = test_setup.gen_meal(true);
+        let result = reqwest::Client::new()
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), reqwest::StatusCode::OK);
+
+        let photo = ij::PhotoName::Converted(C!(body.photos[0].converted));
+        let key = RedisKey::PhotoVisibility.to_string();
+        let result =
+            MealResponse::photo_visibility(&test_setup.postgres, &test_setup.redis, &photo)
+                .await
+                .unwrap();
+        assert_eq!(result, Some(Visibility::Public));
+        let cache: Option<String> = test_setup
+            .redis
+            .hget(&key, photo.to_string())
+            .await
+            .unwrap();
+        assert_eq!(cache, Some(Visibility::Public.to_string()));
+
+        MealResponse::cache_delete(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        let exists: i64 = test_setup.redis.exists(&key).await.unwrap();
+        assert_eq!(exists, 0);
+    }
+
+    #[tokio::test]
+    /// A row from a transaction that commits after a later one isn't skipped by the cursor
+    async fn db_postgres_model_food_change_cursor_overlapping_transactions() {
+        let mut test_setup = setup().await;
+        test_setup.insert_test_user().await;
+        let user_id = test_setup.model_user.as_ref().unwrap().registered_user_id;
+        let insert = "INSERT INTO meal_category(category, registered_user_id) VALUES($1, $2) RETURNING meal_category_id";
+
+        // The first transaction takes the lower audit id, but is still running when the second commits
+        let mut first = test_setup.postgres.begin().await.unwrap();
+        let first_id: i64 = sqlx::query_scalar(insert)
+            .bind(gen_random_hex(12))
+            .bind(user_id)
+            .fetch_one(&mut *first)
+            .await
+            .unwrap();
+        let second_id: i64 = sqlx::query_scalar(insert)
+            .bind(gen_random_hex(12))
+            .bind(user_id)
+            .fetch_one(&test_setup.postgres)
+            .await
+            .unwrap();
+
+        let cursor = ChangeCursor::get(&test_setup.postgres).await.unwrap();
+        first.commit().await.unwrap();
+
+        let (categories, _) = cursor.changed_ids(&test_setup.postgres).await.unwrap();
+        assert!(categories.contains(&first_id));
+
+        sqlx::query("DELETE FROM meal_category WHERE meal_category_id = ANY($1)")
+            .bind(vec![first_id, second_id])
+            .execute(&test_setup.postgres)
+            .await
+            .unwrap();
     }
 }
@@ -1,31 +1,105 @@
+use std::sync::LazyLock;
+
+use futures::stream::BoxStream;
 use jiff_sqlx::ToSqlx;
-use sqlx::{PgPool, Postgres, Transaction};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction, postgres::PgArguments, query::QueryAs};
+
+use crate::{S, api_error::ApiError, helpers::genesis_date, servers::ij};
+
+use super::{MealSlot, ModelMealRevision, ModelTag, ModelUser, Person, Visibility};
 
-use crate::{C, S, api_error::ApiError, servers::ij};
+/// Number of meals in each page of search results
+pub const SEARCH_PAGE_SIZE: i64 = 50;
+
+/// The columns of every ModelMeal query, $1 is a boolean of whether the original photo names are visible
+const MEAL_COLUMNS: &str = "
+    im.individual_meal_id,
+    md.date_of_meal as meal_date,
+    md.meal_date_id,
+    p.person,
+    im.meal_slot AS slot,
+    mc.category,
+    mc.meal_category_id,
+    mde.description,
+    mde.meal_description_id,
+    COALESCE(im.restaurant, false) AS restaurant,
+    COALESCE(im.takeaway, false) AS takeaway,
+    COALESCE(im.vegetarian, false) AS vegetarian,
+    COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'meal_photo_id', mp.meal_photo_id,
+            'photo_original', CASE WHEN $1 THEN mp.photo_original END,
+            'photo_converted', mp.photo_converted,
+            'caption', imp.caption
+        ) ORDER BY imp.position)
+        FROM individual_meal_photo imp
+        JOIN meal_photo mp USING(meal_photo_id)
+        WHERE imp.individual_meal_id = im.individual_meal_id
+    ), '[]') AS photos,
+    im.visibility,
+    ARRAY(SELECT imt.meal_tag_id FROM individual_meal_tag imt WHERE imt.individual_meal_id = im.individual_meal_id ORDER BY imt.meal_tag_id) AS tags,
+    im.meal_venue_id";
 
-use super::{ModelUser, Person};
+/// The tables of every ModelMeal query, each query adds its own WHERE and ORDER BY
+const MEAL_TABLES: &str = "
+FROM
+    individual_meal im
+    JOIN meal_person p USING(meal_person_id)
+    JOIN meal_date md USING(meal_date_id)
+    JOIN meal_category mc USING(meal_category_id)
+    JOIN meal_description mde USING(meal_description_id)";
 
 #[derive(sqlx::FromRow)]
 struct Id {
     id: i64,
 }
 
+/// A single page of search results, and the total number of matches across every page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelMealSearch {
+    pub meals: Vec<ModelMeal>,
+    pub total: i64,
+}
+
+/// A deleted meal, kept in the trash until it's either restored or purged
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelMealTrash {
+    #[sqlx(flatten)]
+    pub meal: ModelMeal,
+    pub deleted_at: String,
+}
+
+/// A single photo of a meal's gallery, built from a jsonb object
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ModelMealPhoto {
+    pub meal_photo_id: i64,
+    pub photo_original: Option<String>,
+    pub photo_converted: String,
+    pub caption: Option<String>,
+}
+
 #[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
 pub struct ModelMeal {
     pub meal_category_id: i64,
     pub meal_description_id: i64,
     pub meal_date_id: i64,
-    pub meal_photo_id: Option<i64>,
     pub individual_meal_id: i64,
     pub meal_date: jiff_sqlx::Date,
     pub category: String,
     pub person: String,
+    pub slot: String,
     pub restaurant: bool,
     pub takeaway: bool,
     pub vegetarian: bool,
     pub description: String,
-    pub photo_original: Option<String>,
-    pub photo_converted: Option<String>,
+    /// Ordered by position, the original is None when it isn't visible
+    #[sqlx(json)]
+    pub photos: Vec<ModelMealPhoto>,
+    pub visibility: Option<String>,
+    /// Tag ids, ordered
+    pub tags: Vec<i64>,
+    pub meal_venue_id: Option<i64>,
 }
 
 impl ModelMeal {
@@ -86,42 +160,37 @@ impl ModelMeal {
         }
     }
 
-    async fn insert_person(
+    /// People are managed via the admin api, so only get the id of an existing person
+    async fn get_person_id(
         transaction: &mut Transaction<'_, Postgres>,
         meal: &ij::Meal,
-        user: &ModelUser,
     ) -> Result<i64, ApiError> {
-        if let Some(id) = sqlx::query_as!(
+        sqlx::query_as!(
             Id,
             "SELECT meal_person_id AS id FROM meal_person WHERE person = $1",
             meal.person.to_string()
         )
         .fetch_optional(&mut **transaction)
         .await?
-        {
-            Ok(id.id)
-        } else {
-            Ok(sqlx::query_as!(Id, "INSERT INTO meal_person(person, registered_user_id) VALUES($1, $2) RETURNING meal_person_id AS id", meal.person.to_string(), user.registered_user_id).fetch_one(&mut **transaction).await?.id)
-        }
+        .map_or_else(|| Err(ApiError::InvalidValue(S!("person"))), |id| Ok(id.id))
     }
 
     async fn insert_photo(
         transaction: &mut Transaction<'_, Postgres>,
-        converted: &ij::PhotoName,
-        original: &ij::PhotoName,
+        photo: &ij::MealPhoto,
         user: &ModelUser,
     ) -> Result<i64, ApiError> {
         if let Some(id) = sqlx::query_as!(Id,"SELECT meal_photo_id AS id FROM meal_photo WHERE photo_original = $1 AND photo_converted = $2",
-            original.to_string(),
-            converted.to_string())
+            photo.original.to_string(),
+            photo.converted.to_string())
             .fetch_optional(&mut **transaction)
             .await?
         {
             Ok(id.id)
         } else {
             Ok(sqlx::query_as!(Id, "INSERT INTO meal_photo(photo_original, photo_converted, registered_user_id) VALUES($1, $2, $3) RETURNING meal_photo_id AS id",
-                original.to_string(),
-                converted.to_string(),
+                photo.original.to_string(),
+                photo.converted.to_string(),
                 user.registered_user_id)
                 .fetch_one(&mut **transaction)
                 .await?
@@ -129,11 +198,40 @@ impl ModelMeal {
         }
     }
 
+    /// Replace the photos of a meal, position is taken from the order of the given photos
+    async fn set_meal_photos(
+        transaction: &mut Transaction<'_, Postgres>,
+        individual_meal_id: i64,
+        photos: &[ij::MealPhoto],
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "DELETE FROM individual_meal_photo WHERE individual_meal_id = $1",
+            individual_meal_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+        for (position, photo) in photos.iter().enumerate() {
+            let meal_photo_id = Self::insert_photo(transaction, photo, user).await?;
+            sqlx::query!(
+                "INSERT INTO individual_meal_photo(individual_meal_id, meal_photo_id, position, caption) VALUES($1, $2, $3, $4)",
+                individual_meal_id,
+                meal_photo_id,
+                i16::try_from(position).unwrap_or(i16::MAX),
+                photo.caption
+            )
+            .execute(&mut **transaction)
+            .await?;
+        }
+        Ok(())
+    }
+
     /// Search for categories, dates, photos, and descriptions, that are dangling, and delete them from postgres
+    /// Returns the (converted, original) names of the deleted photos
     async fn delete_empty(
         transaction: &mut Transaction<'_, Postgres>,
         meal: &Self,
-    ) -> Result<Option<(String, String)>, ApiError> {
+    ) -> Result<Vec<(String, String)>, ApiError> {
         sqlx::query!("DELETE FROM meal_category WHERE meal_category_id = $1 AND (SELECT count(*) from individual_meal WHERE meal_category_id = $1) = 0", 
             meal.meal_category_id)
             .execute(&mut **transaction)
@@ -148,71 +246,223 @@ impl ModelMeal {
             meal.meal_description_id)
             .execute(&mut **transaction)
             .await?;
-        Ok(
-            if let Some(photo_id) = &meal.meal_photo_id
-                && let Some(converted) = &meal.photo_converted
-                && let Some(original) = &meal.photo_original
-            {
-                sqlx::query!("DELETE FROM meal_photo WHERE meal_photo_id = $1 AND (SELECT count(*) from individual_meal WHERE meal_photo_id = $1) = 0",
-                photo_id)
-                .execute(&mut **transaction)
-                .await?;
-                Some((C!(converted), C!(original)))
-            } else {
-                None
-            },
+        Ok(sqlx::query!(
+            "DELETE FROM meal_photo mp WHERE mp.meal_photo_id = ANY($1) AND NOT EXISTS (SELECT 1 FROM individual_meal_photo imp WHERE imp.meal_photo_id = mp.meal_photo_id) RETURNING mp.photo_converted, mp.photo_original",
+            &meal.photos.iter().map(|i| i.meal_photo_id).collect::<Vec<_>>()
         )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .map(|i| (i.photo_converted, i.photo_original))
+        .collect())
     }
 
-    /// Get a single meal by date + person
+    /// Get a single meal by date + person + slot
     pub async fn get_by_date_person(
         postgres: &PgPool,
         person: &Person,
         date: jiff::civil::Date,
+        slot: MealSlot,
     ) -> Result<Option<Self>, ApiError> {
-        let query = "
-SELECT
-    im.individual_meal_id,
-    md.date_of_meal as meal_date,
-    md.meal_date_id,
-    p.person,
-    mc.category,
-    mc.meal_category_id,
-    mde.description,
-    mde.meal_description_id,
-    CASE
-        WHEN im.restaurant IS null THEN false
-        ELSE im.restaurant
-    END AS restaurant,
-    CASE
-        WHEN im.takeaway IS null THEN false
-        ELSE im.takeaway
-    END AS takeaway,
-    CASE
-        WHEN im.vegetarian IS null THEN false
-        ELSE im.vegetarian
-    END AS vegetarian,
-    im.meal_photo_id,
-    mp.photo_original,
-    mp.photo_converted
-FROM
-    individual_meal im
-    JOIN meal_person p USING(meal_person_id)
-    JOIN meal_date md USING(meal_date_id)
-    JOIN meal_category mc USING(meal_category_id)
-    JOIN meal_description mde USING(meal_description_id)
-    LEFT JOIN meal_photo mp USING(meal_photo_id)
+        let query = format!(
+            "SELECT{MEAL_COLUMNS}{MEAL_TABLES}
 WHERE
-    md.date_of_meal = $1
-    AND p.person = $2";
+    md.date_of_meal = $2
+    AND p.person = $3
+    AND im.meal_slot = $4
+    AND im.deleted_at IS NULL"
+        );
 
-        Ok(sqlx::query_as::<_, Self>(query)
+        Ok(sqlx::query_as::<_, Self>(&query)
+            .bind(true)
             .bind(date.to_sqlx())
             .bind(person.to_string())
+            .bind(slot.to_string())
             .fetch_optional(postgres)
             .await?)
     }
 
+    /// Error if both dates are given, and from is after to
+    pub fn check_date_range(
+        from: Option<jiff::civil::Date>,
+        to: Option<jiff::civil::Date>,
+    ) -> Result<(), ApiError> {
+        if let Some(from) = from
+            && let Some(to) = to
+            && from > to
+        {
+            return Err(ApiError::InvalidValue(S!("date range")));
+        }
+        Ok(())
+    }
+
+    /// Stream every meal, ordered by date, person, then slot, with optional person and date range filters
+    /// Only return meals visible at the given visibility level, original photos are never public
+    /// The date range should be checked with `check_date_range` before streaming
+    pub fn export<'a>(
+        postgres: &'a PgPool,
+        export: &ij::MealExport,
+        visibility: Visibility,
+    ) -> BoxStream<'a, Result<Self, sqlx::Error>> {
+        // The stream outlives this function, so the query can't be a local String
+        static QUERY: LazyLock<String> = LazyLock::new(|| {
+            format!(
+                "SELECT{MEAL_COLUMNS}{MEAL_TABLES}
+WHERE
+    COALESCE(im.visibility, p.visibility) = ANY($2)
+    AND im.deleted_at IS NULL
+    AND ($3::TEXT IS NULL OR p.person = $3)
+    AND ($4::DATE IS NULL OR md.date_of_meal >= $4)
+    AND ($5::DATE IS NULL OR md.date_of_meal <= $5)
+    AND ($6::BIGINT[] IS NULL OR ARRAY(SELECT imt.meal_tag_id FROM individual_meal_tag imt WHERE imt.individual_meal_id = im.individual_meal_id) @> $6)
+ORDER BY
+    md.date_of_meal,
+    p.person,
+    array_position(ARRAY['breakfast', 'lunch', 'dinner'], im.meal_slot)"
+            )
+        });
+
+        sqlx::query_as::<_, Self>(&QUERY)
+            .bind(visibility > Visibility::Public)
+            .bind(visibility.allowed())
+            .bind(export.person.as_ref().map(ToString::to_string))
+            .bind(export.from.map(ToSqlx::to_sqlx))
+            .bind(export.to.map(ToSqlx::to_sqlx))
+            .bind(export.tags.clone())
+            .fetch(postgres)
+    }
+
+    /// Bind the parameters of both search queries, the count query ignores the original photo flag, and the page limits
+    fn search_bind<'q, O>(
+        query: QueryAs<'q, Postgres, O, PgArguments>,
+        search: &'q ij::MealSearch,
+        visibility: Visibility,
+    ) -> QueryAs<'q, Postgres, O, PgArguments> {
+        query
+            .bind(visibility > Visibility::Public)
+            .bind(visibility.allowed())
+            .bind(&search.q)
+            .bind(search.person.as_ref().map(ToString::to_string))
+            .bind(search.category.as_ref())
+            .bind(search.from.map(ToSqlx::to_sqlx))
+            .bind(search.to.map(ToSqlx::to_sqlx))
+            .bind(search.restaurant)
+            .bind(search.takeaway)
+            .bind(search.vegetarian)
+            .bind(SEARCH_PAGE_SIZE)
+            .bind((search.page.unwrap_or(1) - 1).saturating_mul(SEARCH_PAGE_SIZE))
+            .bind(search.tags.as_ref())
+    }
+
+    /// Search meal descriptions, full text and trigram, ranked by relevance then date, with optional filters
+    /// Only return meals visible at the given visibility level, original photos are never public
+    /// The total is counted separately, so that it's still correct for a page past the last result
+    pub async fn search(
+        postgres: &PgPool,
+        search: &ij::MealSearch,
+        visibility: Visibility,
+    ) -> Result<ModelMealSearch, ApiError> {
+        Self::check_date_range(search.from, search.to)?;
+        let filter = "
+WHERE
+    COALESCE(im.visibility, p.visibility) = ANY($2)
+    AND im.deleted_at IS NULL
+    AND (
+        to_tsvector('english', mde.description) @@ websearch_to_tsquery('english', $3)
+        OR $3 <% mde.description
+    )
+    AND ($4::TEXT IS NULL OR p.person = $4)
+    AND ($5::TEXT IS NULL OR mc.category = upper($5))
+    AND ($6::DATE IS NULL OR md.date_of_meal >= $6)
+    AND ($7::DATE IS NULL OR md.date_of_meal <= $7)
+    AND ($8::BOOLEAN IS NULL OR COALESCE(im.restaurant, false) = $8)
+    AND ($9::BOOLEAN IS NULL OR COALESCE(im.takeaway, false) = $9)
+    AND ($10::BOOLEAN IS NULL OR COALESCE(im.vegetarian, false) = $10)
+    AND ($13::BIGINT[] IS NULL OR ARRAY(SELECT imt.meal_tag_id FROM individual_meal_tag imt WHERE imt.individual_meal_id = im.individual_meal_id) @> $13)";
+        let query = format!(
+            "SELECT{MEAL_COLUMNS}{MEAL_TABLES}{filter}
+ORDER BY
+    ts_rank(to_tsvector('english', mde.description), websearch_to_tsquery('english', $3)) + word_similarity($3, mde.description) DESC,
+    md.date_of_meal DESC,
+    p.person
+LIMIT $11
+OFFSET $12"
+        );
+        let count = format!("SELECT COUNT(*){MEAL_TABLES}{filter}");
+
+        let meals = Self::search_bind(sqlx::query_as::<_, Self>(&query), search, visibility)
+            .fetch_all(postgres)
+            .await?;
+        let (total,) = Self::search_bind(sqlx::query_as::<_, (i64,)>(&count), search, visibility)
+            .fetch_one(postgres)
+            .await?;
+        Ok(ModelMealSearch { meals, total })
+    }
+
+    /// Get every meal eaten on the same month and day as the given date, in previous years, newest first, then person, then slot
+    /// Only return meals visible at the given visibility level, original photos are never public
+    pub async fn on_this_day(
+        postgres: &PgPool,
+        date: jiff::civil::Date,
+        visibility: Visibility,
+    ) -> Result<Vec<Self>, ApiError> {
+        let query = format!(
+            "SELECT{MEAL_COLUMNS}{MEAL_TABLES}
+WHERE
+    COALESCE(im.visibility, p.visibility) = ANY($2)
+    AND im.deleted_at IS NULL
+    AND EXTRACT(MONTH FROM md.date_of_meal) = $3
+    AND EXTRACT(DAY FROM md.date_of_meal) = $4
+    AND md.date_of_meal >= $5
+    AND md.date_of_meal < $6
+ORDER BY
+    md.date_of_meal DESC,
+    p.person,
+    array_position(ARRAY['breakfast', 'lunch', 'dinner'], im.meal_slot)"
+        );
+
+        Ok(sqlx::query_as::<_, Self>(&query)
+            .bind(visibility > Visibility::Public)
+            .bind(visibility.allowed())
+            .bind(i32::from(date.month()))
+            .bind(i32::from(date.day()))
+            .bind(genesis_date().to_sqlx())
+            .bind(date.to_sqlx())
+            .fetch_all(postgres)
+            .await?)
+    }
+
+    /// Get the most restrictive visibility of all the meals that use a given photo, None if the photo isn't used by any meal
+    pub async fn photo_visibility(
+        postgres: &PgPool,
+        photo: &ij::PhotoName,
+    ) -> Result<Option<Visibility>, ApiError> {
+        Ok(sqlx::query!(
+            r#"
+SELECT
+    COALESCE(im.visibility, mpe.visibility) AS "visibility!"
+FROM
+    meal_photo mp
+JOIN
+    individual_meal_photo USING(meal_photo_id)
+JOIN
+    individual_meal im USING(individual_meal_id)
+JOIN
+    meal_person mpe USING(meal_person_id)
+WHERE
+    (mp.photo_converted = $1 OR mp.photo_original = $1)
+    AND im.deleted_at IS NULL"#,
+            photo.to_string()
+        )
+        .fetch_all(postgres)
+        .await?
+        .into_iter()
+        .map(|i| Visibility::try_from(i.visibility.as_str()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .max())
+    }
+
     /// Insert a new meal, and also clear the redis meal cache
     pub async fn insert(
         postgres: &PgPool,
@@ -220,61 +470,117 @@ WHERE
         user: &ModelUser,
     ) -> Result<(), ApiError> {
         let mut transaction = postgres.begin().await?;
+        Self::insert_meal(&mut transaction, meal, user).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
 
-        let description_id = Self::insert_description(&mut transaction, meal, user).await?;
-        let category_id = Self::insert_category(&mut transaction, meal, user).await?;
-        let date_id = Self::insert_date(&mut transaction, meal, user).await?;
-        let meal_person_id = Self::insert_person(&mut transaction, meal, user).await?;
+    /// Insert multiple new meals in a single transaction, if any insert fails then no meals are inserted
+    pub async fn insert_many(
+        postgres: &PgPool,
+        meals: &[ij::Meal],
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        let mut transaction = postgres.begin().await?;
+        for meal in meals {
+            Self::insert_meal(&mut transaction, meal, user).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
 
-        let photo_id = if let Some(converted) = &meal.photo_converted
-            && let Some(original) = &meal.photo_original
-        {
-            Some(Self::insert_photo(&mut transaction, converted, original, user).await?)
-        } else {
-            None
-        };
+    /// A venue can only be set on a restaurant or takeaway meal, and must exist
+    async fn check_venue(
+        transaction: &mut Transaction<'_, Postgres>,
+        meal: &ij::Meal,
+    ) -> Result<(), ApiError> {
+        if let Some(meal_venue_id) = meal.venue_id {
+            if !meal.restaurant && !meal.takeaway {
+                return Err(ApiError::InvalidValue(S!("venue")));
+            }
+            if sqlx::query_scalar!(
+                "SELECT meal_venue_id FROM meal_venue WHERE meal_venue_id = $1",
+                meal_venue_id
+            )
+            .fetch_optional(&mut **transaction)
+            .await?
+            .is_none()
+            {
+                return Err(ApiError::InvalidValue(S!("unknown venue")));
+            }
+        }
+        Ok(())
+    }
 
-        sqlx::query!("
+    async fn insert_meal(
+        transaction: &mut Transaction<'_, Postgres>,
+        meal: &ij::Meal,
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        Self::check_venue(transaction, meal).await?;
+        let description_id = Self::insert_description(transaction, meal, user).await?;
+        let category_id = Self::insert_category(transaction, meal, user).await?;
+        let date_id = Self::insert_date(transaction, meal, user).await?;
+        let meal_person_id = Self::get_person_id(transaction, meal).await?;
+
+        let individual_meal_id = sqlx::query_as!(Id, "
 INSERT INTO individual_meal
-    (registered_user_id, meal_category_id, meal_date_id, meal_description_id, meal_person_id, meal_photo_id, restaurant, takeaway, vegetarian)
+    (registered_user_id, meal_category_id, meal_date_id, meal_description_id, meal_person_id, restaurant, takeaway, vegetarian, visibility, meal_slot, meal_venue_id)
 VALUES
-    ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+RETURNING
+    individual_meal_id AS id",
             user.registered_user_id,
             category_id,
             date_id,
             description_id,
             meal_person_id,
-            photo_id,
             meal.restaurant,
             meal.takeaway,
-            meal.vegetarian)
-            .execute(&mut *transaction)
-            .await?;
-
-        transaction.commit().await?;
+            meal.vegetarian,
+            meal.visibility.map(|i| i.to_string()),
+            meal.slot.to_string(),
+            meal.venue_id)
+            .fetch_one(&mut **transaction)
+            .await?
+            .id;
+        ModelTag::set_meal_tags(transaction, individual_meal_id, &meal.tags).await?;
+        Self::set_meal_photos(transaction, individual_meal_id, &meal.photos, user).await?;
         Ok(())
     }
 
+    /// Update a meal, if `if_match` is given, the meal must still be at that revision
+    /// Returns the new revision of the meal
     pub async fn update(
         postgres: &PgPool,
         meal: &ij::Meal,
         user: &ModelUser,
         original_meal: &Self,
-    ) -> Result<(), ApiError> {
+        if_match: Option<i64>,
+    ) -> Result<Option<i64>, ApiError> {
         let mut transaction = postgres.begin().await?;
 
+        if let Some(revision) = if_match {
+            // Lock the row, so that a concurrent update has to wait for this one to finish
+            sqlx::query!(
+                "SELECT individual_meal_id FROM individual_meal WHERE individual_meal_id = $1 FOR UPDATE",
+                original_meal.individual_meal_id
+            )
+            .fetch_one(&mut *transaction)
+            .await?;
+            if ModelMealRevision::latest(&mut *transaction, original_meal.individual_meal_id)
+                .await?
+                != Some(revision)
+            {
+                return Err(ApiError::Conflict(S!("meal has been modified")));
+            }
+        }
+
+        Self::check_venue(&mut transaction, meal).await?;
         let description_id = Self::insert_description(&mut transaction, meal, user).await?;
         let category_id = Self::insert_category(&mut transaction, meal, user).await?;
         let date_id = Self::insert_date(&mut transaction, meal, user).await?;
-        let meal_person_id = Self::insert_person(&mut transaction, meal, user).await?;
-
-        let photo_id = if let Some(converted) = &meal.photo_converted
-            && let Some(original) = &meal.photo_original
-        {
-            Some(Self::insert_photo(&mut transaction, converted, original, user).await?)
-        } else {
-            None
-        };
+        let meal_person_id = Self::get_person_id(&mut transaction, meal).await?;
 
         sqlx::query!(
             "
@@ -285,48 +591,173 @@ SET
     meal_date_id = $2,
     meal_description_id = $3,
     meal_person_id = $4,
-    meal_photo_id = $5,
-    restaurant = $6,
-    takeaway = $7,
-    vegetarian = $8
+    restaurant = $5,
+    takeaway = $6,
+    vegetarian = $7,
+    visibility = $8,
+    meal_slot = $9,
+    meal_venue_id = $10
 WHERE
-    individual_meal_id = $9",
+    individual_meal_id = $11",
             category_id,
             date_id,
             description_id,
             meal_person_id,
-            photo_id,
             meal.restaurant,
             meal.takeaway,
             meal.vegetarian,
+            meal.visibility.map(|i| i.to_string()),
+            meal.slot.to_string(),
+            meal.venue_id,
             original_meal.individual_meal_id
         )
         .execute(&mut *transaction)
         .await?;
+        ModelTag::set_meal_tags(
+            &mut transaction,
+            original_meal.individual_meal_id,
+            &meal.tags,
+        )
+        .await?;
+        Self::set_meal_photos(
+            &mut transaction,
+            original_meal.individual_meal_id,
+            &meal.photos,
+            user,
+        )
+        .await?;
         Self::delete_empty(&mut transaction, original_meal).await?;
+        let revision =
+            ModelMealRevision::latest(&mut *transaction, original_meal.individual_meal_id).await?;
         transaction.commit().await?;
-        Ok(())
+        Ok(revision)
     }
 
+    /// Move a meal to the trash, it's hidden from every read path until it's restored
     pub async fn delete(
         postgres: &PgPool,
         person: &Person,
         date: jiff::civil::Date,
-    ) -> Result<Option<(String, String)>, ApiError> {
-        match Self::get_by_date_person(postgres, person, date).await? {
+        slot: MealSlot,
+    ) -> Result<(), ApiError> {
+        match Self::get_by_date_person(postgres, person, date, slot).await? {
             Some(meal) => {
-                let mut transaction = postgres.begin().await?;
                 sqlx::query!(
-                    "DELETE FROM individual_meal WHERE individual_meal_id = $1",
+                    "UPDATE individual_meal SET deleted_at = CURRENT_TIMESTAMP WHERE individual_meal_id = $1",
                     meal.individual_meal_id
                 )
-                .execute(&mut *transaction)
+                .execute(postgres)
                 .await?;
-                let output = Self::delete_empty(&mut transaction, &meal).await?;
-                transaction.commit().await?;
-                Ok(output)
+                Ok(())
             }
             _ => Err(ApiError::InvalidValue(S!("Unknown meal"))),
         }
     }
 }
+
+impl ModelMealTrash {
+    /// Get deleted meals, newest deletion first, optionally only a single meal, or only meals deleted more than `days` ago
+    async fn get(
+        postgres: &PgPool,
+        individual_meal_id: Option<i64>,
+        days: Option<u16>,
+    ) -> Result<Vec<Self>, ApiError> {
+        let query = format!(
+            "SELECT{MEAL_COLUMNS},
+    im.deleted_at::TEXT AS deleted_at{MEAL_TABLES}
+WHERE
+    im.deleted_at IS NOT NULL
+    AND ($2::BIGINT IS NULL OR im.individual_meal_id = $2)
+    AND ($3::INTEGER IS NULL OR im.deleted_at < CURRENT_TIMESTAMP - make_interval(days => $3))
+ORDER BY
+    im.deleted_at DESC,
+    im.individual_meal_id DESC"
+        );
+
+        Ok(sqlx::query_as::<_, Self>(&query)
+            .bind(true)
+            .bind(individual_meal_id)
+            .bind(days.map(i32::from))
+            .fetch_all(postgres)
+            .await?)
+    }
+
+    /// Get every deleted meal, newest deletion first
+    pub async fn get_all(postgres: &PgPool) -> Result<Vec<Self>, ApiError> {
+        Self::get(postgres, None, None).await
+    }
+
+    /// Get a single deleted meal
+    pub async fn get_by_id(
+        postgres: &PgPool,
+        individual_meal_id: i64,
+    ) -> Result<Option<Self>, ApiError> {
+        Ok(Self::get(postgres, Some(individual_meal_id), None)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Restore a deleted meal, unless another meal has since been added in the same date, person, and slot
+    /// The check and the update are a single statement, and a concurrent insert into the same slot still hits the unique index
+    pub async fn restore(&self, postgres: &PgPool) -> Result<(), ApiError> {
+        let exists = || ApiError::InvalidValue(S!("Meal already exists on date and person given"));
+        let result = sqlx::query!(
+            r#"
+UPDATE
+    individual_meal im
+SET
+    deleted_at = NULL
+WHERE
+    im.individual_meal_id = $1
+    AND NOT EXISTS (
+        SELECT 1
+        FROM individual_meal other
+        WHERE
+            other.meal_date_id = im.meal_date_id
+            AND other.meal_person_id = im.meal_person_id
+            AND other.meal_slot = im.meal_slot
+            AND other.deleted_at IS NULL
+    )"#,
+            self.meal.individual_meal_id
+        )
+        .execute(postgres)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => exists(),
+            e => ApiError::from(e),
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(exists());
+        }
+        Ok(())
+    }
+
+    /// Permanently delete a meal, and any category, date, description, or photo that is no longer used
+    /// Returns the converted and original file names of any removed photos
+    pub async fn purge(&self, postgres: &PgPool) -> Result<Vec<(String, String)>, ApiError> {
+        let mut transaction = postgres.begin().await?;
+        sqlx::query!(
+            "DELETE FROM individual_meal WHERE individual_meal_id = $1",
+            self.meal.individual_meal_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let output = ModelMeal::delete_empty(&mut transaction, &self.meal).await?;
+        transaction.commit().await?;
+        Ok(output)
+    }
+
+    /// Purge every meal that was deleted more than `days` ago
+    /// Returns the converted and original file names of every removed photo
+    pub async fn purge_expired(
+        postgres: &PgPool,
+        days: u16,
+    ) -> Result<Vec<(String, String)>, ApiError> {
+        let mut output = vec![];
+        for meal in Self::get(postgres, None, Some(days)).await? {
+            output.extend(meal.purge(postgres).await?);
+        }
+        Ok(output)
+    }
+}
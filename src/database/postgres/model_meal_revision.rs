@@ -0,0 +1,172 @@
+use sqlx::{PgPool, types::Json};
+
+use crate::api_error::ApiError;
+
+use super::ModelMealPhoto;
+
+/// The state of a meal after an insert, or an update, as recorded by the individual_meal_audit trigger
+/// Ids are resolved to names, falling back to the audit tables if the row has since been deleted
+/// Photos are recorded by the individual_meal_photo_audit trigger, in the same transaction, so share the revision's timestamp
+/// Tags are stored in their own table, so aren't part of a revision
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelMealRevision {
+    pub revision: i64,
+    pub action: String,
+    pub timestamp: String,
+    /// Columns whose value was changed by this revision
+    pub changed: Vec<String>,
+    pub date: Option<String>,
+    pub person: Option<String>,
+    pub slot: String,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub restaurant: bool,
+    pub takeaway: bool,
+    pub vegetarian: bool,
+    pub visibility: Option<String>,
+    pub venue_id: Option<i64>,
+    /// Ordered by position, the names of photos that have since been deleted are resolved from the audit table
+    pub photos: Json<Vec<ModelMealPhoto>>,
+}
+
+impl ModelMealRevision {
+    /// Get every revision of a meal, newest first, or just a single revision if `revision` is given
+    #[allow(clippy::too_many_lines)]
+    pub async fn get(
+        postgres: &PgPool,
+        individual_meal_id: i64,
+        revision: Option<i64>,
+    ) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    ima.individual_meal_audit_id AS "revision!",
+    ima.action,
+    ima.action_timestamp::TEXT AS "timestamp!",
+    ARRAY(SELECT jsonb_object_keys(COALESCE(ima.difference, '{}')) ORDER BY 1) AS "changed!",
+    COALESCE(
+        md.date_of_meal::TEXT,
+        (
+            SELECT mda.old_values->>'date_of_meal'
+            FROM meal_date_audit mda
+            WHERE (mda.old_values->>'meal_date_id')::BIGINT = r.meal_date_id
+            ORDER BY mda.meal_date_audit_id DESC
+            LIMIT 1
+        )
+    ) AS date,
+    mpe.person AS "person?",
+    COALESCE(r.meal_slot, 'dinner') AS "slot!",
+    COALESCE(
+        mc.category,
+        (
+            SELECT mca.old_values->>'category'
+            FROM meal_category_audit mca
+            WHERE (mca.old_values->>'meal_category_id')::BIGINT = r.meal_category_id
+            ORDER BY mca.meal_category_audit_id DESC
+            LIMIT 1
+        )
+    ) AS category,
+    COALESCE(
+        mde.description,
+        (
+            SELECT mdea.old_values->>'description'
+            FROM meal_description_audit mdea
+            WHERE (mdea.old_values->>'meal_description_id')::BIGINT = r.meal_description_id
+            ORDER BY mdea.meal_description_audit_id DESC
+            LIMIT 1
+        )
+    ) AS description,
+    COALESCE(r.restaurant, false) AS "restaurant!",
+    COALESCE(r.takeaway, false) AS "takeaway!",
+    COALESCE(r.vegetarian, false) AS "vegetarian!",
+    r.visibility,
+    r.meal_venue_id AS venue_id,
+    COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'meal_photo_id', latest.meal_photo_id,
+            'photo_original', COALESCE(mp.photo_original, mpa.old_values->>'photo_original'),
+            'photo_converted', COALESCE(mp.photo_converted, mpa.old_values->>'photo_converted'),
+            'caption', latest.caption
+        ) ORDER BY latest.position)
+        FROM (
+            SELECT DISTINCT ON (ph.meal_photo_id)
+                impa.action,
+                ph.meal_photo_id,
+                ph.position,
+                ph.caption
+            FROM
+                individual_meal_photo_audit impa
+            CROSS JOIN LATERAL
+                jsonb_to_record(COALESCE(impa.new_values, impa.old_values)) AS ph(
+                    meal_photo_id BIGINT,
+                    position SMALLINT,
+                    caption TEXT
+                )
+            WHERE
+                (COALESCE(impa.new_values, impa.old_values)->>'individual_meal_id')::BIGINT = $1
+                AND impa.action_timestamp <= ima.action_timestamp
+            ORDER BY
+                ph.meal_photo_id, impa.individual_meal_photo_audit_id DESC
+        ) latest
+        LEFT JOIN
+            meal_photo mp USING(meal_photo_id)
+        LEFT JOIN LATERAL (
+            SELECT mpa.old_values
+            FROM meal_photo_audit mpa
+            WHERE (mpa.old_values->>'meal_photo_id')::BIGINT = latest.meal_photo_id
+            ORDER BY mpa.meal_photo_audit_id DESC
+            LIMIT 1
+        ) mpa ON mp.meal_photo_id IS NULL
+        WHERE
+            latest.action <> 'd'
+            AND COALESCE(mp.photo_converted, mpa.old_values->>'photo_converted') IS NOT NULL
+    ), '[]') AS "photos!: Json<Vec<ModelMealPhoto>>"
+FROM
+    individual_meal_audit ima
+CROSS JOIN LATERAL
+    jsonb_to_record(ima.new_values) AS r(
+        meal_date_id BIGINT,
+        meal_person_id BIGINT,
+        meal_category_id BIGINT,
+        meal_description_id BIGINT,
+        restaurant BOOLEAN,
+        takeaway BOOLEAN,
+        vegetarian BOOLEAN,
+        visibility TEXT,
+        meal_slot TEXT,
+        meal_venue_id BIGINT
+    )
+LEFT JOIN
+    meal_date md ON md.meal_date_id = r.meal_date_id
+LEFT JOIN
+    meal_person mpe ON mpe.meal_person_id = r.meal_person_id
+LEFT JOIN
+    meal_category mc ON mc.meal_category_id = r.meal_category_id
+LEFT JOIN
+    meal_description mde ON mde.meal_description_id = r.meal_description_id
+WHERE
+    (ima.new_values->>'individual_meal_id')::BIGINT = $1
+    AND ($2::BIGINT IS NULL OR ima.individual_meal_audit_id = $2)
+ORDER BY
+    ima.individual_meal_audit_id DESC"#,
+            individual_meal_id,
+            revision
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// The id of the latest revision of a meal, used as the ETag of the meal
+    pub async fn latest(
+        executor: impl sqlx::PgExecutor<'_>,
+        individual_meal_id: i64,
+    ) -> Result<Option<i64>, ApiError> {
+        Ok(sqlx::query_scalar!(
+            "SELECT MAX(individual_meal_audit_id) FROM individual_meal_audit WHERE (new_values->>'individual_meal_id')::BIGINT = $1",
+            individual_meal_id
+        )
+        .fetch_one(executor)
+        .await?)
+    }
+}
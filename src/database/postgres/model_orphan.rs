@@ -0,0 +1,75 @@
+use sqlx::PgPool;
+
+use crate::api_error::ApiError;
+
+/// Category, date, description, and photo rows that aren't used by any meal, meals in the trash still count as using a row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelOrphans {
+    pub categories: Vec<String>,
+    pub dates: Vec<String>,
+    pub descriptions: Vec<String>,
+    /// (converted, original) file names
+    pub photos: Vec<(String, String)>,
+}
+
+impl ModelOrphans {
+    /// Find, and delete, every orphaned row, if `dry_run` the deletions are rolled back
+    /// The photo files themselves aren't removed, that's left to the caller
+    pub async fn collect(postgres: &PgPool, dry_run: bool) -> Result<Self, ApiError> {
+        let mut transaction = postgres.begin().await?;
+
+        let categories = sqlx::query_scalar!(
+            "DELETE FROM meal_category mc WHERE NOT EXISTS (SELECT 1 FROM individual_meal im WHERE im.meal_category_id = mc.meal_category_id) RETURNING mc.category"
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let dates = sqlx::query_scalar!(
+            r#"DELETE FROM meal_date md WHERE NOT EXISTS (SELECT 1 FROM individual_meal im WHERE im.meal_date_id = md.meal_date_id) RETURNING md.date_of_meal::TEXT AS "date!""#
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let descriptions = sqlx::query_scalar!(
+            r#"DELETE FROM meal_description mde WHERE NOT EXISTS (SELECT 1 FROM individual_meal im WHERE im.meal_description_id = mde.meal_description_id) RETURNING mde.description AS "description!""#
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let photos = sqlx::query!(
+            "DELETE FROM meal_photo mp WHERE NOT EXISTS (SELECT 1 FROM individual_meal_photo imp WHERE imp.meal_photo_id = mp.meal_photo_id) RETURNING mp.photo_converted, mp.photo_original"
+        )
+        .fetch_all(&mut *transaction)
+        .await?
+        .into_iter()
+        .map(|i| (i.photo_converted, i.photo_original))
+        .collect();
+
+        if dry_run {
+            transaction.rollback().await?;
+        } else {
+            transaction.commit().await?;
+        }
+
+        let mut output = Self {
+            categories,
+            dates,
+            descriptions,
+            photos,
+        };
+        output.categories.sort();
+        output.dates.sort();
+        output.descriptions.sort();
+        output.photos.sort();
+        Ok(output)
+    }
+
+    /// Total number of orphaned rows
+    pub const fn len(&self) -> usize {
+        self.categories.len() + self.dates.len() + self.descriptions.len() + self.photos.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::{LazyLock, RwLock};
+
+use fred::{clients::Pool, interfaces::KeysInterface};
+use sqlx::PgPool;
+
+use crate::{C, api_error::ApiError, database::redis::RedisKey, servers::ij};
+
+use super::{Person, Visibility};
+
+/// In memory copy of the meal_person table, so that incoming data can be validated without a postgres query
+/// Every change to a person increases the people generation, so the copy is reloaded by `sync` once the generation moves on, even if the change was made by another instance
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(|| RwLock::new(Registry::default()));
+
+/// The registered people, and the people generation they were loaded at
+#[derive(Debug, Default)]
+struct Registry {
+    generation: Option<i64>,
+    people: Vec<ModelPerson>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelPerson {
+    pub meal_person_id: i64,
+    pub person: String,
+    pub initial: String,
+    pub visibility: String,
+    /// Email address of the user linked to the person, who is sent missing meal reminders
+    pub email: Option<String>,
+}
+
+impl ModelPerson {
+    /// Get all currently registered people, from memory
+    pub fn registry() -> Vec<Self> {
+        match REGISTRY.read() {
+            Ok(registry) => C!(registry.people),
+            Err(e) => C!(e.into_inner().people),
+        }
+    }
+
+    /// The people generation the in memory registry was loaded at
+    fn registry_generation() -> Option<i64> {
+        match REGISTRY.read() {
+            Ok(registry) => registry.generation,
+            Err(e) => e.into_inner().generation,
+        }
+    }
+
+    /// Modify the in memory registry
+    fn registry_write(f: impl FnOnce(&mut Registry)) {
+        match REGISTRY.write() {
+            Ok(mut registry) => f(&mut registry),
+            Err(e) => f(&mut e.into_inner()),
+        }
+    }
+
+    /// Find a registered person by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::registry().into_iter().find(|i| i.person == name)
+    }
+
+    /// Find a registered person by initial
+    pub fn from_initial(initial: &str) -> Option<Self> {
+        Self::registry().into_iter().find(|i| i.initial == initial)
+    }
+
+    /// The visibility of the person, unknown values are treated as admin only
+    pub fn visibility(&self) -> Visibility {
+        Visibility::try_from(self.visibility.as_str()).unwrap_or(Visibility::Admin)
+    }
+
+    /// Get all people from postgres
+    pub async fn get_all(postgres: &PgPool) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mp.meal_person_id,
+    mp.person,
+    mp.initial,
+    mp.visibility,
+    ru.email AS "email?"
+FROM
+    meal_person mp
+LEFT JOIN
+    registered_user ru ON ru.registered_user_id = mp.linked_user_id
+ORDER BY
+    mp.person"#
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// Refresh the in memory registry from postgres, should be called on startup, and after any change to the meal_person table
+    pub async fn load(postgres: &PgPool) -> Result<(), ApiError> {
+        let people = Self::get_all(postgres).await?;
+        Self::registry_write(|registry| registry.people = people);
+        Ok(())
+    }
+
+    /// Refresh the in memory registry from postgres, and tag it with the people generation it was loaded at
+    async fn load_generation(postgres: &PgPool, generation: Option<i64>) -> Result<(), ApiError> {
+        let people = Self::get_all(postgres).await?;
+        Self::registry_write(|registry| {
+            registry.people = people;
+            registry.generation = generation;
+        });
+        Ok(())
+    }
+
+    /// Refresh the in memory registry from postgres, if the people generation has moved on since it was last synced
+    /// The generation is read before the people, so a change made during the refresh causes another refresh on the next sync
+    pub async fn sync(postgres: &PgPool, redis: &Pool) -> Result<(), ApiError> {
+        let generation = redis
+            .get::<Option<i64>, String>(RedisKey::PeopleGeneration.to_string())
+            .await?;
+        if generation != Self::registry_generation() {
+            Self::load_generation(postgres, generation).await?;
+        }
+        Ok(())
+    }
+
+    /// Increase the people generation, so that every instance refreshes its registry, and refresh this instance's registry straight away
+    async fn changed(postgres: &PgPool, redis: &Pool) -> Result<(), ApiError> {
+        let generation = redis
+            .incr::<i64, String>(RedisKey::PeopleGeneration.to_string())
+            .await?;
+        Self::load_generation(postgres, Some(generation)).await
+    }
+
+    /// Check if a given person is currently attached to any individual meal
+    pub async fn in_use(postgres: &PgPool, person: &Person) -> Result<bool, ApiError> {
+        Ok(sqlx::query!(
+            "SELECT individual_meal_id FROM individual_meal im
+            JOIN meal_person mp USING (meal_person_id)
+            WHERE mp.person = $1",
+            person.to_string()
+        )
+        .fetch_optional(postgres)
+        .await?
+        .is_some())
+    }
+
+    pub async fn insert(
+        postgres: &PgPool,
+        redis: &Pool,
+        person: &ij::PersonPost,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "
+INSERT INTO
+    meal_person(person, initial, visibility, linked_user_id)
+VALUES
+    ($1, $2, $3, (SELECT registered_user_id FROM registered_user WHERE email = $4))",
+            person.person,
+            person.initial,
+            person.visibility.to_string(),
+            person.email
+        )
+        .execute(postgres)
+        .await?;
+        Self::changed(postgres, redis).await
+    }
+
+    pub async fn update(
+        postgres: &PgPool,
+        redis: &Pool,
+        original: &Person,
+        person: &ij::PersonPost,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "
+UPDATE
+    meal_person
+SET
+    person = $1,
+    initial = $2,
+    visibility = $3,
+    linked_user_id = (SELECT registered_user_id FROM registered_user WHERE email = $4),
+    visibility_audit_id = CASE
+        WHEN visibility <> $3 OR initial <> $2 THEN nextval(pg_get_serial_sequence('individual_meal_audit', 'individual_meal_audit_id'))
+        ELSE visibility_audit_id
+    END
+WHERE
+    person = $5",
+            person.person,
+            person.initial,
+            person.visibility.to_string(),
+            person.email,
+            original.to_string()
+        )
+        .execute(postgres)
+        .await?;
+        Self::changed(postgres, redis).await
+    }
+
+    /// Delete a person, should only be used if the person has no meals
+    pub async fn delete(postgres: &PgPool, redis: &Pool, person: &Person) -> Result<(), ApiError> {
+        sqlx::query!(
+            "DELETE FROM meal_person WHERE person = $1",
+            person.to_string()
+        )
+        .execute(postgres)
+        .await?;
+        Self::changed(postgres, redis).await
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::MealResponse,
+        servers::api_tests::{TEST_PERSON, TEST_PERSON_INITIAL, setup},
+    };
+
+    #[tokio::test]
+    /// A person inserted by another instance is only added to the registry once the people generation has been increased, a meals cache delete doesn't refresh the registry
+    async fn db_postgres_model_person_sync() {
+        let test_setup = setup().await;
+        ModelPerson::sync(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO meal_person(person, initial, visibility) VALUES($1, $2, 'admin')")
+            .bind(TEST_PERSON)
+            .bind(TEST_PERSON_INITIAL)
+            .execute(&test_setup.postgres)
+            .await
+            .unwrap();
+        MealResponse::cache_delete(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        ModelPerson::sync(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        assert!(ModelPerson::from_name(TEST_PERSON).is_none());
+
+        test_setup
+            .redis
+            .incr::<i64, String>(RedisKey::PeopleGeneration.to_string())
+            .await
+            .unwrap();
+        ModelPerson::sync(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        assert!(ModelPerson::from_name(TEST_PERSON).is_some());
+        assert!(ModelPerson::from_initial(TEST_PERSON_INITIAL).is_some());
+
+        test_setup.delete_test_person().await;
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, HashMap};
+
+use fred::{
+    clients::Pool,
+    interfaces::{HashesInterface, KeysInterface},
+};
+use sqlx::PgPool;
+
+use crate::{
+    C,
+    api_error::ApiError,
+    database::redis::{HASH_FIELD, RedisKey},
+    hmap,
+    servers::oj::{MealStats, PersonStats, RepeatGap, StatsSummary},
+};
+
+use super::{MealResponse, Visibility};
+
+/// The number of most repeated descriptions to include in each summary
+const TOP_DESCRIPTIONS: usize = 5;
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+struct ModelStatsMeal {
+    person: String,
+    date_of_meal: String,
+    /// Days since the unix epoch, to make streak and gap calculations simple
+    day: i32,
+    year: i32,
+    category: String,
+    description: String,
+    restaurant: bool,
+    takeaway: bool,
+    vegetarian: bool,
+}
+
+impl ModelStatsMeal {
+    /// Get all meals visible at the given visibility level, ordered by person, date, then slot
+    async fn get_all(postgres: &PgPool, visibility: Visibility) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mpe.person,
+    md.date_of_meal::TEXT AS "date_of_meal!",
+    (md.date_of_meal - DATE '1970-01-01') AS "day!",
+    EXTRACT(YEAR FROM md.date_of_meal)::INT AS "year!",
+    mc.category,
+    mde.description AS "description!",
+    COALESCE(im.restaurant, false) AS "restaurant!",
+    COALESCE(im.takeaway, false) AS "takeaway!",
+    COALESCE(im.vegetarian, false) AS "vegetarian!"
+FROM
+    individual_meal im
+JOIN
+    meal_date md USING(meal_date_id)
+JOIN
+    meal_category mc USING(meal_category_id)
+JOIN
+    meal_description mde USING(meal_description_id)
+JOIN
+    meal_person mpe USING(meal_person_id)
+WHERE
+    COALESCE(im.visibility, mpe.visibility) = ANY($1)
+    AND im.deleted_at IS NULL
+ORDER BY
+    mpe.person,
+    md.date_of_meal,
+    array_position(ARRAY['breakfast', 'lunch', 'dinner'], im.meal_slot)"#,
+            &visibility.allowed()
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+}
+
+/// Percentage of part in total, to two decimal places
+#[expect(clippy::cast_precision_loss)]
+fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 / total as f64 * 10_000.0).round() / 100.0
+}
+
+impl StatsSummary {
+    /// Generate a summary from date, then slot, ordered meals, all of the same person
+    fn from_meals(meals: &[&ModelStatsMeal]) -> Self {
+        let total = meals.len();
+        let count = |f: fn(&ModelStatsMeal) -> bool| meals.iter().filter(|i| f(i)).count();
+
+        let mut categories = HashMap::new();
+        let mut descriptions: HashMap<&str, Vec<&ModelStatsMeal>> = HashMap::new();
+        for meal in meals {
+            *categories.entry(C!(meal.category)).or_insert(0) += 1;
+            descriptions
+                .entry(&meal.description)
+                .or_default()
+                .push(meal);
+        }
+
+        let mut most_repeated = descriptions
+            .iter()
+            .filter(|(_, dates)| dates.len() > 1)
+            .map(|(description, dates)| ((*description).to_owned(), dates.len()))
+            .collect::<Vec<_>>();
+        most_repeated.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        most_repeated.truncate(TOP_DESCRIPTIONS);
+
+        let mut longest_repeat_gap: Option<RepeatGap> = None;
+        for (description, dates) in &descriptions {
+            for pair in dates.windows(2) {
+                let days = pair[1].day - pair[0].day;
+                if longest_repeat_gap.as_ref().is_none_or(|gap| {
+                    days > gap.days || days == gap.days && *description < gap.description.as_str()
+                }) {
+                    longest_repeat_gap = Some(RepeatGap {
+                        description: (*description).to_owned(),
+                        days,
+                        from: C!(pair[0].date_of_meal),
+                        to: C!(pair[1].date_of_meal),
+                    });
+                }
+            }
+        }
+
+        // A streak counts days, a day is only vegetarian if every meal that day is, and is broken by either a non-vegetarian day, or a day without a meal
+        let mut days: Vec<(i32, bool)> = vec![];
+        for meal in meals {
+            match days.last_mut() {
+                Some((day, vegetarian)) if *day == meal.day => *vegetarian &= meal.vegetarian,
+                _ => days.push((meal.day, meal.vegetarian)),
+            }
+        }
+        let mut longest_vegetarian_streak = 0;
+        let mut streak = 0;
+        let mut previous_day = None;
+        for (day, vegetarian) in days {
+            if !vegetarian {
+                streak = 0;
+            } else if previous_day.is_some_and(|previous| previous + 1 == day) {
+                streak += 1;
+            } else {
+                streak = 1;
+            }
+            previous_day = Some(day);
+            longest_vegetarian_streak = longest_vegetarian_streak.max(streak);
+        }
+
+        Self {
+            total,
+            categories,
+            restaurant: percentage(count(|i| i.restaurant), total),
+            takeaway: percentage(count(|i| i.takeaway), total),
+            vegetarian: percentage(count(|i| i.vegetarian), total),
+            most_repeated,
+            longest_vegetarian_streak,
+            longest_repeat_gap,
+        }
+    }
+}
+
+pub struct StatsResponse;
+
+impl StatsResponse {
+    /// Get the redis key for the stats data
+    fn key(visibility: Visibility) -> String {
+        RedisKey::Stats(visibility).to_string()
+    }
+
+    /// Check redis for stats cache, and return if present
+    async fn cache_get(
+        redis: &Pool,
+        visibility: Visibility,
+    ) -> Result<Option<MealStats>, ApiError> {
+        // A cache in an outdated format is treated as missing, and will be replaced
+        Ok(redis
+            .hget::<Option<String>, String, &str>(Self::key(visibility), HASH_FIELD)
+            .await?
+            .and_then(|r| serde_json::from_str(&r).ok()))
+    }
+
+    /// Insert stats cache, as long as the meals cache generation hasn't moved on since the stats were read
+    /// A cache delete after the insert has its generation checked again, and the insert is removed
+    async fn cache_insert(
+        redis: &Pool,
+        stats: &MealStats,
+        visibility: Visibility,
+        generation: Option<i64>,
+    ) -> Result<(), ApiError> {
+        if MealResponse::generation(redis).await? != generation {
+            return Ok(());
+        }
+        redis
+            .hset::<(), _, _>(Self::key(visibility), hmap!(serde_json::to_string(&stats)?))
+            .await?;
+        if MealResponse::generation(redis).await? != generation {
+            redis.del::<(), _>(Self::key(visibility)).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the stats for every person, all time and per year, of meals visible at the given visibility level
+    /// Will check cache first, if no cache, then inserts into cache
+    pub async fn get(
+        postgres: &PgPool,
+        redis: &Pool,
+        visibility: Visibility,
+    ) -> Result<MealStats, ApiError> {
+        if let Some(cache) = Self::cache_get(redis, visibility).await? {
+            return Ok(cache);
+        }
+        let generation = MealResponse::generation(redis).await?;
+        let meals = ModelStatsMeal::get_all(postgres, visibility).await?;
+
+        let mut people: BTreeMap<&str, Vec<&ModelStatsMeal>> = BTreeMap::new();
+        for meal in &meals {
+            people.entry(&meal.person).or_default().push(meal);
+        }
+
+        let stats = people
+            .into_iter()
+            .map(|(person, meals)| {
+                let mut years: BTreeMap<i32, Vec<&ModelStatsMeal>> = BTreeMap::new();
+                for meal in &meals {
+                    years.entry(meal.year).or_default().push(meal);
+                }
+                PersonStats {
+                    person: person.to_owned(),
+                    all: StatsSummary::from_meals(&meals),
+                    years: years
+                        .into_iter()
+                        .map(|(year, meals)| (year, StatsSummary::from_meals(&meals)))
+                        .collect(),
+                }
+            })
+            .collect::<MealStats>();
+
+        Self::cache_insert(redis, &stats, visibility, generation).await?;
+        Ok(stats)
+    }
+}
+
+/// cargo watch -q -c -w src/ -x 'test db_postgres_model_stats -- --test-threads=1 --nocapture'
+#[cfg(test)]
+#[expect(clippy::pedantic, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{S, servers::api_tests::setup};
+
+    fn meal(day: i32, description: &str, vegetarian: bool) -> ModelStatsMeal {
+        ModelStatsMeal {
+            person: S!("Jack"),
+            date_of_meal: format!("day {day}"),
+            day,
+            year: 2020,
+            category: S!("PASTA"),
+            description: description.to_owned(),
+            restaurant: day % 2 == 0,
+            takeaway: false,
+            vegetarian,
+        }
+    }
+
+    #[test]
+    fn db_postgres_model_stats_summary() {
+        let meals = [
+            meal(1, "a", true),
+            meal(2, "b", true),
+            meal(3, "a", true),
+            meal(4, "c", false),
+            meal(5, "b", true),
+            meal(7, "a", true),
+            meal(8, "d", true),
+            meal(20, "d", false),
+        ];
+        let meals = meals.iter().collect::<Vec<_>>();
+        let result = StatsSummary::from_meals(&meals);
+
+        assert_eq!(result.total, 8);
+        assert_eq!(result.categories.get("PASTA"), Some(&8));
+        assert_eq!(result.restaurant, 50.0);
+        assert_eq!(result.takeaway, 0.0);
+        assert_eq!(result.vegetarian, 75.0);
+        assert_eq!(
+            result.most_repeated,
+            vec![(S!("a"), 3), (S!("b"), 2), (S!("d"), 2)]
+        );
+        assert_eq!(result.longest_vegetarian_streak, 3);
+        let gap = result.longest_repeat_gap.unwrap();
+        assert_eq!(gap.description, "d");
+        assert_eq!(gap.days, 12);
+        assert_eq!(gap.from, "day 8");
+        assert_eq!(gap.to, "day 20");
+    }
+
+    #[test]
+    fn db_postgres_model_stats_summary_empty() {
+        let result = StatsSummary::from_meals(&[]);
+        assert_eq!(result.total, 0);
+        assert_eq!(result.vegetarian, 0.0);
+        assert!(result.most_repeated.is_empty());
+        assert_eq!(result.longest_vegetarian_streak, 0);
+        assert!(result.longest_repeat_gap.is_none());
+    }
+
+    #[test]
+    /// Every meal of a day must be vegetarian for the day to count towards a streak
+    fn db_postgres_model_stats_summary_streak_slots() {
+        let meals = [
+            meal(1, "a", true),
+            meal(2, "b", true),
+            meal(2, "c", true),
+            meal(3, "d", true),
+        ];
+        let meals = meals.iter().collect::<Vec<_>>();
+        assert_eq!(
+            StatsSummary::from_meals(&meals).longest_vegetarian_streak,
+            3
+        );
+
+        let meals = [
+            meal(1, "a", true),
+            meal(2, "b", false),
+            meal(2, "c", true),
+            meal(3, "d", true),
+        ];
+        let meals = meals.iter().collect::<Vec<_>>();
+        assert_eq!(
+            StatsSummary::from_meals(&meals).longest_vegetarian_streak,
+            1
+        );
+    }
+
+    #[tokio::test]
+    /// Stats read before a cache delete aren't left in the cache
+    async fn db_postgres_model_stats_cache_insert_interleaved_delete() {
+        let test_setup = setup().await;
+        let visibility = Visibility::Public;
+        let stats = MealStats::new();
+
+        let generation = MealResponse::generation(&test_setup.redis).await.unwrap();
+        MealResponse::cache_delete(&test_setup.postgres, &test_setup.redis)
+            .await
+            .unwrap();
+        StatsResponse::cache_insert(&test_setup.redis, &stats, visibility, generation)
+            .await
+            .unwrap();
+        assert!(
+            StatsResponse::cache_get(&test_setup.redis, visibility)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let generation = MealResponse::generation(&test_setup.redis).await.unwrap();
+        StatsResponse::cache_insert(&test_setup.redis, &stats, visibility, generation)
+            .await
+            .unwrap();
+        assert_eq!(
+            StatsResponse::cache_get(&test_setup.redis, visibility)
+                .await
+                .unwrap(),
+            Some(stats)
+        );
+    }
+}
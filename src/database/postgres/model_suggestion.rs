@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+use crate::{
+    api_error::ApiError,
+    servers::{ij, oj::MealSuggestion},
+};
+
+use super::Visibility;
+
+/// The number of dinners to suggest
+const SUGGESTIONS: usize = 10;
+
+/// A dish last eaten this many days ago, or longer, gets the full recency weight
+const RECENCY_DAYS: f64 = 365.0;
+
+const WEIGHT_RECENCY: f64 = 0.4;
+const WEIGHT_FREQUENCY: f64 = 0.3;
+const WEIGHT_CATEGORY: f64 = 0.2;
+const WEIGHT_VEGETARIAN: f64 = 0.1;
+
+/// A description that a person has eaten for dinner, the category and vegetarian flag are from the most recent time it was eaten
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+struct ModelDish {
+    description: String,
+    category: String,
+    vegetarian: bool,
+    times: i64,
+    last_eaten: String,
+    days_since: i32,
+}
+
+impl ModelDish {
+    /// Get every dinner dish of a person, visible at the given visibility level
+    async fn get_all(
+        postgres: &PgPool,
+        person: &str,
+        visibility: Visibility,
+    ) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mde.description AS "description!",
+    (ARRAY_AGG(mc.category ORDER BY md.date_of_meal DESC))[1] AS "category!",
+    (ARRAY_AGG(COALESCE(im.vegetarian, false) ORDER BY md.date_of_meal DESC))[1] AS "vegetarian!",
+    COUNT(*) AS "times!",
+    MAX(md.date_of_meal)::TEXT AS "last_eaten!",
+    (CURRENT_DATE - MAX(md.date_of_meal)) AS "days_since!"
+FROM
+    individual_meal im
+JOIN
+    meal_date md USING(meal_date_id)
+JOIN
+    meal_category mc USING(meal_category_id)
+JOIN
+    meal_description mde USING(meal_description_id)
+JOIN
+    meal_person mpe USING(meal_person_id)
+WHERE
+    mpe.person = $1
+    AND im.meal_slot = 'dinner'
+    AND im.deleted_at IS NULL
+    AND COALESCE(im.visibility, mpe.visibility) = ANY($2)
+GROUP BY
+    mde.meal_description_id"#,
+            person,
+            &visibility.allowed()
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+}
+
+/// Score every dish between 0 and 1, a dish scores higher the longer ago it was last eaten, the more often it was eaten,
+/// the more often its category is eaten, and the more often its vegetarian flag matches the person's other dinners
+#[expect(clippy::cast_precision_loss)]
+fn score_dishes(
+    dishes: Vec<ModelDish>,
+    exclude: &[String],
+    vegetarian: Option<bool>,
+) -> Vec<MealSuggestion> {
+    let total = dishes.iter().map(|i| i.times).sum::<i64>().max(1) as f64;
+    let max_times = dishes.iter().map(|i| i.times).max().unwrap_or(1) as f64;
+
+    let mut categories: HashMap<&str, i64> = HashMap::new();
+    for dish in &dishes {
+        *categories.entry(&dish.category).or_insert(0) += dish.times;
+    }
+    let max_category = categories.values().max().copied().unwrap_or(1) as f64;
+    let category_weights = categories
+        .into_iter()
+        .map(|(category, times)| (category.to_owned(), times as f64 / max_category))
+        .collect::<HashMap<_, _>>();
+
+    let vegetarian_share = dishes
+        .iter()
+        .filter(|i| i.vegetarian)
+        .map(|i| i.times)
+        .sum::<i64>() as f64
+        / total;
+
+    let mut suggestions = dishes
+        .into_iter()
+        .filter(|dish| {
+            vegetarian.is_none_or(|v| v == dish.vegetarian) && !exclude.contains(&dish.category)
+        })
+        .map(|dish| {
+            let recency = (f64::from(dish.days_since) / RECENCY_DAYS).clamp(0.0, 1.0);
+            let frequency = (dish.times as f64).ln_1p() / max_times.ln_1p();
+            let category = category_weights
+                .get(&dish.category)
+                .copied()
+                .unwrap_or_default();
+            let vegetarian = if dish.vegetarian {
+                vegetarian_share
+            } else {
+                1.0 - vegetarian_share
+            };
+            let score = WEIGHT_RECENCY.mul_add(
+                recency,
+                WEIGHT_FREQUENCY.mul_add(
+                    frequency,
+                    WEIGHT_CATEGORY.mul_add(category, WEIGHT_VEGETARIAN * vegetarian),
+                ),
+            );
+            MealSuggestion {
+                description: dish.description,
+                category: dish.category,
+                vegetarian: dish.vegetarian,
+                times: dish.times,
+                last_eaten: dish.last_eaten,
+                days_since: dish.days_since,
+                score: (score * 1000.0).round() / 1000.0,
+            }
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.description.cmp(&b.description))
+    });
+    suggestions.truncate(SUGGESTIONS);
+    suggestions
+}
+
+pub struct SuggestResponse;
+
+impl SuggestResponse {
+    /// Suggest dinners from a person's own history, of meals visible at the given visibility level, best first
+    pub async fn get(
+        postgres: &PgPool,
+        suggest: &ij::MealSuggest,
+        visibility: Visibility,
+    ) -> Result<Vec<MealSuggestion>, ApiError> {
+        let dishes = ModelDish::get_all(postgres, &suggest.person.to_string(), visibility).await?;
+        Ok(score_dishes(dishes, &suggest.exclude, suggest.vegetarian))
+    }
+}
+
+/// cargo watch -q -c -w src/ -x 'test db_postgres_model_suggestion -- --test-threads=1 --nocapture'
+#[cfg(test)]
+#[expect(clippy::pedantic)]
+mod tests {
+    use super::*;
+    use crate::S;
+
+    fn dish(
+        description: &str,
+        category: &str,
+        vegetarian: bool,
+        times: i64,
+        days_since: i32,
+    ) -> ModelDish {
+        ModelDish {
+            description: description.to_owned(),
+            category: category.to_owned(),
+            vegetarian,
+            times,
+            last_eaten: S!("2020-01-01"),
+            days_since,
+        }
+    }
+
+    fn dishes() -> Vec<ModelDish> {
+        vec![
+            dish("a", "PASTA", true, 10, 400),
+            dish("b", "PASTA", true, 10, 2),
+            dish("c", "CURRY", false, 1, 400),
+            dish("d", "PASTA", false, 1, 400),
+        ]
+    }
+
+    #[test]
+    fn db_postgres_model_suggestion_score() {
+        let result = score_dishes(dishes(), &[], None);
+        let descriptions = result
+            .iter()
+            .map(|i| i.description.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, ["a", "d", "b", "c"]);
+        assert_eq!(result[0].score, 0.991);
+        assert!(result.iter().all(|i| (0.0..=1.0).contains(&i.score)));
+    }
+
+    #[test]
+    fn db_postgres_model_suggestion_filter() {
+        let result = score_dishes(dishes(), &[S!("PASTA")], None);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].description, "c");
+
+        let result = score_dishes(dishes(), &[], Some(true));
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|i| i.vegetarian));
+
+        let result = score_dishes(
+            (0..20)
+                .map(|i| dish(&i.to_string(), "PASTA", true, 1, i))
+                .collect(),
+            &[],
+            None,
+        );
+        assert_eq!(result.len(), SUGGESTIONS);
+        assert_eq!(result[0].description, "19");
+
+        assert!(score_dishes(vec![], &[], None).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::{S, api_error::ApiError};
+
+use super::ModelUser;
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct ModelTag {
+    pub meal_tag_id: i64,
+    pub tag: String,
+    /// Number of meals using the tag
+    pub meals: i64,
+}
+
+impl ModelTag {
+    /// Get every tag, and the number of meals using it, ordered by tag
+    pub async fn get_all(postgres: &PgPool) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mt.meal_tag_id,
+    mt.tag,
+    COUNT(imt.individual_meal_id) AS "meals!"
+FROM
+    meal_tag mt
+LEFT JOIN
+    individual_meal_tag imt USING(meal_tag_id)
+GROUP BY
+    mt.meal_tag_id
+ORDER BY
+    mt.tag"#
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// Get a single tag by id
+    pub async fn get(postgres: &PgPool, meal_tag_id: i64) -> Result<Option<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mt.meal_tag_id,
+    mt.tag,
+    COUNT(imt.individual_meal_id) AS "meals!"
+FROM
+    meal_tag mt
+LEFT JOIN
+    individual_meal_tag imt USING(meal_tag_id)
+WHERE
+    mt.meal_tag_id = $1
+GROUP BY
+    mt.meal_tag_id"#,
+            meal_tag_id
+        )
+        .fetch_optional(postgres)
+        .await?)
+    }
+
+    /// Check if a tag name is already in use
+    pub async fn exists(postgres: &PgPool, tag: &str) -> Result<bool, ApiError> {
+        Ok(
+            sqlx::query_scalar!("SELECT meal_tag_id FROM meal_tag WHERE tag = $1", tag)
+                .fetch_optional(postgres)
+                .await?
+                .is_some(),
+        )
+    }
+
+    pub async fn insert(postgres: &PgPool, tag: &str, user: &ModelUser) -> Result<(), ApiError> {
+        sqlx::query!(
+            "INSERT INTO meal_tag(tag, registered_user_id) VALUES($1, $2)",
+            tag,
+            user.registered_user_id
+        )
+        .execute(postgres)
+        .await?;
+        Ok(())
+    }
+
+    /// Rename a tag, meals only reference the tag id, so are unaffected
+    pub async fn update(postgres: &PgPool, meal_tag_id: i64, tag: &str) -> Result<(), ApiError> {
+        sqlx::query!(
+            "UPDATE meal_tag SET tag = $1 WHERE meal_tag_id = $2",
+            tag,
+            meal_tag_id
+        )
+        .execute(postgres)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a tag, it is also removed from every meal that uses it
+    pub async fn delete(postgres: &PgPool, meal_tag_id: i64) -> Result<(), ApiError> {
+        sqlx::query!("DELETE FROM meal_tag WHERE meal_tag_id = $1", meal_tag_id)
+            .execute(postgres)
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the tags of a meal, every tag id must exist
+    pub async fn set_meal_tags(
+        transaction: &mut Transaction<'_, Postgres>,
+        individual_meal_id: i64,
+        tags: &[i64],
+    ) -> Result<(), ApiError> {
+        let known = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM meal_tag WHERE meal_tag_id = ANY($1)"#,
+            tags
+        )
+        .fetch_one(&mut **transaction)
+        .await?;
+        if usize::try_from(known).unwrap_or_default() != tags.len() {
+            return Err(ApiError::InvalidValue(S!("unknown tag")));
+        }
+        sqlx::query!(
+            "DELETE FROM individual_meal_tag WHERE individual_meal_id = $1 AND NOT (meal_tag_id = ANY($2))",
+            individual_meal_id,
+            tags
+        )
+        .execute(&mut **transaction)
+        .await?;
+        sqlx::query!(
+            "
+INSERT INTO
+    individual_meal_tag(individual_meal_id, meal_tag_id)
+SELECT
+    $1, UNNEST($2::BIGINT[])
+ON CONFLICT DO NOTHING",
+            individual_meal_id,
+            tags
+        )
+        .execute(&mut **transaction)
+        .await?;
+        Ok(())
+    }
+}
@@ -13,6 +13,7 @@ use crate::{
     servers::{ApiState, get_cookie_ulid},
 };
 
+#[expect(clippy::struct_excessive_bools)]
 #[derive(sqlx::FromRow, Debug, Clone, PartialEq, Eq)]
 pub struct ModelUser {
     pub registered_user_id: i64,
@@ -24,6 +25,7 @@ pub struct ModelUser {
     pub two_fa_always_required: bool,
     pub two_fa_backup_count: i64,
     pub admin: bool,
+    pub meal_reminder: bool,
     password_hash: String,
 }
 
@@ -42,6 +44,7 @@ impl ModelUser {
     ru.email,
     ru.password_hash,
     ru.full_name,
+    ru.meal_reminder,
     COALESCE(tfs.always_required, false) AS "two_fa_always_required!",
     COALESCE(au.admin, false) AS "admin!",
     COALESCE(la.login_attempt_number, 0) AS "login_attempt_number!",
@@ -108,6 +111,22 @@ VALUES
         .await?;
         Ok(())
     }
+
+    /// Enable, or disable, the missing meal reminder emails
+    pub async fn update_meal_reminder(
+        &self,
+        db: &PgPool,
+        meal_reminder: bool,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "UPDATE registered_user SET meal_reminder = $1 WHERE registered_user_id = $2",
+            meal_reminder,
+            self.registered_user_id
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
 }
 
 impl<S> FromRequestParts<S> for ModelUser
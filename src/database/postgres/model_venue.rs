@@ -0,0 +1,191 @@
+use sqlx::PgPool;
+
+use crate::{api_error::ApiError, servers::ij};
+
+use super::{ModelUser, Visibility};
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct ModelVenue {
+    pub meal_venue_id: i64,
+    pub name: String,
+    pub city: String,
+    pub venue_type: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Number of meals at the venue
+    pub meals: i64,
+}
+
+impl ModelVenue {
+    /// Get every venue, and the number of meals at it, ordered by city then name
+    pub async fn get_all(postgres: &PgPool) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mv.meal_venue_id,
+    mv.name,
+    mv.city,
+    mv.venue_type,
+    mv.latitude,
+    mv.longitude,
+    COUNT(im.individual_meal_id) AS "meals!"
+FROM
+    meal_venue mv
+LEFT JOIN
+    individual_meal im USING(meal_venue_id)
+GROUP BY
+    mv.meal_venue_id
+ORDER BY
+    mv.city,
+    mv.name"#
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+
+    /// Get a single venue by id
+    pub async fn get(postgres: &PgPool, meal_venue_id: i64) -> Result<Option<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mv.meal_venue_id,
+    mv.name,
+    mv.city,
+    mv.venue_type,
+    mv.latitude,
+    mv.longitude,
+    COUNT(im.individual_meal_id) AS "meals!"
+FROM
+    meal_venue mv
+LEFT JOIN
+    individual_meal im USING(meal_venue_id)
+WHERE
+    mv.meal_venue_id = $1
+GROUP BY
+    mv.meal_venue_id"#,
+            meal_venue_id
+        )
+        .fetch_optional(postgres)
+        .await?)
+    }
+
+    /// Get the id of the venue with the given name, in the given city
+    pub async fn get_id(
+        postgres: &PgPool,
+        name: &str,
+        city: &str,
+    ) -> Result<Option<i64>, ApiError> {
+        Ok(sqlx::query_scalar!(
+            "SELECT meal_venue_id FROM meal_venue WHERE name = $1 AND city = $2",
+            name,
+            city
+        )
+        .fetch_optional(postgres)
+        .await?)
+    }
+
+    pub async fn insert(
+        postgres: &PgPool,
+        venue: &ij::Venue,
+        user: &ModelUser,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "INSERT INTO meal_venue(name, city, venue_type, latitude, longitude, registered_user_id) VALUES($1, $2, $3, $4, $5, $6)",
+            venue.name,
+            venue.city,
+            venue.venue_type.to_string(),
+            venue.coordinates.map(|i| i.latitude),
+            venue.coordinates.map(|i| i.longitude),
+            user.registered_user_id
+        )
+        .execute(postgres)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update(
+        postgres: &PgPool,
+        meal_venue_id: i64,
+        venue: &ij::Venue,
+    ) -> Result<(), ApiError> {
+        sqlx::query!(
+            "UPDATE meal_venue SET name = $1, city = $2, venue_type = $3, latitude = $4, longitude = $5 WHERE meal_venue_id = $6",
+            venue.name,
+            venue.city,
+            venue.venue_type.to_string(),
+            venue.coordinates.map(|i| i.latitude),
+            venue.coordinates.map(|i| i.longitude),
+            meal_venue_id
+        )
+        .execute(postgres)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a venue, should only be called when no meals are at the venue
+    pub async fn delete(postgres: &PgPool, meal_venue_id: i64) -> Result<(), ApiError> {
+        sqlx::query!(
+            "DELETE FROM meal_venue WHERE meal_venue_id = $1",
+            meal_venue_id
+        )
+        .execute(postgres)
+        .await?;
+        Ok(())
+    }
+}
+
+/// A venue, with the number of visits, and the dates of the first and last visit, yyyy-mm-dd
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct ModelVenueVisits {
+    pub meal_venue_id: i64,
+    pub name: String,
+    pub city: String,
+    pub venue_type: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub visits: i64,
+    pub first_visit: String,
+    pub last_visit: String,
+}
+
+impl ModelVenueVisits {
+    /// Get every venue that has a meal visible at the given visibility level, ordered by most visited
+    /// Multiple people eating at a venue in the same meal slot is a single visit
+    pub async fn get(postgres: &PgPool, visibility: Visibility) -> Result<Vec<Self>, ApiError> {
+        Ok(sqlx::query_as!(
+            Self,
+            r#"
+SELECT
+    mv.meal_venue_id,
+    mv.name,
+    mv.city,
+    mv.venue_type,
+    mv.latitude,
+    mv.longitude,
+    COUNT(DISTINCT (md.date_of_meal, im.meal_slot)) AS "visits!",
+    MIN(md.date_of_meal)::TEXT AS "first_visit!",
+    MAX(md.date_of_meal)::TEXT AS "last_visit!"
+FROM
+    individual_meal im
+JOIN
+    meal_venue mv USING(meal_venue_id)
+JOIN
+    meal_date md USING(meal_date_id)
+JOIN
+    meal_person mpe USING(meal_person_id)
+WHERE
+    COALESCE(im.visibility, mpe.visibility) = ANY($1)
+    AND im.deleted_at IS NULL
+GROUP BY
+    mv.meal_venue_id
+ORDER BY
+    "visits!" DESC,
+    mv.name"#,
+            &visibility.allowed()
+        )
+        .fetch_all(postgres)
+        .await?)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{S, api_error::ApiError, parse_env::AppEnv};
+use crate::{S, api_error::ApiError, database::Visibility, parse_env::AppEnv};
 use fred::{clients::Pool, interfaces::ClientLike, prelude::ReconnectPolicy};
 use std::{fmt, net::IpAddr};
 use ulid::Ulid;
@@ -26,26 +26,32 @@ pub enum RedisKey<'a> {
     Session(&'a Ulid),
     SessionSet(i64),
     CacheUseragent(&'a str),
-    AllMealsHash,
-    AllMeals,
-    JackMealsHash,
-    JackMeals,
+    MealsGeneration,
+    MealsHash(Visibility),
+    MealsLock(Visibility),
+    Meals(Visibility),
+    PeopleGeneration,
+    PhotoVisibility,
+    Stats(Visibility),
     TwoFASetup(i64),
 }
 
 impl fmt::Display for RedisKey<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let disp = match self {
-            Self::AllMeals => S!("cache::all_meals"),
-            Self::AllMealsHash => S!("cache::all_meals_hash"),
             Self::CacheIp(ip) => format!("cache::ip::{ip}"),
             Self::CacheUseragent(useragent) => format!("cache::useragent::{useragent}"),
-            Self::JackMeals => S!("cache::jack_meals"),
-            Self::JackMealsHash => S!("cache::jack_meals_hash"),
+            Self::Meals(visibility) => format!("cache::meals::{visibility}"),
+            Self::MealsGeneration => S!("cache::meals_generation"),
+            Self::MealsHash(visibility) => format!("cache::meals_hash::{visibility}"),
+            Self::MealsLock(visibility) => format!("lock::meals::{visibility}"),
+            Self::PeopleGeneration => S!("cache::people_generation"),
+            Self::PhotoVisibility => S!("cache::photo_visibility"),
             Self::RateLimitEmail(email) => format!("ratelimit::email::{email}"),
             Self::RateLimitIp(ip) => format!("ratelimit::ip::{ip}"),
             Self::Session(ulid) => format!("session::{ulid}"),
             Self::SessionSet(id) => format!("session_set::user::{id}"),
+            Self::Stats(visibility) => format!("cache::stats::{visibility}"),
             Self::TwoFASetup(id) => format!("two_fa_setup::{id}"),
             Self::VerifyEmail(email) => format!("verify::email::{email}"),
             Self::VerifySecret(secret) => format!("verify::secret::{secret}"),
@@ -49,6 +49,8 @@ pub enum EmailTemplate {
     TwoFADisabled,
     TwoFABackupEnabled,
     TwoFABackupDisabled,
+    /// person, and the dates, newest first, they have no meal for
+    MissingMeals(String, Vec<String>),
     Custom(CustomEmail),
 }
 
@@ -72,6 +74,7 @@ impl EmailTemplate {
             Self::TwoFADisabled => S!("Two-Factor Disabled"),
             Self::TwoFABackupEnabled => S!("Two-Factor Backup Enabled"),
             Self::TwoFABackupDisabled => S!("Two-Factor Backup Disabled"),
+            Self::MissingMeals(..) => S!("Missing Meals"),
             Self::Custom(custom_email) => C!(custom_email.title),
         }
     }
@@ -116,6 +119,12 @@ impl EmailTemplate {
             Self::TwoFABackupEnabled => S!(
                 "You have created Two-Factor Authentication backup codes for your Meal Pedant account. The codes should be stored somewhere secure"
             ),
+            Self::MissingMeals(person, dates) => format!(
+                "{person} has {} missing meal{}: {}",
+                dates.len(),
+                if dates.len() == 1 { "" } else { "s" },
+                dates.join(", ")
+            ),
             Self::TwoFADisabled => {
                 S!("You have disabled Two-Factor Authentication for your Meal Pedant account.")
             }
@@ -142,6 +151,9 @@ impl EmailTemplate {
             Self::PasswordResetRequested(_) => Some(S!(
                 "If you did not request a password reset then please ignore this email"
             )),
+            Self::MissingMeals(..) => Some(S!(
+                "These reminders can be turned off in the user settings area."
+            )),
             Self::Custom(custom_email) => C!(custom_email.line_two),
             _ => None,
         }
@@ -418,6 +430,29 @@ mod tests {
         );
         assert!(result.contains(&link));
         assert!(result.contains("VERIFY EMAIL ADDRESS"));
+
+        let input = create_input(EmailTemplate::MissingMeals(
+            S!("Jack"),
+            vec![S!("2020-01-02"), S!("2020-01-01")],
+        ));
+        let result = create_template(&input, &app_env.domain);
+        // title
+        assert!(result.contains("Missing Meals"));
+        // name
+        assert!(result.contains("Hi john smith,"));
+        // line one
+        assert!(result.contains("Jack has 2 missing meals: 2020-01-02, 2020-01-01"));
+        // line two
+        assert!(result.contains("These reminders can be turned off in the user settings area."));
+        // no button
+        assert!(!result.contains("<mj-button"));
+
+        let input = create_input(EmailTemplate::MissingMeals(
+            S!("Jack"),
+            vec![S!("2020-01-01")],
+        ));
+        let result = create_template(&input, &app_env.domain);
+        assert!(result.contains("Jack has 1 missing meal: 2020-01-01"));
     }
 
     #[test]
@@ -51,6 +51,81 @@ pub fn xor(input_1: &[u8], input_2: &[u8]) -> bool {
         == 0
 }
 
+/// Parse a csv string into rows of fields, fields can be quoted, with a double quote escaped as "", and rows end with \n or \r\n
+/// Empty lines are skipped, returns None if a quoted field is never closed
+pub fn parse_csv(input: &str) -> Option<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => (),
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|i| !i.is_empty()) || row.len() > 1 {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Some(rows)
+}
+
+/// Create a csv row, ending with \r\n, fields containing a comma, quote, or newline are quoted, the inverse of `parse_csv`
+pub fn csv_row(fields: &[&str]) -> String {
+    let mut row = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                (*field).to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+/// Split an Accept, or Accept-Encoding, header into each lowercase value, and its quality weighting, which defaults to 1
+/// Values with an invalid weighting are ignored
+pub fn quality_values(header: &str) -> Vec<(String, f32)> {
+    header
+        .split(',')
+        .filter_map(|i| {
+            let mut params = i.split(';').map(str::trim);
+            let value = params
+                .next()
+                .filter(|i| !i.is_empty())?
+                .to_ascii_lowercase();
+            let q = params
+                .find_map(|p| p.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            Some((value, q))
+        })
+        .collect()
+}
+
 /// Check if a given password in is HIBP using K-Anonymity
 #[cfg(not(test))]
 pub async fn pwned_password(password: &str) -> Result<bool, ApiError> {
@@ -151,4 +226,50 @@ mod tests {
         let result = xor(s1.as_bytes(), s2.as_bytes());
         assert!(!result);
     }
+
+    #[test]
+    fn helpers_parse_csv() {
+        let result = parse_csv("a,b,c\n1,2,3\n").unwrap();
+        assert_eq!(result, vec![vec!["a", "b", "c"], vec!["1", "2", "3"]]);
+
+        // crlf, no trailing newline, and empty lines are skipped
+        let result = parse_csv("a,b\r\n\r\n1,\n\n,2").unwrap();
+        assert_eq!(result, vec![vec!["a", "b"], vec!["1", ""], vec!["", "2"]]);
+
+        // quoted fields, with commas, newlines, and escaped quotes
+        let result = parse_csv("\"a,b\",\"say \"\"hi\"\"\"\n\"line\nbreak\",c").unwrap();
+        assert_eq!(
+            result,
+            vec![vec!["a,b", "say \"hi\""], vec!["line\nbreak", "c"]]
+        );
+
+        assert!(parse_csv("").unwrap().is_empty());
+        assert!(parse_csv("a,\"b\n1,2").is_none());
+    }
+
+    #[test]
+    fn helpers_csv_row() {
+        assert_eq!(csv_row(&["a", "", "b"]), "a,,b\r\n");
+
+        let fields = ["a,b", "say \"hi\"", "line\nbreak", "plain"];
+        let result = csv_row(&fields);
+        assert_eq!(
+            result,
+            "\"a,b\",\"say \"\"hi\"\"\",\"line\nbreak\",plain\r\n"
+        );
+        assert_eq!(parse_csv(&result).unwrap(), vec![fields]);
+    }
+
+    #[test]
+    fn helpers_quality_values() {
+        assert!(quality_values("").is_empty());
+        assert_eq!(
+            quality_values("GZIP, br;q=0.5, deflate;q=x, *;q=0"),
+            vec![
+                ("gzip".to_owned(), 1.0),
+                ("br".to_owned(), 0.5),
+                ("*".to_owned(), 0.0)
+            ]
+        );
+    }
 }
@@ -15,7 +15,7 @@ mod servers;
 use api_error::ApiError;
 
 use parse_env::AppEnv;
-use scheduler::BackupSchedule;
+use scheduler::Scheduler;
 use servers::{api, static_serve};
 use tracing_subscriber::{fmt, prelude::__tracing_subscriber_SubscriberExt};
 
@@ -49,7 +49,8 @@ async fn spawned_main(app_env: AppEnv) -> Result<(), ApiError> {
     );
     let postgres = database::db_postgres::db_pool(&app_env).await?;
     let redis = database::DbRedis::get_pool(&app_env).await?;
-    BackupSchedule::init(&app_env);
+    database::ModelPerson::load(&postgres).await?;
+    Scheduler::init(&app_env, &postgres);
 
     let static_data = (C!(app_env), C!(postgres), C!(redis));
     tokio::spawn(async move {
@@ -13,8 +13,19 @@ enum EnvError {
     FileNotFound(String),
     #[error("'{0}' - cannot parse into number'")]
     IntParse(String),
+    #[error("'{0}' - cannot parse into time'")]
+    TimeParse(String),
 }
 
+/// Default hour and minute, UTC, to send missing meal reminders
+const REMINDER_TIME: (i8, i8) = (9, 0);
+
+/// Default number of days to look back for missing meals
+const REMINDER_DAYS: u16 = 30;
+
+/// Default number of days a deleted meal is kept in the trash, before being purged
+const TRASH_DAYS: u16 = 30;
+
 #[derive(Debug, Clone, Copy)]
 pub enum RunMode {
     Production,
@@ -82,10 +93,13 @@ pub struct AppEnv {
     pub redis_host: String,
     pub redis_password: String,
     pub redis_port: u16,
+    pub reminder_days: u16,
+    pub reminder_time: (i8, i8),
     pub run_mode: RunMode,
     pub start_time: SystemTime,
     pub static_host: String,
     pub static_port: u16,
+    pub trash_days: u16,
 }
 
 impl AppEnv {
@@ -116,6 +130,29 @@ impl AppEnv {
             })
     }
 
+    /// Parse an optional number, else return the default
+    fn parse_optional_number<T: TryFrom<u64> + std::str::FromStr>(
+        key: &str,
+        map: &EnvHashMap,
+        default: T,
+    ) -> Result<T, EnvError> {
+        if map.contains_key(key) {
+            Self::parse_number(key, map)
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// Parse an optional "hh:mm" time into an hour and minute, else return the default
+    fn parse_time(key: &str, map: &EnvHashMap, default: (i8, i8)) -> Result<(i8, i8), EnvError> {
+        map.get(key).map_or(Ok(default), |data| {
+            data.split_once(':')
+                .and_then(|(hour, minute)| Some((hour.parse().ok()?, minute.parse().ok()?)))
+                .filter(|(hour, minute)| (0..24).contains(hour) && (0..60).contains(minute))
+                .ok_or_else(|| EnvError::TimeParse(data.into()))
+        })
+    }
+
     fn parse_string(key: &str, map: &EnvHashMap) -> Result<String, EnvError> {
         map.get(key).map_or_else(
             || Err(EnvError::NotFound(key.into())),
@@ -209,10 +246,13 @@ impl AppEnv {
             redis_host: Self::parse_string("REDIS_HOST", &env_map)?,
             redis_password: Self::parse_string("REDIS_PASS", &env_map)?,
             redis_port: Self::parse_number("REDIS_PORT", &env_map)?,
+            reminder_days: Self::parse_optional_number("REMINDER_DAYS", &env_map, REMINDER_DAYS)?,
+            reminder_time: Self::parse_time("REMINDER_TIME", &env_map, REMINDER_TIME)?,
             run_mode: Self::parse_production(&env_map),
             start_time: SystemTime::now(),
             static_host: Self::parse_string("STATIC_HOST", &env_map)?,
             static_port: Self::parse_number("STATIC_PORT", &env_map)?,
+            trash_days: Self::parse_optional_number("TRASH_DAYS", &env_map, TRASH_DAYS)?,
         })
     }
 
@@ -494,6 +534,45 @@ mod tests {
         assert_eq!(result.unwrap_err(), EnvError::IntParse(S!("123456")));
     }
 
+    #[test]
+    fn env_parse_optional_number() {
+        // FIXTURES
+        let map = HashMap::from([(S!("valid"), S!("14")), (S!("invalid"), S!("fourteen"))]);
+
+        // ACTION
+        let result01 = AppEnv::parse_optional_number::<u16>("valid", &map, 30);
+        let result02 = AppEnv::parse_optional_number::<u16>("missing", &map, 30);
+        let result03 = AppEnv::parse_optional_number::<u16>("invalid", &map, 30);
+
+        // CHECK
+        assert_eq!(result01.unwrap(), 14);
+        assert_eq!(result02.unwrap(), 30);
+        assert_eq!(result03.unwrap_err(), EnvError::IntParse(S!("fourteen")));
+    }
+
+    #[test]
+    fn env_parse_time() {
+        // FIXTURES
+        let map = HashMap::from([
+            (S!("valid"), S!("18:30")),
+            (S!("hour"), S!("24:00")),
+            (S!("minute"), S!("12:60")),
+            (S!("format"), S!("1830")),
+        ]);
+
+        // ACTION
+        let result01 = AppEnv::parse_time("valid", &map, (9, 0));
+        let result02 = AppEnv::parse_time("missing", &map, (9, 0));
+
+        // CHECK
+        assert_eq!(result01.unwrap(), (18, 30));
+        assert_eq!(result02.unwrap(), (9, 0));
+        for key in ["hour", "minute", "format"] {
+            let result = AppEnv::parse_time(key, &map, (9, 0));
+            assert_eq!(result.unwrap_err(), EnvError::TimeParse(map[key].clone()));
+        }
+    }
+
     #[test]
     fn env_parse_boolean_ok() {
         // FIXTURES
@@ -39,6 +39,22 @@ impl PhotoLocationEnv {
             ij::PhotoName::Original(name) => PathBuf::from(&self.original).join(name),
         }
     }
+
+    /// Remove the converted and original files of each photo, a file that is already missing is ignored
+    pub async fn remove_photos(&self, photos: &[(String, String)]) -> Result<(), ApiError> {
+        for (converted, original) in photos {
+            for path in [
+                self.get_converted_path().join(converted),
+                self.get_original_path().join(original),
+            ] {
+                match tokio::fs::remove_file(path).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
 }
 // Need to look into this
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -49,7 +65,6 @@ pub struct PhotoConvertor {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Photo {
-    pub file_name: String,
     pub data: Bytes,
 }
 
@@ -62,14 +77,14 @@ impl PhotoConvertor {
         Ok(())
     }
     /// Generate a random file name for a photo,
-    /// 32 chars include .jpg, first 26 is a ulid, then 1/0 depending on person, then is 1/0 depending if original, finally .jpg
-    /// [ulid:26][Dave/Jack][Original,Converted].jpg
-    /// [ulid:26][0/1]      [0/1]               .jpg
-    fn generate_name(name: &str, original: bool) -> String {
+    /// 32 chars include .jpg, first 26 is a ulid, then a legacy 0, then is 1/0 depending if original, finally .jpg
+    /// The legacy char was the person's visibility, which is now only taken from the meals that use the photo, it's kept so that existing names remain valid
+    /// [ulid:26][Legacy][Original,Converted].jpg
+    /// [ulid:26][0]     [0/1]               .jpg
+    fn generate_name(original: bool) -> String {
         format!(
-            "{ulid}{person}{variant}.jpg",
+            "{ulid}0{variant}.jpg",
             ulid = ulid::Ulid::new().to_string().to_lowercase(),
-            person = i8::from(name != "D"),
             variant = i8::from(!original),
         )
     }
@@ -78,8 +93,8 @@ impl PhotoConvertor {
         original_photo: Photo,
         photo_env: &PhotoLocationEnv,
     ) -> Result<Self, ApiError> {
-        let original_file_name = Self::generate_name(&original_photo.file_name, true);
-        let converted_file_name = Self::generate_name(&original_photo.file_name, false);
+        let original_file_name = Self::generate_name(true);
+        let converted_file_name = Self::generate_name(false);
 
         Self::write_to_disk(
             PathBuf::from(&photo_env.original).join(&original_file_name),
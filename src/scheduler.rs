@@ -1,26 +1,95 @@
-use tracing::error;
+use sqlx::PgPool;
+use tracing::{error, info};
 
 use crate::{
     C,
-    database::backup::{BackupEnv, BackupType, create_backup},
+    database::{
+        ModelMealReminder, ModelMealTrash, ModelOrphans,
+        backup::{BackupEnv, BackupType, create_backup},
+    },
+    emailer::{Email, EmailTemplate, EmailerEnv},
     helpers::now_utc,
     parse_env::AppEnv,
+    photo_convertor::PhotoLocationEnv,
     sleep,
 };
 
-pub struct BackupSchedule {
+pub struct Scheduler {
     backup_env: BackupEnv,
+    email_env: EmailerEnv,
+    photo_env: PhotoLocationEnv,
+    postgres: PgPool,
+    reminder_days: u16,
+    reminder_time: (i8, i8),
+    trash_days: u16,
 }
 
-impl BackupSchedule {
-    /// In it's own tokio thread, start a backup schedule loop
-    pub fn init(app_env: &AppEnv) {
+impl Scheduler {
+    /// In it's own tokio thread, start a backup, meal reminder, trash purge, and orphan collection schedule loop
+    pub fn init(app_env: &AppEnv, postgres: &PgPool) {
         let inner = Self {
             backup_env: BackupEnv::new(app_env),
+            email_env: EmailerEnv::new(app_env),
+            photo_env: PhotoLocationEnv::new(app_env),
+            postgres: C!(postgres),
+            reminder_days: app_env.reminder_days,
+            reminder_time: app_env.reminder_time,
+            trash_days: app_env.trash_days,
         };
         tokio::spawn(async move { inner.start().await });
     }
 
+    /// Email every linked user a list of their person's missing meals
+    async fn send_reminders(postgres: PgPool, email_env: EmailerEnv, days: u16) {
+        match ModelMealReminder::get(&postgres, days).await {
+            Ok(reminders) => {
+                for reminder in reminders {
+                    Email::new(
+                        &reminder.full_name,
+                        &reminder.email,
+                        EmailTemplate::MissingMeals(reminder.person, reminder.missing_dates),
+                        &email_env,
+                    )
+                    .send();
+                }
+            }
+            Err(e) => error!("meal reminders: {e}"),
+        }
+    }
+
+    /// Permanently delete every meal, and its photo files, that has been in the trash for longer than the given number of days
+    async fn purge_trash(postgres: PgPool, photo_env: PhotoLocationEnv, days: u16) {
+        match ModelMealTrash::purge_expired(&postgres, days).await {
+            Ok(photos) => {
+                if let Err(e) = photo_env.remove_photos(&photos).await {
+                    error!("trash purge photos: {e}");
+                }
+            }
+            Err(e) => error!("trash purge: {e}"),
+        }
+    }
+
+    /// Remove every category, date, description, and photo, that is no longer used by any meal
+    async fn collect_orphans(postgres: PgPool, photo_env: PhotoLocationEnv) {
+        match ModelOrphans::collect(&postgres, false).await {
+            Ok(orphans) => {
+                if let Err(e) = photo_env.remove_photos(&orphans.photos).await {
+                    error!("orphan photos: {e}");
+                }
+                if !orphans.is_empty() {
+                    info!(
+                        "orphans removed: {} categories, {} dates, {} descriptions, {} photos",
+                        orphans.categories.len(),
+                        orphans.dates.len(),
+                        orphans.descriptions.len(),
+                        orphans.photos.len()
+                    );
+                }
+            }
+            Err(e) => error!("orphan collection: {e}"),
+        }
+    }
+
     /// the actual loop, check every minute
     async fn start(&self) {
         // Wait until the current time ends in 0 (i.e. exactly on the minute), before starting the loop
@@ -49,8 +118,27 @@ impl BackupSchedule {
                         }
                     });
                 }
+                (4, 10) => {
+                    tokio::spawn(Self::purge_trash(
+                        C!(self.postgres),
+                        C!(self.photo_env),
+                        self.trash_days,
+                    ));
+                }
+                // After the trash purge, which can leave orphans behind
+                (4, 15) => {
+                    tokio::spawn(Self::collect_orphans(C!(self.postgres), C!(self.photo_env)));
+                }
                 _ => (),
             }
+            // Not part of the match, as the reminder time is configurable, and so may clash with a backup
+            if current == self.reminder_time {
+                tokio::spawn(Self::send_reminders(
+                    C!(self.postgres),
+                    C!(self.email_env),
+                    self.reminder_days,
+                ));
+            }
             sleep!(60 * 1000);
         }
     }
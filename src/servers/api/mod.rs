@@ -11,7 +11,7 @@ use crate::{
     C, S,
     api_error::ApiError,
     parse_env::{AppEnv, RunMode},
-    servers::{get_addr, rate_limiting, shutdown_signal},
+    servers::{get_addr, negotiate_format, rate_limiting, shutdown_signal, sync_people},
 };
 
 use super::ApiState;
@@ -70,7 +70,10 @@ pub async fn serve(app_env: AppEnv, postgres: PgPool, redis: Pool) -> Result<(),
             axum::http::header::CACHE_CONTROL,
             axum::http::header::CONTENT_LANGUAGE,
             axum::http::header::CONTENT_TYPE,
+            axum::http::header::IF_MATCH,
+            axum::http::header::IF_NONE_MATCH,
         ])
+        .expose_headers([axum::http::header::ETAG])
         .allow_origin(
             cors_url
                 .parse::<HeaderValue>()
@@ -97,9 +100,14 @@ pub async fn serve(app_env: AppEnv, postgres: PgPool, redis: Pool) -> Result<(),
             ServiceBuilder::new()
                 .layer(cors)
                 .layer(Extension(C!(application_state.cookie_key)))
+                .layer(middleware::from_fn(negotiate_format))
                 .layer(middleware::from_fn_with_state(
-                    application_state,
+                    C!(application_state),
                     rate_limiting,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    application_state,
+                    sync_people,
                 )),
         );
     let addr = get_addr(&app_env.api_host, app_env.api_port)?;
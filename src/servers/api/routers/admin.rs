@@ -4,10 +4,11 @@ use axum::{
     extract::State,
     http::{StatusCode, header},
     middleware,
-    response::{AppendHeaders, IntoResponse},
-    routing::{delete, get, put},
+    response::{AppendHeaders, IntoResponse, Response},
+    routing::{delete, get, post, put},
 };
 use axum_extra::extract::PrivateCookieJar;
+use sqlx::PgPool;
 use std::{collections::HashMap, os::unix::fs::MetadataExt, time::SystemTime};
 use tokio_util::io::ReaderStream;
 
@@ -15,8 +16,9 @@ use crate::{
     C, S,
     api_error::ApiError,
     database::{
-        MealResponse, ModelPasswordReset, ModelUser, ModelUserAgentIp, RateLimit, RedisSession,
-        admin_queries,
+        DESCRIPTION_SIMILARITY, FromModel, MealResponse, ModelCategory, ModelDescription,
+        ModelMealTrash, ModelOrphans, ModelPasswordReset, ModelPerson, ModelTag, ModelUser,
+        ModelUserAgentIp, ModelVenue, RateLimit, RedisSession, Visibility, admin_queries,
         backup::{BackupType, create_backup},
     },
     define_routes,
@@ -26,6 +28,7 @@ use crate::{
         Outgoing,
         api::{ApiRouter, ApiState},
         authentication::{authenticate_password_token, is_admin},
+        export::export_response,
         get_cookie_ulid,
         ij::{self, Path, PhotoName},
         oj::{self, AdminPhoto},
@@ -77,14 +80,28 @@ define_routes! {
     Backup => "/backup",
     BackupParam => "/backup/{file_name}",
     Cache => "/cache",
+    Category => "/category",
+    CategoryMerge => "/category/merge",
+    DescriptionMerge => "/description/merge",
+    DescriptionSimilar => "/description/similar",
     Email => "/email",
+    Export => "/export",
     Limit => "/limit",
     Logs => "/logs",
     Memory => "/memory",
+    Orphans => "/orphans",
+    Person => "/person",
+    PersonParam => "/person/{param}",
     Photo => "/photo",
     PhotoParam => "/photo/{file_name}",
     Restart => "/restart",
+    Tag => "/tag",
+    TagParam => "/tag/{param}",
+    Trash => "/trash",
+    TrashParam => "/trash/{param}",
     User => "/user",
+    Venue => "/venue",
+    VenueParam => "/venue/{param}",
     SessionParam => "/session/{param}"
 }
 
@@ -106,22 +123,75 @@ impl ApiRouter for AdminRouter {
                     .post(Self::backup_post),
             )
             .route(&AdminRoutes::Cache.addr(), delete(Self::cache_delete))
+            .route(
+                &AdminRoutes::Category.addr(),
+                get(Self::category_get).patch(Self::category_patch),
+            )
+            .route(
+                &AdminRoutes::CategoryMerge.addr(),
+                post(Self::category_merge_post),
+            )
+            .route(
+                &AdminRoutes::DescriptionMerge.addr(),
+                post(Self::description_merge_post),
+            )
+            .route(
+                &AdminRoutes::DescriptionSimilar.addr(),
+                get(Self::description_similar_get),
+            )
             .route(
                 &AdminRoutes::Email.addr(),
                 get(Self::email_get).post(Self::email_post),
             )
+            .route(&AdminRoutes::Export.addr(), get(Self::export_get))
             .route(
                 &AdminRoutes::Limit.addr(),
                 delete(Self::limit_delete).get(Self::limit_get),
             )
             .route(&AdminRoutes::Logs.addr(), get(Self::logs_get))
             .route(&AdminRoutes::Memory.addr(), get(Self::memory_get))
+            .route(&AdminRoutes::Orphans.addr(), post(Self::orphans_post))
+            .route(
+                &AdminRoutes::Person.addr(),
+                get(Self::person_get)
+                    .patch(Self::person_patch)
+                    .post(Self::person_post),
+            )
+            .route(
+                &AdminRoutes::PersonParam.addr(),
+                delete(Self::person_param_delete),
+            )
             .route(&AdminRoutes::Photo.addr(), get(Self::photo_get))
             .route(
                 &AdminRoutes::PhotoParam.addr(),
                 delete(Self::photo_param_delete),
             )
             .route(&AdminRoutes::Restart.addr(), put(Self::restart_put))
+            .route(
+                &AdminRoutes::Tag.addr(),
+                get(Self::tag_get)
+                    .patch(Self::tag_patch)
+                    .post(Self::tag_post),
+            )
+            .route(
+                &AdminRoutes::TagParam.addr(),
+                delete(Self::tag_param_delete),
+            )
+            .route(&AdminRoutes::Trash.addr(), get(Self::trash_get))
+            .route(
+                &AdminRoutes::TrashParam.addr(),
+                delete(Self::trash_param_delete).put(Self::trash_param_put),
+            )
+            .route(
+                &AdminRoutes::Venue.addr(),
+                get(Self::venue_get)
+                    .patch(Self::venue_patch)
+                    .post(Self::venue_post),
+            )
+            .route(
+                &AdminRoutes::VenueParam.addr(),
+                delete(Self::venue_param_delete),
+            )
             .route(
                 &AdminRoutes::SessionParam.addr(),
                 delete(Self::session_param_delete).get(Self::session_param_get),
@@ -213,11 +283,125 @@ impl AdminRouter {
         Ok((headers, body).into_response())
     }
 
+    /// Get every category, and how many meals use it
+    async fn category_get(
+        State(state): State<ApiState>,
+    ) -> Result<Outgoing<Vec<oj::AdminCategory>>, ApiError> {
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelCategory::get_all(&state.postgres)
+                    .await?
+                    .into_iter()
+                    .map(oj::AdminCategory::from)
+                    .collect(),
+            ),
+        ))
+    }
+
+    /// Rename a category, use merge if the new name is already a category
+    async fn category_patch(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::IncomingJson(body): ij::IncomingJson<ij::CategoryPatch>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if body.original_category == body.category {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        let Some(category) = ModelCategory::get(&state.postgres, &body.original_category).await?
+        else {
+            return Err(ApiError::InvalidValue(S!("unknown category")));
+        };
+        if ModelCategory::get(&state.postgres, &body.category)
+            .await?
+            .is_some()
+        {
+            return Err(ApiError::InvalidValue(S!("Category already exists")));
+        }
+        ModelCategory::rename(&state.postgres, &category, &body.category, &user).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Move every meal of one category into another, the emptied category is removed
+    async fn category_merge_post(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::IncomingJson(body): ij::IncomingJson<ij::CategoryMerge>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if body.from == body.into {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        let (Some(from), Some(into)) = (
+            ModelCategory::get(&state.postgres, &body.from).await?,
+            ModelCategory::get(&state.postgres, &body.into).await?,
+        ) else {
+            return Err(ApiError::InvalidValue(S!("unknown category")));
+        };
+        ModelCategory::merge(&state.postgres, &from, &into, &user).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Get clusters of similar descriptions, each cluster ordered by most used
+    async fn description_similar_get(
+        State(state): State<ApiState>,
+        ij::Query(query): ij::Query<ij::DescriptionSimilar>,
+    ) -> Result<Outgoing<Vec<Vec<oj::AdminDescription>>>, ApiError> {
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelDescription::similar(
+                    &state.postgres,
+                    query.threshold.unwrap_or(DESCRIPTION_SIMILARITY),
+                )
+                .await?
+                .into_iter()
+                .map(|i| i.into_iter().map(oj::AdminDescription::from).collect())
+                .collect(),
+            ),
+        ))
+    }
+
+    /// Merge descriptions into a single description, every meal using one of the descriptions is updated
+    async fn description_merge_post(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::IncomingJson(body): ij::IncomingJson<ij::DescriptionMerge>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let descriptions = ModelDescription::get_many(&state.postgres, &body.descriptions).await?;
+        if body
+            .descriptions
+            .iter()
+            .any(|i| !descriptions.iter().any(|d| &d.description == i))
+        {
+            return Err(ApiError::InvalidValue(S!("unknown description")));
+        }
+        if descriptions
+            .iter()
+            .all(|i| i.description == body.description)
+        {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        ModelDescription::merge(&state.postgres, &body.description, &descriptions, &user).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Download every meal, including admin only meals, as csv, json lines, or an iCalendar
+    #[expect(clippy::unused_async)]
+    async fn export_get(
+        State(state): State<ApiState>,
+        ij::Query(export): ij::Query<ij::MealExport>,
+    ) -> Result<Response, ApiError> {
+        export_response(&state.postgres, &state.domain, export, Visibility::Admin)
+    }
+
     /// Delete and renew the meals cache's
     async fn cache_delete(
         State(state): State<ApiState>,
     ) -> Result<axum::http::StatusCode, ApiError> {
-        MealResponse::cache_delete(&state.redis).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
         Ok(axum::http::StatusCode::OK)
     }
 
@@ -296,6 +480,114 @@ impl AdminRouter {
             }),
         ))
     }
+
+    /// Remove every category, date, description, and photo, that is no longer used by any meal, and report what was removed
+    async fn orphans_post(
+        State(state): State<ApiState>,
+        ij::IncomingJson(body): ij::IncomingJson<ij::OrphanPost>,
+    ) -> Result<Outgoing<oj::Orphans>, ApiError> {
+        let orphans = ModelOrphans::collect(&state.postgres, body.dry_run).await?;
+        if !body.dry_run {
+            state.photo_env.remove_photos(&orphans.photos).await?;
+        }
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(oj::Orphans::from_model(orphans, body.dry_run)),
+        ))
+    }
+
+    /// Get all people
+    async fn person_get(
+        State(state): State<ApiState>,
+    ) -> Result<Outgoing<Vec<oj::AdminPerson>>, ApiError> {
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelPerson::get_all(&state.postgres)
+                    .await?
+                    .into_iter()
+                    .map(oj::AdminPerson::from)
+                    .collect(),
+            ),
+        ))
+    }
+
+    /// Check that the user a person is linked to, if any, exists
+    async fn person_linked_user(
+        postgres: &PgPool,
+        person: &ij::PersonPost,
+    ) -> Result<(), ApiError> {
+        if let Some(email) = person.email.as_ref()
+            && ModelUser::get(postgres, email).await?.is_none()
+        {
+            return Err(ApiError::InvalidValue(S!("unknown user")));
+        }
+        Ok(())
+    }
+
+    /// Update a person's name, initial, visibility, or linked user
+    async fn person_patch(
+        State(state): State<ApiState>,
+        ij::IncomingJson(body): ij::IncomingJson<ij::PersonPatch>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let original_name = body.original_person.to_string();
+        let people = ModelPerson::registry();
+        if people.iter().any(|i| {
+            i.person == original_name
+                && i.person == body.person.person
+                && i.initial == body.person.initial
+                && i.visibility() == body.person.visibility
+                && i.email == body.person.email
+        }) {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        if people.iter().any(|i| {
+            i.person != original_name
+                && (i.person == body.person.person || i.initial == body.person.initial)
+        }) {
+            return Err(ApiError::InvalidValue(S!("Person already exists")));
+        }
+        Self::person_linked_user(&state.postgres, &body.person).await?;
+        ModelPerson::update(
+            &state.postgres,
+            &state.redis,
+            &body.original_person,
+            &body.person,
+        )
+        .await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Insert a new person
+    async fn person_post(
+        State(state): State<ApiState>,
+        ij::IncomingJson(body): ij::IncomingJson<ij::PersonPost>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if ModelPerson::from_name(&body.person).is_some()
+            || ModelPerson::from_initial(&body.initial).is_some()
+        {
+            return Err(ApiError::InvalidValue(S!("Person already exists")));
+        }
+        Self::person_linked_user(&state.postgres, &body).await?;
+        ModelPerson::insert(&state.postgres, &state.redis, &body).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Delete a person, as long as they have no meals
+    async fn person_param_delete(
+        State(state): State<ApiState>,
+        ij::Path(ij::PersonParam { param }): ij::Path<ij::PersonParam>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if ModelPerson::in_use(&state.postgres, &param).await? {
+            return Err(ApiError::InvalidValue(S!("Person in use")));
+        }
+        ModelPerson::delete(&state.postgres, &state.redis, &param).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
     /// Get a vec of all photos and their matching meals
     async fn photo_get(
         State(state): State<ApiState>,
@@ -440,6 +732,184 @@ impl AdminRouter {
         ))
     }
 
+    /// Get every tag, and how many meals use it
+    async fn tag_get(
+        State(state): State<ApiState>,
+    ) -> Result<Outgoing<Vec<oj::AdminTag>>, ApiError> {
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelTag::get_all(&state.postgres)
+                    .await?
+                    .into_iter()
+                    .map(oj::AdminTag::from)
+                    .collect(),
+            ),
+        ))
+    }
+
+    /// Rename a tag
+    async fn tag_patch(
+        State(state): State<ApiState>,
+        ij::IncomingJson(body): ij::IncomingJson<ij::TagPatch>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let Some(tag) = ModelTag::get(&state.postgres, body.meal_tag_id).await? else {
+            return Err(ApiError::InvalidValue(S!("unknown tag")));
+        };
+        if tag.tag == body.tag {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        if ModelTag::exists(&state.postgres, &body.tag).await? {
+            return Err(ApiError::InvalidValue(S!("Tag already exists")));
+        }
+        ModelTag::update(&state.postgres, tag.meal_tag_id, &body.tag).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Insert a new tag
+    async fn tag_post(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::IncomingJson(body): ij::IncomingJson<ij::TagPost>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if ModelTag::exists(&state.postgres, &body.tag).await? {
+            return Err(ApiError::InvalidValue(S!("Tag already exists")));
+        }
+        ModelTag::insert(&state.postgres, &body.tag, &user).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Delete a tag, and remove it from every meal
+    async fn tag_param_delete(
+        State(state): State<ApiState>,
+        ij::Path(ij::TagParam { param }): ij::Path<ij::TagParam>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if ModelTag::get(&state.postgres, param).await?.is_none() {
+            return Err(ApiError::InvalidValue(S!("unknown tag")));
+        }
+        ModelTag::delete(&state.postgres, param).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Get every venue, and how many meals were eaten at it
+    async fn venue_get(
+        State(state): State<ApiState>,
+    ) -> Result<Outgoing<Vec<oj::AdminVenue>>, ApiError> {
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelVenue::get_all(&state.postgres)
+                    .await?
+                    .into_iter()
+                    .map(oj::AdminVenue::from)
+                    .collect(),
+            ),
+        ))
+    }
+
+    /// Update a venue, the name + city combination must be unique
+    async fn venue_patch(
+        State(state): State<ApiState>,
+        ij::IncomingJson(body): ij::IncomingJson<ij::VenuePatch>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let Some(venue) = ModelVenue::get(&state.postgres, body.meal_venue_id).await? else {
+            return Err(ApiError::InvalidValue(S!("unknown venue")));
+        };
+        if ij::Venue::from_model(&venue)? == body.venue {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        if ModelVenue::get_id(&state.postgres, &body.venue.name, &body.venue.city)
+            .await?
+            .is_some_and(|id| id != venue.meal_venue_id)
+        {
+            return Err(ApiError::InvalidValue(S!("Venue already exists")));
+        }
+        ModelVenue::update(&state.postgres, venue.meal_venue_id, &body.venue).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Insert a new venue
+    async fn venue_post(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::IncomingJson(body): ij::IncomingJson<ij::Venue>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if ModelVenue::get_id(&state.postgres, &body.name, &body.city)
+            .await?
+            .is_some()
+        {
+            return Err(ApiError::InvalidValue(S!("Venue already exists")));
+        }
+        ModelVenue::insert(&state.postgres, &body, &user).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Delete a venue, only possible if no meals were eaten at it
+    async fn venue_param_delete(
+        State(state): State<ApiState>,
+        ij::Path(ij::VenueParam { param }): ij::Path<ij::VenueParam>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let Some(venue) = ModelVenue::get(&state.postgres, param).await? else {
+            return Err(ApiError::InvalidValue(S!("unknown venue")));
+        };
+        if venue.meals > 0 {
+            return Err(ApiError::InvalidValue(S!("Venue in use")));
+        }
+        ModelVenue::delete(&state.postgres, param).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Get every meal in the trash, most recently deleted first
+    async fn trash_get(
+        State(state): State<ApiState>,
+    ) -> Result<Outgoing<Vec<oj::TrashMeal>>, ApiError> {
+        Ok((
+            StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelMealTrash::get_all(&state.postgres)
+                    .await?
+                    .into_iter()
+                    .map(oj::TrashMeal::from)
+                    .collect(),
+            ),
+        ))
+    }
+
+    /// Restore a meal from the trash, only possible if its date, person, and slot is still free
+    async fn trash_param_put(
+        State(state): State<ApiState>,
+        ij::Path(ij::TrashParam { param }): ij::Path<ij::TrashParam>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let Some(meal) = ModelMealTrash::get_by_id(&state.postgres, param).await? else {
+            return Err(ApiError::InvalidValue(S!("unknown meal")));
+        };
+        meal.restore(&state.postgres).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(StatusCode::OK)
+    }
+
+    /// Permanently delete a meal from the trash, requires password/token
+    async fn trash_param_delete(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Path(ij::TrashParam { param }): ij::Path<ij::TrashParam>,
+        ij::IncomingJson(body): ij::IncomingJson<ij::PasswordToken>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        if !authenticate_password_token(&user, &body.password, body.token, &state.postgres).await? {
+            return Err(ApiError::Authorization);
+        }
+        let Some(meal) = ModelMealTrash::get_by_id(&state.postgres, param).await? else {
+            return Err(ApiError::InvalidValue(S!("unknown meal")));
+        };
+        state
+            .photo_env
+            .remove_photos(&meal.purge(&state.postgres).await?)
+            .await?;
+        Ok(StatusCode::OK)
+    }
+
     /// Get big array of users
     async fn user_get(
         State(state): State<ApiState>,
@@ -531,17 +1001,22 @@ mod tests {
     use crate::{
         C, S,
         database::{
-            ModelPasswordReset, admin_queries,
+            MealSlot, ModelCategory, ModelDescription, ModelMeal, ModelMealReminder,
+            ModelPasswordReset, ModelPerson, Person, VenueType, Visibility, admin_queries,
             backup::{BackupEnv, BackupType, create_backup},
         },
-        helpers::gen_random_hex,
+        helpers::{gen_random_hex, now_utc},
         parse_env::AppEnv,
         servers::{
             api_tests::{
                 ANON_EMAIL, ANON_FULL_NAME, Response, TEST_EMAIL, TEST_FULL_NAME, TEST_PASSWORD,
-                base_url, start_both_servers,
+                TEST_PERSON, TEST_PERSON_INITIAL, TestSetup, base_url, start_both_servers,
+            },
+            ij::{
+                AdminUserPatch, CategoryMerge, CategoryPatch, Coordinates, DescriptionMerge,
+                EmailPost, PersonPatch, PersonPost, TagPatch, TagPost, UserPatch, Venue,
+                VenuePatch,
             },
-            ij::{AdminUserPatch, EmailPost, UserPatch},
         },
         sleep, tmp_file,
     };
@@ -1067,12 +1542,12 @@ mod tests {
         let result = result.json::<Response>().await.unwrap().response;
         assert_eq!(result, "Invalid Authentication");
         let all_meals_cache: Option<String> =
-            test_setup.redis.get("cache::all_meals").await.unwrap();
+            test_setup.redis.get("cache::meals::admin").await.unwrap();
         assert!(all_meals_cache.is_none());
     }
 
     #[tokio::test]
-    /// Delete all food caches, redis keys no longer there
+    /// Delete all food caches, redis keys no longer there, and the meals caches are re-warmed in the background
     async fn api_router_food_cache_admin_valid() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
@@ -1092,9 +1567,19 @@ mod tests {
             .unwrap();
         assert_eq!(result.status(), StatusCode::OK);
 
-        let all_meals_cache: Option<String> =
-            test_setup.redis.get("cache::all_meals").await.unwrap();
-        assert!(all_meals_cache.is_none());
+        test_setup.warmed_meals_cache().await;
+        for visibility in ["public", "authenticated", "admin"] {
+            for field in ["json", "br", "gzip", "etag"] {
+                let exists: bool = test_setup
+                    .redis
+                    .hexists(format!("cache::meals::{visibility}"), field)
+                    .await
+                    .unwrap();
+                assert!(exists);
+            }
+        }
+        let lock: bool = test_setup.redis.exists("lock::meals::admin").await.unwrap();
+        assert!(!lock);
 
         // Check redis cache
         let category_cache: Option<String> = test_setup.redis.get("cache::category").await.unwrap();
@@ -2539,14 +3024,9 @@ mod tests {
 
             assert!(["Jack", "Dave"].contains(&person));
 
-            assert_eq!(
-                file_name_original.chars().nth(26).unwrap(),
-                if person == "Jack" { '1' } else { '0' }
-            );
-            assert_eq!(
-                file_name_converted.chars().nth(26).unwrap(),
-                if person == "Jack" { '1' } else { '0' }
-            );
+            // The legacy visibility char is only set for photos uploaded before it was dropped
+            assert!(['0', '1'].contains(&file_name_original.chars().nth(26).unwrap()));
+            assert!(['0', '1'].contains(&file_name_converted.chars().nth(26).unwrap()));
 
             assert_eq!(file_name_converted.chars().nth(27).unwrap(), '1');
             assert_eq!(file_name_original.chars().nth(27).unwrap(), '0');
@@ -2823,4 +3303,1782 @@ mod tests {
             assert!(!std::fs::exists(file_path).unwrap());
         }
     }
+
+    // Person
+
+    #[tokio::test]
+    /// Unauthenticated user unable to [GET, PATCH, POST] "/person" route, or [DELETE] "/person/{param}"
+    async fn api_router_admin_person_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+
+        for result in [
+            client.get(&url).send().await.unwrap(),
+            client.patch(&url).send().await.unwrap(),
+            client.post(&url).send().await.unwrap(),
+            client.delete(format!("{url}/Jack")).send().await.unwrap(),
+        ] {
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    #[tokio::test]
+    /// Authenticated, but not admin user, unable to [GET, PATCH, POST] "/person" route, or [DELETE] "/person/{param}"
+    async fn api_router_admin_person_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+
+        for request in [
+            client.get(&url),
+            client.patch(&url),
+            client.post(&url),
+            client.delete(format!("{url}/Jack")),
+        ] {
+            let result = request
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user get array of all people
+    async fn api_router_admin_person_get_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+        let result = client
+            .get(&url)
+            .header("cookie", authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let result = result.as_array().unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0]["person"], "Dave");
+        assert_eq!(result[0]["initial"], "D");
+        assert_eq!(result[0]["visibility"], "authenticated");
+        assert_eq!(result[1]["person"], "Jack");
+        assert_eq!(result[1]["initial"], "J");
+        assert_eq!(result[1]["visibility"], "public");
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user can insert a new person, which can then be used as a meal person, but not twice
+    async fn api_router_admin_person_post_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let body = PersonPost {
+            person: S!(TEST_PERSON),
+            initial: S!(TEST_PERSON_INITIAL),
+            visibility: Visibility::Authenticated,
+            email: None,
+        };
+        let client = reqwest::Client::new();
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let person = ModelPerson::from_name(TEST_PERSON).unwrap();
+        assert_eq!(person.initial, TEST_PERSON_INITIAL);
+        assert_eq!(person.visibility(), Visibility::Authenticated);
+        assert!(Person::try_from(TEST_PERSON).is_ok());
+
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Person already exists");
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user unable to insert a person with an invalid initial or visibility
+    async fn api_router_admin_person_post_invalid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+        for initial in ["", "x", "XY", "1"] {
+            let body = PersonPost {
+                person: S!(TEST_PERSON),
+                initial: S!(initial),
+                visibility: Visibility::Authenticated,
+                email: None,
+            };
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "initial");
+        }
+
+        for visibility in ["", "private", "Public"] {
+            let body = HashMap::from([
+                ("person", TEST_PERSON),
+                ("initial", TEST_PERSON_INITIAL),
+                ("visibility", visibility),
+            ]);
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "visibility");
+        }
+        assert!(ModelPerson::from_name(TEST_PERSON).is_none());
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user can update a person, but not to clash with an existing person
+    async fn api_router_admin_person_patch_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+
+        let body = PersonPatch {
+            original_person: Person::try_from(TEST_PERSON).unwrap(),
+            person: PersonPost {
+                person: S!(TEST_PERSON),
+                initial: S!("J"),
+                visibility: Visibility::Admin,
+                email: None,
+            },
+        };
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Person already exists");
+
+        let body = PersonPatch {
+            original_person: Person::try_from(TEST_PERSON).unwrap(),
+            person: PersonPost {
+                person: S!(TEST_PERSON),
+                initial: S!(TEST_PERSON_INITIAL),
+                visibility: Visibility::Admin,
+                email: None,
+            },
+        };
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            ModelPerson::from_name(TEST_PERSON).unwrap().visibility(),
+            Visibility::Admin
+        );
+
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "no changes");
+    }
+
+    #[tokio::test]
+    /// A person's visibility change returns every date they have a meal on as a change
+    async fn api_router_admin_person_patch_changes() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let client = reqwest::Client::new();
+
+        let meal_date = now_utc().date() - jiff::Span::new().days(10);
+        let mut meal = test_setup.gen_meal(false);
+        meal.person = S!(TEST_PERSON);
+        meal.date = meal_date.to_string();
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&meal)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let changes_url = format!("{}/food/changes", base_url(&test_setup.app_env));
+        let result = client
+            .get(&changes_url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let cursor = result.json::<Response>().await.unwrap().response["cursor"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        let body = PersonPatch {
+            original_person: Person::try_from(TEST_PERSON).unwrap(),
+            person: PersonPost {
+                person: S!(TEST_PERSON),
+                initial: S!(TEST_PERSON_INITIAL),
+                visibility: Visibility::Admin,
+                email: None,
+            },
+        };
+        let result = client
+            .patch(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                AdminRoutes::Person.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client
+            .get(format!("{changes_url}?since={cursor}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let date = meal_date.strftime("%y%m%d").to_string();
+        assert!(
+            result["m"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|i| i["a"] == date && i["p"][TEST_PERSON_INITIAL].is_object())
+        );
+
+        test_setup
+            .purge_meal(&Person::try_from(TEST_PERSON).unwrap(), meal_date)
+            .await;
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user can link a person to a user, who is then reminded of the person's missing meals, unless they opt out
+    async fn api_router_admin_person_patch_linked_user() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+
+        let mut body = PersonPatch {
+            original_person: Person::try_from(TEST_PERSON).unwrap(),
+            person: PersonPost {
+                person: S!(TEST_PERSON),
+                initial: S!(TEST_PERSON_INITIAL),
+                visibility: Visibility::Authenticated,
+                email: Some(format!("{}@example.com", gen_random_hex(12))),
+            },
+        };
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "unknown user");
+
+        body.person.email = Some(S!(TEST_EMAIL));
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            ModelPerson::from_name(TEST_PERSON).unwrap().email.unwrap(),
+            TEST_EMAIL
+        );
+
+        // Missing meals start at the person's first meal, so give the test person a meal before the look back window
+        let meal_date = now_utc().date() - jiff::Span::new().days(10);
+        let mut meal = test_setup.gen_meal(false);
+        meal.person = S!(TEST_PERSON);
+        meal.date = meal_date.to_string();
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&meal)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        // Every day of the look back window is missing
+        let reminders = ModelMealReminder::get(&test_setup.postgres, 3)
+            .await
+            .unwrap();
+        let reminder = reminders.iter().find(|i| i.person == TEST_PERSON).unwrap();
+        assert_eq!(reminder.email, TEST_EMAIL);
+        let yesterday = now_utc().yesterday().unwrap().date();
+        assert_eq!(reminder.missing_dates.len(), 3);
+        assert_eq!(reminder.missing_dates[0], yesterday.to_string());
+
+        let user = test_setup.model_user.as_ref().unwrap();
+        user.update_meal_reminder(&test_setup.postgres, false)
+            .await
+            .unwrap();
+        let reminders = ModelMealReminder::get(&test_setup.postgres, 3)
+            .await
+            .unwrap();
+        assert!(!reminders.iter().any(|i| i.person == TEST_PERSON));
+
+        test_setup
+            .purge_meal(&Person::try_from(TEST_PERSON).unwrap(), meal_date)
+            .await;
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user unable to delete a person who has meals
+    async fn api_router_admin_person_param_delete_in_use() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/meal", base_url(&test_setup.app_env));
+        let body = test_setup.gen_meal(false);
+        client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+
+        let url = format!(
+            "{}{}/Jack",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let result = client
+            .delete(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Person in use");
+        assert!(ModelPerson::from_name("Jack").is_some());
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user can delete a person without meals, unknown person returns error
+    async fn api_router_admin_person_param_delete_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let url = format!(
+            "{}{}/{TEST_PERSON}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Person.addr(),
+        );
+        let client = reqwest::Client::new();
+        let result = client
+            .delete(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(ModelPerson::from_name(TEST_PERSON).is_none());
+
+        let result = client
+            .delete(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "invalid person param");
+    }
+
+    #[tokio::test]
+    /// Only admin users can access the export route
+    async fn api_router_admin_export_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let url = format!(
+            "{}{}?format=csv",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Export.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Admin export includes admin only meals, and original photos
+    async fn api_router_admin_export_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(true);
+        body.visibility = Some(S!("admin"));
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}{}?format=csv&person=Jack&from={date}&to={date}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Export.addr(),
+            date = body.date
+        );
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.text().await.unwrap();
+        let lines = result.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains(&body.description));
+        assert!(lines[1].contains(&body.photos[0].original));
+        assert!(lines[1].ends_with(",admin,,"));
+    }
+
+    /// Get the category of a meal, and the latest meal_category_change row, which is then removed
+    async fn category_change(
+        postgres: &sqlx::PgPool,
+        meal: &ModelMeal,
+    ) -> (String, String, String, i64) {
+        let change = sqlx::query!(
+            "DELETE FROM meal_category_change WHERE meal_category_change_id = (SELECT MAX(meal_category_change_id) FROM meal_category_change) RETURNING action, from_category, to_category, meal_count"
+        )
+        .fetch_one(postgres)
+        .await
+        .unwrap();
+        assert_eq!(change.to_category, meal.category);
+        (
+            change.action,
+            change.from_category,
+            change.to_category,
+            change.meal_count,
+        )
+    }
+
+    #[tokio::test]
+    /// Only admin users can access the category routes
+    async fn api_router_admin_category_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+
+        for (method, route) in [
+            (reqwest::Method::GET, AdminRoutes::Category),
+            (reqwest::Method::PATCH, AdminRoutes::Category),
+            (reqwest::Method::POST, AdminRoutes::CategoryMerge),
+        ] {
+            let result = client
+                .request(
+                    method,
+                    format!("{}{}", base_url(&test_setup.app_env), route.addr()),
+                )
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    #[tokio::test]
+    /// Categories are listed with the number of meals that use them
+    async fn api_router_admin_category_get_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+
+        let body = test_setup.gen_meal(false);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client
+            .get(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                AdminRoutes::Category.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let categories = result.as_array().unwrap();
+        let category = categories
+            .iter()
+            .find(|i| i["category"] == body.category.to_uppercase())
+            .unwrap();
+        assert_eq!(category["meals"], 1);
+        assert!(categories.iter().all(|i| i["meals"].as_i64().unwrap() >= 0));
+    }
+
+    #[tokio::test]
+    /// Rename a category, invalid renames return an error, a change is recorded
+    async fn api_router_admin_category_patch_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Category.addr()
+        );
+
+        test_setup.insert_test_person().await;
+        let mut test_person_meal = test_setup.gen_meal(false);
+        test_person_meal.person = S!(TEST_PERSON);
+        let body = test_setup.gen_meal(false);
+        for meal in [&body, &test_person_meal] {
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(meal)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let new_category = gen_random_hex(12);
+        for (original_category, category, expected) in [
+            (C!(body.category), S!(" "), "category"),
+            (
+                C!(body.category),
+                body.category.to_uppercase(),
+                "no changes",
+            ),
+            (gen_random_hex(12), C!(new_category), "unknown category"),
+            (
+                C!(body.category),
+                test_person_meal.category.to_lowercase(),
+                "Category already exists",
+            ),
+        ] {
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&CategoryPatch {
+                    original_category,
+                    category,
+                })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&CategoryPatch {
+                original_category: C!(body.category),
+                category: C!(new_category),
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let meal = test_setup.query_meal().await.unwrap();
+        assert_eq!(meal.category, new_category.to_uppercase());
+        assert!(
+            ModelCategory::get(&test_setup.postgres, &body.category)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            category_change(&test_setup.postgres, &meal).await,
+            (
+                S!("rename"),
+                body.category.to_uppercase(),
+                new_category.to_uppercase(),
+                1
+            )
+        );
+    }
+
+    #[tokio::test]
+    /// Merge one category into another, the meals are moved and the emptied category is removed
+    async fn api_router_admin_category_merge_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::CategoryMerge.addr()
+        );
+
+        let mut test_person_meal = test_setup.gen_meal(false);
+        test_person_meal.person = S!(TEST_PERSON);
+        let jack_meal = test_setup.gen_meal(false);
+        for body in [&jack_meal, &test_person_meal] {
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        for (from, into, expected) in [
+            (C!(jack_meal.category), C!(jack_meal.category), "no changes"),
+            (
+                gen_random_hex(12),
+                C!(jack_meal.category),
+                "unknown category",
+            ),
+            (
+                C!(jack_meal.category),
+                gen_random_hex(12),
+                "unknown category",
+            ),
+        ] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&CategoryMerge { from, into })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&CategoryMerge {
+                from: C!(jack_meal.category),
+                into: C!(test_person_meal.category),
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let meal = test_setup.query_meal().await.unwrap();
+        assert_eq!(meal.category, test_person_meal.category.to_uppercase());
+        assert!(
+            ModelCategory::get(&test_setup.postgres, &jack_meal.category)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let into = ModelCategory::get(&test_setup.postgres, &test_person_meal.category)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(into.meals, 2);
+        assert_eq!(
+            category_change(&test_setup.postgres, &meal).await,
+            (
+                S!("merge"),
+                jack_meal.category.to_uppercase(),
+                test_person_meal.category.to_uppercase(),
+                1
+            )
+        );
+    }
+
+    #[tokio::test]
+    /// Only admin users can access the description routes
+    async fn api_router_admin_description_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+
+        for (method, route) in [
+            (reqwest::Method::GET, AdminRoutes::DescriptionSimilar),
+            (reqwest::Method::POST, AdminRoutes::DescriptionMerge),
+        ] {
+            let result = client
+                .request(
+                    method,
+                    format!("{}{}", base_url(&test_setup.app_env), route.addr()),
+                )
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    /// Insert a Jack and a test person meal, with similar descriptions, returns the Jack meal description, and the test person meal description
+    async fn insert_similar_descriptions(
+        test_setup: &mut TestSetup,
+        authed_cookie: &str,
+    ) -> (String, String) {
+        test_setup.insert_test_person().await;
+        let client = reqwest::Client::new();
+
+        let prefix = gen_random_hex(12);
+        let mut test_person_meal = test_setup.gen_meal(false);
+        test_person_meal.person = S!(TEST_PERSON);
+        test_person_meal.description = format!("{prefix} chicken curry, with rice.");
+        let mut jack_meal = test_setup.gen_meal(false);
+        jack_meal.description = format!("{prefix} Chicken curry with rice");
+        for body in [&jack_meal, &test_person_meal] {
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+        (jack_meal.description, test_person_meal.description)
+    }
+
+    #[tokio::test]
+    /// Similar descriptions are clustered together
+    async fn api_router_admin_description_similar_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let (jack_description, test_person_description) =
+            insert_similar_descriptions(&mut test_setup, &authed_cookie).await;
+        let client = reqwest::Client::new();
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::DescriptionSimilar.addr()
+        );
+        for query in ["?threshold=2", "?threshold=0", "?threshold=abc"] {
+            let result = client
+                .get(format!("{url}{query}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "threshold");
+        }
+
+        let result = client
+            .get(format!("{url}?threshold=0.8"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let prefix = jack_description.split_once(' ').unwrap().0;
+        let cluster = result
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i[0]["description"].as_str().unwrap().starts_with(prefix))
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(cluster.len(), 2);
+        for description in [&jack_description, &test_person_description] {
+            assert!(cluster.iter().any(|i| i["description"] == *description));
+        }
+        assert!(cluster.iter().all(|i| i["meals"] == 1));
+    }
+
+    #[tokio::test]
+    /// Merge descriptions into a single description, invalid merges return an error
+    async fn api_router_admin_description_merge_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let (jack_description, test_person_description) =
+            insert_similar_descriptions(&mut test_setup, &authed_cookie).await;
+        let client = reqwest::Client::new();
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::DescriptionMerge.addr()
+        );
+        for (description, descriptions, expected) in [
+            (C!(jack_description), vec![], "descriptions"),
+            (S!(" "), vec![C!(jack_description)], "description"),
+            (
+                C!(jack_description),
+                vec![C!(jack_description), gen_random_hex(12)],
+                "unknown description",
+            ),
+            (
+                C!(jack_description),
+                vec![C!(jack_description)],
+                "no changes",
+            ),
+        ] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&DescriptionMerge {
+                    description,
+                    descriptions,
+                })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&DescriptionMerge {
+                description: C!(jack_description),
+                descriptions: vec![C!(jack_description), C!(test_person_description)],
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let meal = test_setup.query_meal().await.unwrap();
+        let result = ModelMeal::get_by_date_person(
+            &test_setup.postgres,
+            &Person::try_from(TEST_PERSON).unwrap(),
+            meal.meal_date.to_jiff(),
+            MealSlot::Dinner,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(result.description, jack_description);
+        let remaining = ModelDescription::get_many(
+            &test_setup.postgres,
+            &[jack_description, test_person_description],
+        )
+        .await
+        .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].meals, 2);
+    }
+
+    #[tokio::test]
+    /// Only admin users can access the tag routes
+    async fn api_router_admin_tag_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+
+        for (method, route) in [
+            (reqwest::Method::GET, AdminRoutes::Tag.addr()),
+            (reqwest::Method::PATCH, AdminRoutes::Tag.addr()),
+            (reqwest::Method::POST, AdminRoutes::Tag.addr()),
+            (reqwest::Method::DELETE, S!("/admin/tag/1")),
+        ] {
+            let result = client
+                .request(method, format!("{}{route}", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    #[tokio::test]
+    /// Insert, list, rename, and delete a tag, deleting a tag removes it from any meal
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_admin_tag_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Tag.addr()
+        );
+        let get_tags = || async {
+            let result = client
+                .get(&url)
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            result.json::<Response>().await.unwrap().response
+        };
+
+        let tag = gen_random_hex(12).to_lowercase();
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&TagPost {
+                tag: format!(" {} ", tag.to_uppercase()),
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        for (tag, expected) in [
+            (C!(tag), "Tag already exists"),
+            (S!(" "), "tag"),
+            ("a".repeat(33), "tag"),
+        ] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&TagPost { tag })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = get_tags().await;
+        let inserted = result
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["tag"] == tag.as_str())
+            .unwrap();
+        assert_eq!(inserted["meals"], 0);
+        let meal_tag_id = inserted["meal_tag_id"].as_i64().unwrap();
+
+        let other_tag = test_setup.insert_test_tag().await;
+        let mut body = test_setup.gen_meal(false);
+        body.tags = vec![meal_tag_id, other_tag];
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let other_tag_name =
+            sqlx::query_scalar!("SELECT tag FROM meal_tag WHERE meal_tag_id = $1", other_tag)
+                .fetch_one(&test_setup.postgres)
+                .await
+                .unwrap();
+        let new_tag = gen_random_hex(12).to_lowercase();
+        for (meal_tag_id, tag, expected) in [
+            (meal_tag_id, C!(tag), "no changes"),
+            (meal_tag_id, C!(other_tag_name), "Tag already exists"),
+            (other_tag + 1000, C!(new_tag), "unknown tag"),
+            (0, C!(new_tag), "id"),
+        ] {
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&TagPatch { meal_tag_id, tag })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&TagPatch {
+                meal_tag_id,
+                tag: C!(new_tag),
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = get_tags().await;
+        let renamed = result
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["meal_tag_id"] == meal_tag_id)
+            .unwrap();
+        assert_eq!(renamed["tag"], new_tag);
+        assert_eq!(renamed["meals"], 1);
+
+        let result = client
+            .delete(format!("{url}/{}", other_tag + 1000))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "unknown tag");
+
+        let result = client
+            .delete(format!("{url}/{meal_tag_id}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(test_setup.query_meal().await.unwrap().tags, vec![other_tag]);
+    }
+
+    #[tokio::test]
+    /// Only admin users can access the venue routes
+    async fn api_router_admin_venue_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+
+        for (method, route) in [
+            (reqwest::Method::GET, AdminRoutes::Venue.addr()),
+            (reqwest::Method::PATCH, AdminRoutes::Venue.addr()),
+            (reqwest::Method::POST, AdminRoutes::Venue.addr()),
+            (reqwest::Method::DELETE, S!("/admin/venue/1")),
+        ] {
+            let result = client
+                .request(method, format!("{}{route}", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    #[tokio::test]
+    /// Insert, list, update, and delete a venue, a venue with meals can't be deleted
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_admin_venue_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Venue.addr()
+        );
+        let get_venue = |meal_venue_id: i64| {
+            let client = C!(client);
+            let url = C!(url);
+            let authed_cookie = C!(authed_cookie);
+            async move {
+                let result = client
+                    .get(&url)
+                    .header("cookie", &authed_cookie)
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(result.status(), StatusCode::OK);
+                result
+                    .json::<Response>()
+                    .await
+                    .unwrap()
+                    .response
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|i| i["meal_venue_id"] == meal_venue_id)
+                    .cloned()
+                    .unwrap()
+            }
+        };
+
+        let venue = Venue {
+            name: gen_random_hex(12).to_lowercase(),
+            city: S!("Paris"),
+            venue_type: VenueType::Cafe,
+            coordinates: Some(Coordinates {
+                latitude: 48.8566,
+                longitude: 2.3522,
+            }),
+        };
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&venue)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        for (body, expected) in [
+            (
+                serde_json::to_value(&venue).unwrap(),
+                "Venue already exists",
+            ),
+            (
+                serde_json::json!({"name": " ", "city": "Paris", "venue_type": "cafe"}),
+                "name",
+            ),
+            (
+                serde_json::json!({"name": "a".repeat(65), "city": "Paris", "venue_type": "cafe"}),
+                "name",
+            ),
+            (
+                serde_json::json!({"name": "a", "city": "", "venue_type": "cafe"}),
+                "city",
+            ),
+            (
+                serde_json::json!({"name": "a", "city": "Paris", "venue_type": "bar"}),
+                "venue_type",
+            ),
+            (
+                serde_json::json!({"name": "a", "city": "Paris", "venue_type": "cafe", "coordinates": {"latitude": 91, "longitude": 0}}),
+                "latitude",
+            ),
+            (
+                serde_json::json!({"name": "a", "city": "Paris", "venue_type": "cafe", "coordinates": {"latitude": 0, "longitude": -181}}),
+                "longitude",
+            ),
+        ] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let meal_venue_id = sqlx::query_scalar!(
+            "SELECT meal_venue_id FROM meal_venue WHERE name = $1",
+            venue.name
+        )
+        .fetch_one(&test_setup.postgres)
+        .await
+        .unwrap();
+        let result = get_venue(meal_venue_id).await;
+        assert_eq!(result["city"], "Paris");
+        assert_eq!(result["venue_type"], "cafe");
+        assert_eq!(result["coordinates"]["latitude"], 48.8566);
+        assert_eq!(result["coordinates"]["longitude"], 2.3522);
+        assert_eq!(result["meals"], 0);
+
+        let other_venue = test_setup.insert_test_venue().await;
+        let other_venue_name = sqlx::query_scalar!(
+            "SELECT name FROM meal_venue WHERE meal_venue_id = $1",
+            other_venue
+        )
+        .fetch_one(&test_setup.postgres)
+        .await
+        .unwrap();
+        for (meal_venue_id, venue, expected) in [
+            (meal_venue_id, C!(venue), "no changes"),
+            (
+                meal_venue_id,
+                Venue {
+                    name: other_venue_name,
+                    city: S!("London"),
+                    ..C!(venue)
+                },
+                "Venue already exists",
+            ),
+            (other_venue + 1000, C!(venue), "unknown venue"),
+            (0, C!(venue), "id"),
+        ] {
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&VenuePatch {
+                    meal_venue_id,
+                    venue,
+                })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&VenuePatch {
+                meal_venue_id,
+                venue: Venue {
+                    venue_type: VenueType::Takeaway,
+                    coordinates: None,
+                    ..C!(venue)
+                },
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = get_venue(meal_venue_id).await;
+        assert_eq!(result["venue_type"], "takeaway");
+        assert!(result["coordinates"].is_null());
+
+        let mut body = test_setup.gen_meal(false);
+        body.venue_id = Some(meal_venue_id);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(get_venue(meal_venue_id).await["meals"], 1);
+
+        for (meal_venue_id, expected) in [
+            (meal_venue_id, "Venue in use"),
+            (other_venue + 1000, "unknown venue"),
+        ] {
+            let result = client
+                .delete(format!("{url}/{meal_venue_id}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        let result = client
+            .delete(format!("{url}/{other_venue}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(
+            sqlx::query_scalar!(
+                "SELECT meal_venue_id FROM meal_venue WHERE meal_venue_id = $1",
+                other_venue
+            )
+            .fetch_optional(&test_setup.postgres)
+            .await
+            .unwrap()
+            .is_none()
+        );
+    }
+
+    #[tokio::test]
+    /// Only admin users can access the trash routes
+    async fn api_router_admin_trash_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+
+        for (method, route) in [
+            (reqwest::Method::GET, AdminRoutes::Trash.addr()),
+            (reqwest::Method::PUT, S!("/admin/trash/1")),
+            (reqwest::Method::DELETE, S!("/admin/trash/1")),
+        ] {
+            let result = client
+                .request(method, format!("{}{route}", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::FORBIDDEN);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "Invalid Authentication");
+        }
+    }
+
+    #[tokio::test]
+    /// A deleted meal is listed in the trash, it can only be restored if its slot is still free, and purging requires a password
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_admin_trash_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Trash.addr()
+        );
+        let body = test_setup.gen_meal(false);
+        let password = HashMap::from([("password", TEST_PASSWORD)]);
+
+        let insert_delete = || {
+            let client = C!(client);
+            let meal_url = C!(meal_url);
+            let authed_cookie = C!(authed_cookie);
+            let body = C!(body);
+            let password = C!(password);
+            async move {
+                let result = client
+                    .post(&meal_url)
+                    .header("cookie", &authed_cookie)
+                    .json(&body)
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(result.status(), StatusCode::OK);
+                let result = client
+                    .delete(format!("{meal_url}/{}/{}", body.date, body.person))
+                    .header("cookie", &authed_cookie)
+                    .json(&password)
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(result.status(), StatusCode::OK);
+            }
+        };
+        let get_trash = || {
+            let client = C!(client);
+            let url = C!(url);
+            let authed_cookie = C!(authed_cookie);
+            async move {
+                let result = client
+                    .get(&url)
+                    .header("cookie", &authed_cookie)
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(result.status(), StatusCode::OK);
+                result
+                    .json::<Response>()
+                    .await
+                    .unwrap()
+                    .response
+                    .as_array()
+                    .unwrap()
+                    .clone()
+            }
+        };
+
+        insert_delete().await;
+        assert!(test_setup.query_meal().await.is_none());
+        let trash = get_trash().await;
+        let first = &trash[0];
+        assert_eq!(first["date"], body.date);
+        assert_eq!(first["person"], body.person);
+        assert_eq!(first["description"], body.description);
+        assert!(first["deleted_at"].is_string());
+        let first_id = first["id"].as_i64().unwrap();
+
+        // The slot is free again, so a new meal can be added, and then also deleted
+        insert_delete().await;
+        let trash = get_trash().await;
+        let second_id = trash[0]["id"].as_i64().unwrap();
+        assert_ne!(first_id, second_id);
+        assert_eq!(trash[1]["id"], first_id);
+
+        let result = client
+            .post(&meal_url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = client
+            .put(format!("{url}/{first_id}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Meal already exists on date and person given");
+
+        let result = client
+            .delete(format!("{meal_url}/{}/{}", body.date, body.person))
+            .header("cookie", &authed_cookie)
+            .json(&password)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = client
+            .put(format!("{url}/{first_id}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let meal = test_setup.query_meal().await.unwrap();
+        assert_eq!(meal.individual_meal_id, first_id);
+        assert!(get_trash().await.iter().all(|i| i["id"] != first_id));
+
+        let result = client
+            .delete(format!("{url}/{second_id}"))
+            .header("cookie", &authed_cookie)
+            .json(&HashMap::from([("password", "not_the_password")]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid email address and/or password and/or token");
+
+        let result = client
+            .delete(format!("{url}/{second_id}"))
+            .header("cookie", &authed_cookie)
+            .json(&password)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(get_trash().await.iter().all(|i| i["id"] != second_id));
+
+        for (method, expected) in [
+            (reqwest::Method::PUT, "unknown meal"),
+            (reqwest::Method::DELETE, "unknown meal"),
+        ] {
+            let result = client
+                .request(method, format!("{url}/{second_id}"))
+                .header("cookie", &authed_cookie)
+                .json(&password)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[tokio::test]
+    /// Purging a meal from the trash also removes its photo files
+    async fn api_router_admin_trash_purge_photos() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Trash.addr()
+        );
+        let password = HashMap::from([("password", TEST_PASSWORD)]);
+        let body = test_setup.gen_meal(true);
+        let photo_paths = [
+            PathBuf::from(&test_setup.app_env.location_photo_converted)
+                .join(&body.photos[0].converted),
+            PathBuf::from(&test_setup.app_env.location_photo_original)
+                .join(&body.photos[0].original),
+        ];
+        for path in &photo_paths {
+            std::fs::write(path, [0]).unwrap();
+        }
+
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+        let result = client
+            .post(&meal_url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = client
+            .delete(format!("{meal_url}/{}/{}", body.date, body.person))
+            .header("cookie", &authed_cookie)
+            .json(&password)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        // A deleted meal keeps its photos, so it can be restored
+        for path in &photo_paths {
+            assert!(path.exists());
+        }
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        let trash = result.json::<Response>().await.unwrap().response;
+        let id = trash[0]["id"].as_i64().unwrap();
+        let result = client
+            .delete(format!("{url}/{id}"))
+            .header("cookie", &authed_cookie)
+            .json(&password)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        for path in &photo_paths {
+            assert!(!path.exists());
+        }
+    }
+
+    #[tokio::test]
+    /// Only admin users can collect orphans
+    async fn api_router_admin_orphans_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+        let result = client
+            .post(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                AdminRoutes::Orphans.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .json(&HashMap::from([("dry_run", true)]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// A dry run only reports the orphans, otherwise the rows, and photo files, are removed, rows used by a meal are kept
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_admin_orphans_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            AdminRoutes::Orphans.addr()
+        );
+        let body = test_setup.gen_meal(true);
+        client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+
+        let user_id = test_setup
+            .get_model_user()
+            .await
+            .unwrap()
+            .registered_user_id;
+        let category = gen_random_hex(12).to_uppercase();
+        let description = gen_random_hex(12);
+        let date = "2015-06-01";
+        let photo = TestSetup::gen_meal_photo();
+        sqlx::query!(
+            "INSERT INTO meal_category(category, registered_user_id) VALUES($1, $2)",
+            category,
+            user_id
+        )
+        .execute(&test_setup.postgres)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO meal_description(description, registered_user_id) VALUES($1, $2)",
+            description,
+            user_id
+        )
+        .execute(&test_setup.postgres)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO meal_date(date_of_meal, registered_user_id) VALUES($1::TEXT::DATE, $2) ON CONFLICT DO NOTHING",
+            date,
+            user_id
+        )
+        .execute(&test_setup.postgres)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO meal_photo(photo_original, photo_converted, registered_user_id) VALUES($1, $2, $3)",
+            photo.original,
+            photo.converted,
+            user_id
+        )
+        .execute(&test_setup.postgres)
+        .await
+        .unwrap();
+        let photo_paths = [
+            PathBuf::from(&test_setup.app_env.location_photo_converted).join(&photo.converted),
+            PathBuf::from(&test_setup.app_env.location_photo_original).join(&photo.original),
+        ];
+        for path in &photo_paths {
+            std::fs::write(path, [0]).unwrap();
+        }
+
+        for dry_run in [true, false] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&HashMap::from([("dry_run", dry_run)]))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result["dry_run"], dry_run);
+            let contains =
+                |key: &str, value: &str| result[key].as_array().unwrap().iter().any(|i| i == value);
+            assert!(contains("categories", &category));
+            assert!(contains("descriptions", &description));
+            assert!(contains("dates", date));
+            assert!(!contains("categories", &body.category.to_uppercase()));
+            assert!(!contains("descriptions", &body.description));
+            assert!(!contains("dates", &body.date));
+            let photos = result["photos"].as_array().unwrap();
+            assert!(
+                photos
+                    .iter()
+                    .any(|i| i["converted"] == photo.converted && i["original"] == photo.original)
+            );
+            assert!(
+                !photos
+                    .iter()
+                    .any(|i| i["converted"] == body.photos[0].converted)
+            );
+
+            let category_exists = ModelCategory::get(&test_setup.postgres, &category)
+                .await
+                .unwrap()
+                .is_some();
+            assert_eq!(category_exists, dry_run);
+            for path in &photo_paths {
+                assert_eq!(path.exists(), dry_run);
+            }
+        }
+        assert!(test_setup.query_meal().await.is_some());
+    }
 }
@@ -1,15 +1,17 @@
-use axum::{Router, extract::State, middleware, routing::get};
+use axum::{Router, extract::State, middleware, response::Response, routing::get};
 
 use crate::{
     C,
     api::{ApiRouter, ApiState},
     api_error::ApiError,
-    database::MealResponse,
+    database::{
+        MealChanges, MealResponse, ModelMeal, ModelUser, ModelVenueVisits, StatsResponse,
+        SuggestResponse, Visibility,
+    },
     define_routes,
+    helpers::now_utc,
     servers::{
-        Outgoing,
-        authentication::is_authenticated,
-        oj::{self, MealInfo},
+        Outgoing, authentication::is_authenticated, export::export_response, ij, meals_response, oj,
     },
 };
 
@@ -17,7 +19,14 @@ define_routes! {
     FoodRoutes,
     "/food",
     All => "/all",
-    Hash => "/hash"
+    Changes => "/changes",
+    Export => "/export",
+    Hash => "/hash",
+    OnThisDay => "/on-this-day",
+    Search => "/search",
+    Stats => "/stats",
+    Suggest => "/suggest",
+    Venues => "/venues"
 }
 
 pub struct FoodRouter;
@@ -26,28 +35,161 @@ impl ApiRouter for FoodRouter {
     fn create_router(state: &ApiState) -> Router<ApiState> {
         Router::new()
             .route(&FoodRoutes::All.addr(), get(Self::all_get))
+            .route(&FoodRoutes::Changes.addr(), get(Self::changes_get))
+            .route(&FoodRoutes::Export.addr(), get(Self::export_get))
             .route(&FoodRoutes::Hash.addr(), get(Self::hash_get))
+            .route(&FoodRoutes::OnThisDay.addr(), get(Self::on_this_day_get))
+            .route(&FoodRoutes::Search.addr(), get(Self::search_get))
+            .route(&FoodRoutes::Stats.addr(), get(Self::stats_get))
+            .route(&FoodRoutes::Suggest.addr(), get(Self::suggest_get))
+            .route(&FoodRoutes::Venues.addr(), get(Self::venues_get))
             .layer(middleware::from_fn_with_state(C!(state), is_authenticated))
     }
 }
 
 impl FoodRouter {
-    /// get individual meals, sorted by date
-    async fn all_get(State(state): State<ApiState>) -> Result<Outgoing<MealInfo>, ApiError> {
+    /// get individual meals, sorted by date, admin users can also see admin only meals
+    async fn all_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        if_none_match: ij::IfNoneMatch,
+        accept_format: ij::AcceptFormat,
+        accept_encoding: ij::AcceptEncoding,
+    ) -> Result<Response, ApiError> {
+        meals_response(
+            &state,
+            Visibility::from(Some(&user)),
+            &if_none_match,
+            accept_format,
+            accept_encoding,
+        )
+        .await
+    }
+
+    /// Search meals, ranked by relevance, admin users can also search admin only meals
+    async fn search_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Query(search): ij::Query<ij::MealSearch>,
+    ) -> Result<Outgoing<oj::MealSearch>, ApiError> {
+        let meals =
+            ModelMeal::search(&state.postgres, &search, Visibility::from(Some(&user))).await?;
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(oj::MealSearch::from_model(meals, search.page)),
+        ))
+    }
+
+    /// Get the meals from the same month and day as the given date, or today, in previous years, admin users can also see admin only meals
+    async fn on_this_day_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Query(query): ij::Query<ij::OnThisDay>,
+    ) -> Result<Outgoing<oj::OnThisDay>, ApiError> {
+        let date = query.date.unwrap_or_else(|| now_utc().date());
+        let meals =
+            ModelMeal::on_this_day(&state.postgres, date, Visibility::from(Some(&user))).await?;
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(oj::OnThisDay::from_model(date, meals)),
+        ))
+    }
+
+    /// Download every meal, as csv, json lines, or an iCalendar, admin users can also export admin only meals
+    #[expect(clippy::unused_async)]
+    async fn export_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Query(export): ij::Query<ij::MealExport>,
+    ) -> Result<Response, ApiError> {
+        export_response(
+            &state.postgres,
+            &state.domain,
+            export,
+            Visibility::from(Some(&user)),
+        )
+    }
+
+    /// Get every meal change since the given cursor, and a new cursor to use for the next request
+    /// Changes to a person aren't audited, so clients should still check the hash, and fetch all meals when it changes unexpectedly
+    async fn changes_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Query(query): ij::Query<ij::MealChanges>,
+    ) -> Result<Outgoing<oj::MealChanges>, ApiError> {
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(
+                MealChanges::get(
+                    &state.postgres,
+                    query.since.unwrap_or_default(),
+                    Visibility::from(Some(&user)),
+                )
+                .await?,
+            ),
+        ))
+    }
+
+    /// Get per person, all time and per year, meal stats, admin users can also see stats of admin only meals
+    async fn stats_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+    ) -> Result<Outgoing<oj::MealStats>, ApiError> {
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(
+                StatsResponse::get(&state.postgres, &state.redis, Visibility::from(Some(&user)))
+                    .await?,
+            ),
+        ))
+    }
+
+    /// Suggest dinners from a person's own history, admin users can also get suggestions from admin only meals
+    async fn suggest_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Query(suggest): ij::Query<ij::MealSuggest>,
+    ) -> Result<Outgoing<Vec<oj::MealSuggestion>>, ApiError> {
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(
+                SuggestResponse::get(&state.postgres, &suggest, Visibility::from(Some(&user)))
+                    .await?,
+            ),
+        ))
+    }
+
+    /// Get every visited venue, with visit counts, and first and last visit dates, admin users can also see venues of admin only meals
+    async fn venues_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+    ) -> Result<Outgoing<Vec<oj::VenueVisits>>, ApiError> {
         Ok((
             axum::http::StatusCode::OK,
             oj::OutgoingJson::new(
-                MealResponse::get_all(&state.postgres, &state.redis, Some(())).await?,
+                ModelVenueVisits::get(&state.postgres, Visibility::from(Some(&user)))
+                    .await?
+                    .into_iter()
+                    .map(oj::VenueVisits::from)
+                    .collect(),
             ),
         ))
     }
 
-    /// Just return the last id from the individual_meal_audit
-    async fn hash_get(State(state): State<ApiState>) -> Result<Outgoing<String>, ApiError> {
+    /// Return a hash of all the date meals, clients can compare it to their own to check for changes
+    async fn hash_get(
+        State(state): State<ApiState>,
+        user: ModelUser,
+    ) -> Result<Outgoing<String>, ApiError> {
         Ok((
             axum::http::StatusCode::OK,
             oj::OutgoingJson::new(
-                MealResponse::get_hash(&state.postgres, &state.redis, Some(())).await?,
+                MealResponse::get_hash(
+                    &state.postgres,
+                    &state.redis,
+                    Visibility::from(Some(&user)),
+                )
+                .await?,
             ),
         ))
     }
@@ -59,13 +201,26 @@ impl FoodRouter {
 #[expect(clippy::unwrap_used)]
 mod tests {
 
+    use std::{collections::HashMap, io::Read};
+
     use super::FoodRoutes;
-    use crate::servers::{
-        api_tests::{Response, base_url, start_both_servers},
-        deserializer::IncomingDeserializer,
+    use crate::{
+        C, S,
+        database::{ModelVenueVisits, Person, Visibility},
+        helpers::now_utc,
+        servers::{
+            BodyFormat,
+            api_tests::{
+                Response, TEST_PASSWORD, TEST_PERSON, TestSetup, base_url, start_both_servers,
+            },
+            deserializer::IncomingDeserializer,
+        },
     };
 
-    use fred::interfaces::{HashesInterface, KeysInterface};
+    use fred::{
+        interfaces::{HashesInterface, KeysInterface},
+        types::Expiration,
+    };
     use reqwest::StatusCode;
 
     #[tokio::test]
@@ -110,6 +265,10 @@ mod tests {
 
         let result = result.json::<Response>().await.unwrap().response;
 
+        let people = result.get("p").unwrap().as_object().unwrap();
+        assert_eq!(people.get("D").unwrap(), "Dave");
+        assert_eq!(people.get("J").unwrap(), "Jack");
+
         let descriptions = result.get("d");
         assert!(descriptions.is_some());
         let descriptions = descriptions.unwrap().as_object().unwrap();
@@ -142,7 +301,7 @@ mod tests {
         assert!(meal_dates.len() > 200);
 
         for i in meal_dates {
-            // assert each has a d, and j object, and a c object, and each j & d object should have a c, and m
+            // assert each has a people object, with a D and J object, and each D & J object should have a c, and m
             let entry = i.as_object().unwrap();
             let meal_date = entry.get("a");
             assert!(meal_date.is_some());
@@ -151,8 +310,9 @@ mod tests {
             assert!(meal_date.as_str().unwrap().chars().count() == 6);
             assert!(meal_date.as_str().unwrap().chars().all(char::is_numeric));
 
-            for i in ["d", "j"] {
-                let person = entry.get(i);
+            let people = entry.get("p").unwrap().as_object().unwrap();
+            for i in ["D", "J"] {
+                let person = people.get(i);
                 assert!(person.is_some());
                 let person = person.unwrap();
                 assert!(person.is_object());
@@ -173,8 +333,11 @@ mod tests {
                     }
                 }
 
-                if let Some(p) = person.get("p") {
-                    assert!(p.is_object());
+                for p in person
+                    .get("p")
+                    .map(|i| i.as_array().unwrap().clone())
+                    .unwrap_or_default()
+                {
                     let p = p.as_object().unwrap();
 
                     if let Some(original) = p.get("o") {
@@ -203,7 +366,7 @@ mod tests {
         // Check redis cache
         let redis_cache: Option<String> = test_setup
             .redis
-            .hget("cache::all_meals", "data")
+            .hget("cache::meals::authenticated", "json")
             .await
             .unwrap();
         assert!(redis_cache.is_some());
@@ -224,8 +387,11 @@ mod tests {
         assert_eq!(result.status(), StatusCode::FORBIDDEN);
         let result = result.json::<Response>().await.unwrap().response;
         assert_eq!(result, "Invalid Authentication");
-        let all_meals_cache: Option<String> =
-            test_setup.redis.get("cache::all_meals").await.unwrap();
+        let all_meals_cache: Option<String> = test_setup
+            .redis
+            .get("cache::meals::authenticated")
+            .await
+            .unwrap();
         assert!(all_meals_cache.is_none());
     }
 
@@ -258,9 +424,1464 @@ mod tests {
         assert!(IncomingDeserializer::is_hex(result, 64));
 
         // Check redis cache
-        let redis_cache: Option<String> =
-            test_setup.redis.get("cache::all_meals_hash").await.unwrap();
+        let redis_cache: Option<String> = test_setup
+            .redis
+            .get("cache::meals_hash::authenticated")
+            .await
+            .unwrap();
         assert!(redis_cache.is_some());
         assert_eq!(redis_cache.unwrap(), result);
     }
+
+    #[tokio::test]
+    /// Admin only meals are only returned to admin users, each visibility level has its own cache
+    async fn api_router_food_all_meal_visibility() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(false);
+        body.visibility = Some(S!("admin"));
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::All.addr()
+        );
+        let date = body
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let has_date = |result: &serde_json::Value| {
+            result
+                .get("m")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|i| i.get("a").unwrap() == date.as_str())
+        };
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert!(has_date(&result));
+
+        sqlx::query!(
+            "UPDATE admin_user SET admin = false WHERE registered_user_id = $1",
+            test_setup.model_user.as_ref().unwrap().registered_user_id
+        )
+        .execute(&test_setup.postgres)
+        .await
+        .unwrap();
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert!(!has_date(&result));
+
+        for key in ["cache::meals::admin", "cache::meals::authenticated"] {
+            let redis_cache: bool = test_setup.redis.exists(key).await.unwrap();
+            assert!(redis_cache);
+        }
+    }
+
+    #[tokio::test]
+    /// "/all" has a strong ETag, a matching If-None-Match returns an empty 304, and the ETag changes when the meals change
+    async fn api_router_food_all_etag() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::All.addr()
+        );
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            result.headers().get("cache-control").unwrap(),
+            "private, no-cache"
+        );
+        let etag = result
+            .headers()
+            .get("etag")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert!(IncomingDeserializer::is_hex(
+            etag.trim_matches('"').strip_suffix("-br").unwrap(),
+            64
+        ));
+
+        let redis_etag: Option<String> = test_setup
+            .redis
+            .hget("cache::meals::admin", "etag")
+            .await
+            .unwrap();
+        // reqwest accepts brotli, so the ETag is that of the brotli encoding
+        assert_eq!(format!("\"{}-br\"", redis_etag.unwrap()), etag);
+
+        for header in [
+            C!(etag),
+            format!("W/{etag}"),
+            format!("\"abc\", {etag}"),
+            S!("*"),
+        ] {
+            let result = client
+                .get(&url)
+                .header("cookie", &authed_cookie)
+                .header("if-none-match", header)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(result.headers().get("etag").unwrap(), etag.as_str());
+            assert_eq!(
+                result.headers().get("cache-control").unwrap(),
+                "private, no-cache"
+            );
+            assert!(result.bytes().await.unwrap().is_empty());
+        }
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .header("if-none-match", "\"abc\"")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(result.headers().get("etag").unwrap(), etag.as_str());
+
+        let body = test_setup.gen_meal(false);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .header("if-none-match", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_ne!(result.headers().get("etag").unwrap(), etag.as_str());
+    }
+
+    #[tokio::test]
+    /// "/all" is served in the encoding preferred by the Accept-Encoding header, each encoding is cached, and decodes to the same body
+    async fn api_router_food_all_encoding() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        // Disable reqwest's automatic decompression, so the raw bodies can be checked
+        let client = reqwest::Client::builder()
+            .no_brotli()
+            .no_gzip()
+            .build()
+            .unwrap();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::All.addr()
+        );
+
+        let mut bodies = vec![];
+        for (accept, encoding) in [
+            ("", None),
+            ("identity", None),
+            ("gzip;q=0, br;q=0", None),
+            ("gzip", Some("gzip")),
+            ("gzip, br", Some("br")),
+            ("br;q=0.5, gzip", Some("gzip")),
+            ("*", Some("br")),
+            ("*, br;q=0", Some("gzip")),
+        ] {
+            let result = client
+                .get(&url)
+                .header("cookie", &authed_cookie)
+                .header("accept-encoding", accept)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            assert_eq!(
+                result
+                    .headers()
+                    .get("content-encoding")
+                    .map(|i| i.to_str().unwrap()),
+                encoding
+            );
+            assert_eq!(
+                result.headers().get("vary").unwrap(),
+                "Accept, Accept-Encoding"
+            );
+            assert_eq!(
+                result.headers().get("content-type").unwrap(),
+                "application/json"
+            );
+            let etag = result.headers().get("etag").unwrap().to_str().unwrap();
+            assert_eq!(
+                etag.trim_matches('"').split_once('-').map(|i| i.1),
+                encoding
+            );
+            let body = result.bytes().await.unwrap().to_vec();
+            let body = match encoding {
+                Some("br") => {
+                    let mut output = vec![];
+                    brotli::BrotliDecompress(&mut body.as_slice(), &mut output).unwrap();
+                    output
+                }
+                Some(_) => {
+                    let mut output = vec![];
+                    flate2::read::GzDecoder::new(body.as_slice())
+                        .read_to_end(&mut output)
+                        .unwrap();
+                    output
+                }
+                None => body,
+            };
+            bodies.push(body);
+        }
+        assert!(bodies.windows(2).all(|i| i[0] == i[1]));
+        let result = serde_json::from_slice::<Response>(&bodies[0])
+            .unwrap()
+            .response;
+        assert!(result.get("m").is_some());
+
+        let redis_cache: HashMap<String, Vec<u8>> = test_setup
+            .redis
+            .hgetall("cache::meals::authenticated")
+            .await
+            .unwrap();
+        for field in ["json", "br", "gzip", "etag"] {
+            assert!(redis_cache.contains_key(field));
+        }
+        assert_eq!(redis_cache.get("json").unwrap(), &bodies[0]);
+    }
+
+    #[tokio::test]
+    /// "/all" is served as MessagePack, or CBOR, from the cache, if the Accept header prefers it, each mirrors the json response
+    async fn api_router_food_all_format() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::All.addr()
+        );
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let json = result.json::<serde_json::Value>().await.unwrap();
+
+        for (format, name) in [
+            (BodyFormat::MessagePack, "msgpack"),
+            (BodyFormat::Cbor, "cbor"),
+        ] {
+            let result = client
+                .get(&url)
+                .header("cookie", &authed_cookie)
+                .header("accept", format.content_type())
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            assert_eq!(
+                result.headers().get("content-type").unwrap(),
+                format.content_type()
+            );
+            assert!(result.headers().get("content-encoding").is_none());
+            let etag = result.headers().get("etag").unwrap().to_str().unwrap();
+            assert!(etag.ends_with(&format!("-{name}\"")));
+
+            let body = result.bytes().await.unwrap();
+            assert_eq!(
+                format.deserialize::<serde_json::Value>(&body).unwrap(),
+                json
+            );
+            let redis_cache: Option<Vec<u8>> = test_setup
+                .redis
+                .hget("cache::meals::authenticated", name)
+                .await
+                .unwrap();
+            assert_eq!(redis_cache.unwrap(), body);
+        }
+    }
+
+    #[tokio::test]
+    /// Only the holder of the rebuild lock fills a missing cache, other requests wait for it, or for the lock to expire
+    async fn api_router_food_all_single_flight() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::All.addr()
+        );
+
+        // A lock held elsewhere, that is never released, so the requests have to wait for it to expire
+        test_setup
+            .redis
+            .set::<(), &str, &str>(
+                "lock::meals::authenticated",
+                "elsewhere",
+                Some(Expiration::PX(500)),
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let requests = (0..8).map(|_| client.get(&url).header("cookie", &authed_cookie).send());
+        let results = futures::future::join_all(requests).await;
+        assert!(start.elapsed().as_millis() >= 450);
+
+        let etags = results
+            .into_iter()
+            .map(|i| {
+                let i = i.unwrap();
+                assert_eq!(i.status(), StatusCode::OK);
+                i.headers()
+                    .get("etag")
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>();
+        assert!(etags.windows(2).all(|i| i[0] == i[1]));
+
+        let lock: bool = test_setup
+            .redis
+            .exists("lock::meals::authenticated")
+            .await
+            .unwrap();
+        assert!(!lock);
+    }
+
+    #[tokio::test]
+    /// Unauthenticated user unable to access "/search" route
+    async fn api_router_food_search_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let url = format!(
+            "{}{}?q=curry",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Search.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Invalid search params return the param name
+    async fn api_router_food_search_invalid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Search.addr()
+        );
+
+        for (query, expected) in [
+            ("", "missing q"),
+            ("?person=Jack", "missing q"),
+            ("?q=", "search"),
+            ("?q=curry&person=Nobody", "person"),
+            ("?q=curry&from=2020-13-01", "date"),
+            ("?q=curry&from=2020-02-01&to=2020-01-01", "date range"),
+            ("?q=curry&page=0", "id"),
+            ("?q=curry&unknown=1", "invalid input"),
+        ] {
+            let result = client
+                .get(format!("{url}{query}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[tokio::test]
+    /// Search for a meal by description, and filters exclude it when they don't match
+    async fn api_router_food_search_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let body = test_setup.gen_meal(false);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}{}?q={}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Search.addr(),
+            body.description
+        );
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["page"], 1);
+        assert_eq!(result["page_size"], 50);
+        assert_eq!(result["total"], 1);
+        let meals = result["meals"].as_array().unwrap();
+        assert_eq!(meals.len(), 1);
+        assert_eq!(meals[0]["description"], body.description);
+        assert_eq!(meals[0]["person"], "Jack");
+        assert_eq!(meals[0]["date"], body.date);
+        assert_eq!(meals[0]["category"], body.category.to_uppercase());
+
+        // A page past the last result is empty, but still has the total of every page
+        for (filter, expected, total) in [
+            (
+                format!("&person=Jack&takeaway=true&from={}", body.date),
+                1,
+                1,
+            ),
+            (format!("&to={}&restaurant=false", body.date), 1, 1),
+            (format!("&category={}", body.category), 1, 1),
+            (S!("&person=Dave"), 0, 0),
+            (S!("&takeaway=false"), 0, 0),
+            (S!("&vegetarian=true"), 0, 0),
+            (S!("&to=2020-01-01"), 0, 0),
+            (S!("&category=not_a_category"), 0, 0),
+            (S!("&page=2"), 0, 1),
+        ] {
+            let result = client
+                .get(format!("{url}{filter}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result["meals"].as_array().unwrap().len(), expected);
+            assert_eq!(result["total"], total);
+        }
+    }
+
+    #[tokio::test]
+    /// Breakfast and lunch meals are grouped separately from the dinner meals of a date
+    async fn api_router_food_all_slots() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let dinner = test_setup.gen_meal(false);
+        let mut breakfast = test_setup.gen_meal(false);
+        breakfast.slot = Some(S!("breakfast"));
+        for body in [&dinner, &breakfast] {
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let result = client
+            .get(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                FoodRoutes::All.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let date = dinner
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let date_meal = result["m"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["a"] == date.as_str())
+            .unwrap();
+        let descriptions = &result["d"];
+        for (slot, body) in [("p", &dinner), ("b", &breakfast)] {
+            let id = date_meal[slot]["J"]["m"].as_i64().unwrap();
+            assert_eq!(descriptions[id.to_string()], body.description);
+        }
+        assert!(date_meal.get("l").is_none());
+    }
+
+    #[tokio::test]
+    /// Category, and description, counts are per person, and include every meal slot
+    async fn api_router_food_all_counts() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let dinner = test_setup.gen_meal(false);
+        let mut lunch = test_setup.gen_meal(false);
+        lunch.category = C!(dinner.category);
+        lunch.description = C!(dinner.description);
+        lunch.slot = Some(S!("lunch"));
+        let mut breakfast = test_setup.gen_meal(false);
+        breakfast.category = C!(dinner.category);
+        breakfast.slot = Some(S!("breakfast"));
+        for body in [&dinner, &lunch, &breakfast] {
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let result = client
+            .get(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                FoodRoutes::All.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let date = dinner
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let date_meal = result["m"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["a"] == date.as_str())
+            .unwrap();
+        let category_id = date_meal["p"]["J"]["c"].to_string();
+        let dinner_id = date_meal["p"]["J"]["m"].to_string();
+        let breakfast_id = date_meal["b"]["J"]["m"].to_string();
+
+        let counts = &result["n"]["J"];
+        assert_eq!(counts["c"][&category_id], 3);
+        assert_eq!(counts["d"][&dinner_id], 2);
+        assert_eq!(counts["d"][&breakfast_id], 1);
+        assert!(
+            result["n"]
+                .get("D")
+                .is_none_or(|i| i["c"].get(&category_id).is_none())
+        );
+    }
+
+    #[tokio::test]
+    /// A meal's photos are an ordered list, each with an optional caption
+    async fn api_router_food_all_photos() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(true);
+        let mut second_photo = TestSetup::gen_meal_photo();
+        second_photo.caption = Some(S!("dessert"));
+        body.photos.push(second_photo);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client
+            .get(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                FoodRoutes::All.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let date = body
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let date_meal = result["m"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["a"] == date.as_str())
+            .unwrap();
+        let photos = date_meal["p"]["J"]["p"].as_array().unwrap();
+        assert_eq!(photos.len(), 2);
+        for (photo, expected) in photos.iter().zip(&body.photos) {
+            assert_eq!(photo["o"], expected.original);
+            assert_eq!(photo["c"], expected.converted);
+        }
+        assert!(photos[0].get("t").is_none());
+        assert_eq!(photos[1]["t"], "dessert");
+    }
+
+    #[tokio::test]
+    /// Only meals from the same month and day of previous years are returned, grouped by year, newest first
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_food_on_this_day_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::OnThisDay.addr()
+        );
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+
+        let body = test_setup.gen_meal(true);
+        let meals = [
+            ("2018-06-15", "dinner", true),
+            ("2019-06-15", "dinner", false),
+            ("2019-06-15", "lunch", false),
+            ("2019-06-16", "dinner", false),
+            ("2020-06-15", "dinner", false),
+        ];
+        for (date, slot, with_photo) in meals {
+            let mut meal = C!(body);
+            meal.person = S!(TEST_PERSON);
+            meal.date = S!(date);
+            meal.slot = Some(S!(slot));
+            meal.visibility = (date == "2018-06-15").then(|| S!("admin"));
+            if !with_photo {
+                meal.photos = vec![];
+            }
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(&meal)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        for (query, status, expected) in [
+            ("date=2020-06-15", StatusCode::OK, "2020-06-15"),
+            ("date=2020-13-15", StatusCode::BAD_REQUEST, "date"),
+            ("date=2014-06-15", StatusCode::BAD_REQUEST, "date"),
+        ] {
+            let result = client
+                .get(format!("{url}?{query}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), status);
+            let result = result.json::<Response>().await.unwrap().response;
+            if status != StatusCode::OK {
+                assert_eq!(result, expected);
+                continue;
+            }
+            assert_eq!(result["date"], expected);
+            let years = result["years"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|year| {
+                    let meals = year["meals"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .filter(|i| i["person"] == TEST_PERSON)
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    (year["year"].as_i64().unwrap(), year["date"].clone(), meals)
+                })
+                .filter(|(_, _, meals)| !meals.is_empty())
+                .collect::<Vec<_>>();
+            assert_eq!(years.len(), 2);
+            assert_eq!(years[0].0, 2019);
+            assert_eq!(years[0].1, "2019-06-15");
+            assert_eq!(years[0].2.len(), 2);
+            assert_eq!(years[0].2[0]["slot"], "lunch");
+            assert_eq!(years[0].2[1]["slot"], "dinner");
+            assert_eq!(years[1].0, 2018);
+            assert_eq!(years[1].2.len(), 1);
+            assert_eq!(
+                years[1].2[0]["photos"][0]["original"],
+                body.photos[0].original
+            );
+        }
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["date"], now_utc().date().to_string());
+
+        let person = Person::try_from(TEST_PERSON).unwrap();
+        for (date, ..) in meals {
+            test_setup.purge_meal(&person, date.parse().unwrap()).await;
+        }
+    }
+
+    #[tokio::test]
+    /// Suggestions only use the person's own dinners, and can exclude categories, or require vegetarian dishes
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_food_suggest_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Suggest.addr()
+        );
+
+        let result = client
+            .get(format!("{url}?person={TEST_PERSON}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+
+        let body = test_setup.gen_meal(false);
+        let meals = [
+            ("2019-01-01", "dinner", "PASTA", "a", true),
+            ("2019-02-01", "dinner", "PASTA", "a", true),
+            ("2019-03-01", "dinner", "CURRY", "b", false),
+            ("2019-03-01", "lunch", "SALAD", "c", true),
+        ];
+        for (date, slot, category, description, vegetarian) in meals {
+            let mut meal = C!(body);
+            meal.person = S!(TEST_PERSON);
+            meal.date = S!(date);
+            meal.slot = Some(S!(slot));
+            meal.category = S!(category);
+            meal.description = format!("{}{description}", body.description);
+            meal.vegetarian = vegetarian;
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(&meal)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        for (query, expected) in [
+            (S!(""), "missing person"),
+            (S!("person=unknown"), "person"),
+            (format!("person={TEST_PERSON}&exclude=PASTA,"), "exclude"),
+            (
+                format!("person={TEST_PERSON}&vegetarian=yes"),
+                "provided string was not `true` or `false`",
+            ),
+        ] {
+            let result = client
+                .get(format!("{url}?{query}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        for (query, expected) in [
+            (S!(""), vec!["a", "b"]),
+            (S!("&exclude=%20pasta"), vec!["b"]),
+            (S!("&vegetarian=true"), vec!["a"]),
+            (S!("&exclude=pasta,curry"), vec![]),
+        ] {
+            let result = client
+                .get(format!("{url}?person={TEST_PERSON}{query}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            let result = result.json::<Response>().await.unwrap().response;
+            let descriptions = result
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["description"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            let expected = expected
+                .into_iter()
+                .map(|i| format!("{}{i}", body.description))
+                .collect::<Vec<_>>();
+            assert_eq!(descriptions, expected);
+        }
+
+        let result = client
+            .get(format!("{url}?person={TEST_PERSON}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result[0]["category"], "PASTA");
+        assert_eq!(result[0]["vegetarian"], true);
+        assert_eq!(result[0]["times"], 2);
+        assert_eq!(result[0]["last_eaten"], "2019-02-01");
+
+        let person = Person::try_from(TEST_PERSON).unwrap();
+        for (date, ..) in meals {
+            test_setup.purge_meal(&person, date.parse().unwrap()).await;
+        }
+    }
+
+    #[tokio::test]
+    /// Venue visits only count meals visible to the user, multiple meals in the same slot are a single visit
+    async fn api_router_food_venues_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let meal_venue_id = test_setup.insert_test_venue().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Venues.addr()
+        );
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+
+        let mut body = test_setup.gen_meal(false);
+        body.venue_id = Some(meal_venue_id);
+        let mut lunch = C!(body);
+        lunch.slot = Some(S!("lunch"));
+        lunch.visibility = Some(S!("admin"));
+        for meal in [&body, &lunch] {
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(meal)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let venue = result
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["meal_venue_id"] == meal_venue_id)
+            .unwrap();
+        assert_eq!(venue["city"], "London");
+        assert_eq!(venue["venue_type"], "restaurant");
+        assert!(venue["coordinates"].is_null());
+        assert_eq!(venue["visits"], 2);
+        assert_eq!(venue["first_visit"], body.date);
+        assert_eq!(venue["last_visit"], body.date);
+
+        let result = ModelVenueVisits::get(&test_setup.postgres, Visibility::Authenticated)
+            .await
+            .unwrap();
+        let venue = result
+            .iter()
+            .find(|i| i.meal_venue_id == meal_venue_id)
+            .unwrap();
+        assert_eq!(venue.visits, 1);
+    }
+
+    #[tokio::test]
+    /// Meal tags are included in "/all", and can be used to filter both search and export
+    async fn api_router_food_tags_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let tags = [
+            test_setup.insert_test_tag().await,
+            test_setup.insert_test_tag().await,
+        ];
+        let unused_tag = test_setup.insert_test_tag().await;
+
+        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(false);
+        body.tags = vec![tags[0], tags[1], unused_tag + 1000];
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "unknown tag");
+
+        body.tags = tags.to_vec();
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(test_setup.query_meal().await.unwrap().tags, tags.to_vec());
+
+        let result = client
+            .get(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                FoodRoutes::All.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let meal_tags = result["g"].as_object().unwrap();
+        for tag in tags {
+            assert!(meal_tags.contains_key(&tag.to_string()));
+        }
+        assert!(!meal_tags.contains_key(&unused_tag.to_string()));
+        let date = body
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let date_meal = result["m"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["a"] == date.as_str())
+            .unwrap();
+        assert_eq!(date_meal["p"]["J"]["g"], serde_json::json!(tags));
+
+        for (filter, expected) in [
+            (format!("{}", tags[0]), 1),
+            (format!("{},{}", tags[1], tags[0]), 1),
+            (format!("{},{unused_tag}", tags[0]), 0),
+        ] {
+            let result = client
+                .get(format!(
+                    "{}{}?q={}&tags={filter}",
+                    base_url(&test_setup.app_env),
+                    FoodRoutes::Search.addr(),
+                    body.description
+                ))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            let result = result.json::<Response>().await.unwrap().response;
+            let meals = result["meals"].as_array().unwrap();
+            assert_eq!(meals.len(), expected);
+            if expected == 1 {
+                assert_eq!(meals[0]["tags"], serde_json::json!(tags));
+            }
+
+            let result = client
+                .get(format!(
+                    "{}{}?format=jsonl&person=Jack&from={date}&to={date}&tags={filter}",
+                    base_url(&test_setup.app_env),
+                    FoodRoutes::Export.addr(),
+                    date = body.date
+                ))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            assert_eq!(result.text().await.unwrap().lines().count(), expected);
+        }
+    }
+
+    #[tokio::test]
+    /// Invalid cursor returns an error
+    async fn api_router_food_changes_invalid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+
+        let url = format!(
+            "{}{}?since=1-2-3",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Changes.addr()
+        );
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "cursor");
+    }
+
+    #[tokio::test]
+    /// The description, and category, of a lunch meal are included in the changes
+    async fn api_router_food_changes_slots() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Changes.addr()
+        );
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let cursor = result.json::<Response>().await.unwrap().response["cursor"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        let mut body = test_setup.gen_meal(false);
+        body.slot = Some(S!("lunch"));
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client
+            .get(format!("{url}?since={cursor}"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let meal = &result["m"][0]["l"]["J"];
+        assert_eq!(result["d"][meal["m"].to_string()], body.description);
+        assert_eq!(
+            result["c"][meal["c"].to_string()],
+            body.category.to_uppercase()
+        );
+    }
+
+    #[tokio::test]
+    /// Inserted, and then deleted, meals are returned as changes since the cursor
+    async fn api_router_food_changes_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Changes.addr()
+        );
+        let get_changes = async |since: &str| {
+            let result = client
+                .get(format!("{url}?since={since}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            result.json::<Response>().await.unwrap().response
+        };
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let cursor = result.json::<Response>().await.unwrap().response["cursor"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        let body = test_setup.gen_meal(false);
+        let date = body
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+        let result = client
+            .post(&meal_url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = get_changes(&cursor).await;
+        let new_cursor = result["cursor"].as_str().unwrap().to_owned();
+        assert_ne!(cursor, new_cursor);
+        assert!(result["x"].as_array().unwrap().is_empty());
+        let date_meals = result["m"].as_array().unwrap();
+        assert_eq!(date_meals.len(), 1);
+        assert_eq!(date_meals[0]["a"], date);
+        let meal = &date_meals[0]["p"]["J"];
+        let description_id = meal["m"].to_string();
+        let category_id = meal["c"].to_string();
+        assert_eq!(result["d"][&description_id], body.description);
+        assert_eq!(result["c"][&category_id], body.category.to_uppercase());
+        assert_eq!(result["p"]["J"], "Jack");
+
+        // No changes since the new cursor
+        let result = get_changes(&new_cursor).await;
+        assert_eq!(result["cursor"], new_cursor);
+        assert!(result["m"].as_array().unwrap().is_empty());
+        assert!(result["x"].as_array().unwrap().is_empty());
+        assert!(result["d"].as_object().unwrap().is_empty());
+        assert!(result["c"].as_object().unwrap().is_empty());
+
+        let result = client
+            .delete(format!("{meal_url}/{}/Jack", body.date))
+            .header("cookie", &authed_cookie)
+            .json(&HashMap::from([("password", TEST_PASSWORD)]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = get_changes(&new_cursor).await;
+        assert_ne!(result["cursor"], new_cursor);
+        assert!(result["m"].as_array().unwrap().is_empty());
+        assert_eq!(result["x"], serde_json::json!([date]));
+    }
+
+    #[tokio::test]
+    /// Unauthenticated user unable to access "/stats" route
+    async fn api_router_food_stats_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Stats.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Get the meal stats, check that they get inserted into redis cache, and that the cache is removed when a meal is added
+    async fn api_router_food_stats_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+
+        let mut body = test_setup.gen_meal(false);
+        body.vegetarian = true;
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+        let result = client
+            .post(&meal_url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Stats.addr()
+        );
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+
+        let jack = result
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["person"] == "Jack")
+            .unwrap();
+        let year = &jack["years"][&body.date[..4]];
+        assert!(year["total"].as_u64().unwrap() >= 1);
+        assert_eq!(year["categories"][body.category.to_uppercase()], 1);
+        assert!(year["longest_vegetarian_streak"].as_u64().unwrap() >= 1);
+        assert!(jack["all"]["total"].as_u64().unwrap() >= year["total"].as_u64().unwrap());
+
+        let key = "cache::stats::admin";
+        let redis_cache: bool = test_setup.redis.exists(key).await.unwrap();
+        assert!(redis_cache);
+
+        let result = client
+            .delete(format!("{meal_url}/{}/Jack", body.date))
+            .header("cookie", &authed_cookie)
+            .json(&HashMap::from([("password", TEST_PASSWORD)]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let redis_cache: bool = test_setup.redis.exists(key).await.unwrap();
+        assert!(!redis_cache);
+    }
+
+    #[tokio::test]
+    /// Unauthenticated user unable to access "/export" route
+    async fn api_router_food_export_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let url = format!(
+            "{}{}?format=csv",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Export.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Invalid export params return the param name
+    async fn api_router_food_export_invalid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Export.addr()
+        );
+
+        for (query, expected) in [
+            ("", "missing format"),
+            ("?person=Jack", "missing format"),
+            ("?format=xml", "format"),
+            ("?format=csv&person=Nobody", "person"),
+            ("?format=csv&to=2020-13-01", "date"),
+            ("?format=csv&from=2020-02-01&to=2020-01-01", "date range"),
+            ("?format=csv&unknown=1", "invalid input"),
+        ] {
+            let result = client
+                .get(format!("{url}{query}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[tokio::test]
+    /// Export a meal as csv, json lines, and an iCalendar, with the correct headers
+    async fn api_router_food_export_ok() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let body = test_setup.gen_meal(false);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}{}?person=Jack&from={date}&to={date}",
+            base_url(&test_setup.app_env),
+            FoodRoutes::Export.addr(),
+            date = body.date
+        );
+        let export = |format: &'static str| {
+            let request = client
+                .get(format!("{url}&format={format}"))
+                .header("cookie", &authed_cookie);
+            async move {
+                let result = request.send().await.unwrap();
+                assert_eq!(result.status(), StatusCode::OK);
+                assert_eq!(
+                    result.headers().get("content-disposition").unwrap(),
+                    &format!("attachment; filename=\"mealpedant.{format}\"")
+                );
+                let content_type = result
+                    .headers()
+                    .get("content-type")
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_owned();
+                (content_type, result.text().await.unwrap())
+            }
+        };
+
+        let (content_type, result) = export("csv").await;
+        assert_eq!(content_type, "text/csv; charset=utf-8");
+        assert_eq!(
+            result,
+            format!(
+                "date,category,person,slot,restaurant,takeaway,vegetarian,description,photos,visibility,tags,venue_id\r\n{},{},Jack,dinner,false,true,false,{},,,,\r\n",
+                body.date,
+                body.category.to_uppercase(),
+                body.description
+            )
+        );
+
+        let (content_type, result) = export("jsonl").await;
+        assert_eq!(content_type, "application/jsonl; charset=utf-8");
+        let lines = result.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+        let meal = serde_json::from_str::<serde_json::Value>(lines[0]).unwrap();
+        assert_eq!(meal["date"], body.date);
+        assert_eq!(meal["person"], "Jack");
+        assert_eq!(meal["description"], body.description);
+        assert_eq!(meal["takeaway"], true);
+
+        let (content_type, result) = export("ics").await;
+        assert_eq!(content_type, "text/calendar; charset=utf-8");
+        assert!(result.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(result.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert_eq!(result.matches("BEGIN:VEVENT").count(), 1);
+        assert!(result.contains(&format!(
+            "DTSTART;VALUE=DATE:{}\r\n",
+            body.date.replace('-', "")
+        )));
+        assert!(result.contains(&format!("SUMMARY:Jack: {}\r\n", body.description)));
+        assert!(result.contains("DESCRIPTION:takeaway\r\n"));
+
+        // Filters exclude the meal
+        let result = client
+            .get(format!(
+                "{}{}?format=csv&person=Dave&from={}",
+                base_url(&test_setup.app_env),
+                FoodRoutes::Export.addr(),
+                body.date
+            ))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(result.text().await.unwrap().lines().count(), 1);
+    }
 }
@@ -12,8 +12,8 @@ use crate::{
     api_error::ApiError,
     argon::ArgonHash,
     database::{
-        MealResponse, ModelBannedEmail, ModelLogin, ModelPasswordReset, ModelUser,
-        ModelUserAgentIp, RedisNewUser, RedisSession,
+        MealResponse, ModelBannedEmail, ModelLogin, ModelMeal, ModelPasswordReset, ModelUser,
+        ModelUserAgentIp, RedisNewUser, RedisSession, Visibility,
     },
     define_routes,
     emailer::{Email, EmailTemplate},
@@ -23,15 +23,15 @@ use crate::{
         api::{ApiRouter, ApiState},
         authentication::{authenticate_signin, authenticate_token, not_authenticated},
         deserializer::IncomingDeserializer,
-        get_cookie_ulid, ij,
-        oj::{self, MealInfo},
+        export::export_response,
+        get_cookie_ulid, ij, meals_response, oj,
     },
 };
 use axum::{
     Router,
     extract::{Path, State},
     middleware,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 
@@ -45,6 +45,9 @@ define_routes! {
     Signin => "/signin",
     VerifyParam => "/verify/{secret}",
     Meals => "/meals",
+    MealsExport => "/meals/export",
+    MealsSearch => "/meals/search",
+    MealsOnThisDay => "/meals/on-this-day",
     MealHash => "/hash"
 }
 
@@ -92,6 +95,18 @@ impl ApiRouter for IncognitoRouter {
             )
             .layer(middleware::from_fn_with_state(C!(state), not_authenticated))
             .route(&IncognitoRoutes::Meals.addr(), get(Self::meals_get))
+            .route(
+                &IncognitoRoutes::MealsExport.addr(),
+                get(Self::meals_export_get),
+            )
+            .route(
+                &IncognitoRoutes::MealsOnThisDay.addr(),
+                get(Self::meals_on_this_day_get),
+            )
+            .route(
+                &IncognitoRoutes::MealsSearch.addr(),
+                get(Self::meals_search_get),
+            )
             .route(&IncognitoRoutes::MealHash.addr(), get(Self::hash_get))
             .route(&IncognitoRoutes::Signin.addr(), post(Self::signin_post))
             .route(&IncognitoRoutes::Online.addr(), get(Self::get_online))
@@ -111,12 +126,53 @@ impl IncognitoRouter {
         )
     }
 
-    async fn meals_get(State(state): State<ApiState>) -> Result<Outgoing<MealInfo>, ApiError> {
+    async fn meals_get(
+        State(state): State<ApiState>,
+        if_none_match: ij::IfNoneMatch,
+        accept_format: ij::AcceptFormat,
+        accept_encoding: ij::AcceptEncoding,
+    ) -> Result<Response, ApiError> {
+        meals_response(
+            &state,
+            Visibility::Public,
+            &if_none_match,
+            accept_format,
+            accept_encoding,
+        )
+        .await
+    }
+
+    /// Download every public meal, as csv, json lines, or an iCalendar
+    #[expect(clippy::unused_async)]
+    async fn meals_export_get(
+        State(state): State<ApiState>,
+        ij::Query(export): ij::Query<ij::MealExport>,
+    ) -> Result<Response, ApiError> {
+        export_response(&state.postgres, &state.domain, export, Visibility::Public)
+    }
+
+    /// Search meals, ranked by relevance, only public meals are searched
+    async fn meals_search_get(
+        State(state): State<ApiState>,
+        ij::Query(search): ij::Query<ij::MealSearch>,
+    ) -> Result<Outgoing<oj::MealSearch>, ApiError> {
+        let meals = ModelMeal::search(&state.postgres, &search, Visibility::Public).await?;
         Ok((
             axum::http::StatusCode::OK,
-            oj::OutgoingJson::new(
-                MealResponse::get_all(&state.postgres, &state.redis, None).await?,
-            ),
+            oj::OutgoingJson::new(oj::MealSearch::from_model(meals, search.page)),
+        ))
+    }
+
+    /// Get the public meals from the same month and day as the given date, or today, in previous years
+    async fn meals_on_this_day_get(
+        State(state): State<ApiState>,
+        ij::Query(query): ij::Query<ij::OnThisDay>,
+    ) -> Result<Outgoing<oj::OnThisDay>, ApiError> {
+        let date = query.date.unwrap_or_else(|| helpers::now_utc().date());
+        let meals = ModelMeal::on_this_day(&state.postgres, date, Visibility::Public).await?;
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(oj::OnThisDay::from_model(date, meals)),
         ))
     }
 
@@ -124,7 +180,7 @@ impl IncognitoRouter {
         Ok((
             axum::http::StatusCode::OK,
             oj::OutgoingJson::new(
-                MealResponse::get_hash(&state.postgres, &state.redis, None).await?,
+                MealResponse::get_hash(&state.postgres, &state.redis, Visibility::Public).await?,
             ),
         ))
     }
@@ -469,13 +525,14 @@ impl IncognitoRouter {
 #[expect(clippy::pedantic, clippy::unwrap_used)]
 mod tests {
 
-    use crate::database::{ModelLogin, ModelPasswordReset, RedisNewUser, RedisSession};
+    use crate::database::{ModelLogin, ModelPasswordReset, Person, RedisNewUser, RedisSession};
     use crate::helpers::gen_random_hex;
     use crate::parse_env::AppEnv;
+    use crate::servers::BodyFormat;
     use crate::servers::api::routers::incognito::IncognitoRoutes;
     use crate::servers::api_tests::{
-        Response, TEST_EMAIL, TEST_PASSWORD, TEST_PASSWORD_HASH, TestSetup, base_url, get_keys,
-        start_both_servers,
+        Response, TEST_EMAIL, TEST_PASSWORD, TEST_PASSWORD_HASH, TEST_PERSON, TEST_PERSON_INITIAL,
+        TestBodyMealPatch, TestSetup, base_url, get_keys, start_both_servers,
     };
     use crate::servers::deserializer::IncomingDeserializer;
     use crate::{C, S, sleep, tmp_file};
@@ -1511,6 +1568,65 @@ mod tests {
         assert_eq!(login_count.unwrap().unwrap().login_attempt_number, 0);
     }
 
+    #[tokio::test]
+    /// Request bodies can be MessagePack, or CBOR, and json responses are converted into the format given in the Accept header
+    async fn api_router_incognito_signin_post_binary_formats() {
+        let mut test_setup = start_both_servers().await;
+        test_setup.insert_test_user().await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/incognito/signin", base_url(&test_setup.app_env));
+
+        for (format, name) in [
+            (BodyFormat::MessagePack, "MessagePack"),
+            (BodyFormat::Cbor, "CBOR"),
+        ] {
+            let body = TestSetup::gen_signin_body(None, None, None, None);
+            let result = client
+                .post(&url)
+                .header("content-type", format.content_type())
+                .body(format.serialize(&body).unwrap())
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+
+            let mut body = serde_json::to_value(&body).unwrap();
+            body.as_object_mut().unwrap().remove("password");
+            let result = client
+                .post(&url)
+                .header("content-type", format.content_type())
+                .header("accept", format.content_type())
+                .body(format.serialize(&body).unwrap())
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(
+                result.headers().get("content-type").unwrap(),
+                format.content_type()
+            );
+            let result = format
+                .deserialize::<Response>(&result.bytes().await.unwrap())
+                .unwrap();
+            assert_eq!(result.response, "missing password");
+
+            let result = client
+                .post(&url)
+                .header("content-type", format.content_type())
+                .body(if format == BodyFormat::Cbor {
+                    vec![0x1c]
+                } else {
+                    vec![0xc1]
+                })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(result.headers().get("vary").unwrap(), "Accept");
+            assert_eq!(result.json::<Response>().await.unwrap().response, name);
+        }
+    }
+
     #[tokio::test]
     /// When two factor enabled, but no token provided, should return a 202 message
     async fn api_router_incognito_signin_post_login_no_token() {
@@ -1809,6 +1925,10 @@ mod tests {
 
         let result = result.json::<Response>().await.unwrap().response;
 
+        let people = result.get("p").unwrap().as_object().unwrap();
+        assert_eq!(people.len(), 1);
+        assert_eq!(people.get("J").unwrap(), "Jack");
+
         let descriptions = result.get("d");
         assert!(descriptions.is_some());
         let descriptions = descriptions.unwrap().as_object().unwrap();
@@ -1842,7 +1962,7 @@ mod tests {
         let mut photo_count = 0;
 
         for i in meal_dates {
-            // assert each has a d, and j object, and a c object, and each j & d object should have a c, and m
+            // assert each has a people object, containing only a J object, and each J object should have a c, and m
             let entry = i.as_object().unwrap();
             let meal_date = entry.get("a");
             assert!(meal_date.is_some());
@@ -1851,9 +1971,10 @@ mod tests {
             assert!(meal_date.as_str().unwrap().chars().count() == 6);
             assert!(meal_date.as_str().unwrap().chars().all(char::is_numeric));
 
-            assert!(entry.get("d").is_none());
+            let people = entry.get("p").unwrap().as_object().unwrap();
+            assert!(people.get("D").is_none());
 
-            let person = entry.get("j");
+            let person = people.get("J");
             assert!(person.is_some());
             let person = person.unwrap();
             assert!(person.is_object());
@@ -1890,7 +2011,7 @@ mod tests {
         // Check redis cache
         let redis_cache: Option<String> = test_setup
             .redis
-            .hget("cache::jack_meals", "data")
+            .hget("cache::meals::public", "json")
             .await
             .unwrap();
         assert!(redis_cache.is_some());
@@ -1914,6 +2035,10 @@ mod tests {
 
         let result = result.json::<Response>().await.unwrap().response;
 
+        let people = result.get("p").unwrap().as_object().unwrap();
+        assert_eq!(people.len(), 1);
+        assert_eq!(people.get("J").unwrap(), "Jack");
+
         let descriptions = result.get("d");
         assert!(descriptions.is_some());
         let descriptions = descriptions.unwrap().as_object().unwrap();
@@ -1947,7 +2072,7 @@ mod tests {
         let mut photo_count = 0;
 
         for i in meal_dates {
-            // assert each has a d, and j object, and a c object, and each j & d object should have a c, and m
+            // assert each has a people object, containing only a J object, and each J object should have a c, and m
             let entry = i.as_object().unwrap();
             let meal_date = entry.get("a");
             assert!(meal_date.is_some());
@@ -1956,9 +2081,10 @@ mod tests {
             assert!(meal_date.as_str().unwrap().chars().count() == 6);
             assert!(meal_date.as_str().unwrap().chars().all(char::is_numeric));
 
-            assert!(entry.get("d").is_none());
+            let people = entry.get("p").unwrap().as_object().unwrap();
+            assert!(people.get("D").is_none());
 
-            let person = entry.get("j");
+            let person = people.get("J");
             assert!(person.is_some());
             let person = person.unwrap();
             assert!(person.is_object());
@@ -1995,7 +2121,7 @@ mod tests {
         // Check redis cache
         let redis_cache: Option<String> = test_setup
             .redis
-            .hget("cache::jack_meals", "data")
+            .hget("cache::meals::public", "json")
             .await
             .unwrap();
         assert!(redis_cache.is_some());
@@ -2031,13 +2157,286 @@ mod tests {
         // Check redis cache
         let redis_cache: Option<String> = test_setup
             .redis
-            .get("cache::jack_meals_hash")
+            .get("cache::meals_hash::public")
             .await
             .unwrap();
         assert!(redis_cache.is_some());
         assert_eq!(redis_cache.unwrap(), result);
     }
 
+    #[tokio::test]
+    /// A meal visibility overrides the person visibility, an admin only Jack meal is hidden, whereas a public meal of a non-public person is shown
+    async fn api_router_incognito_get_food_meal_visibility() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+
+        let client = reqwest::Client::new();
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+
+        let mut jack_meal = test_setup.gen_meal(false);
+        jack_meal.visibility = Some(S!("admin"));
+        let mut test_person_meal = C!(jack_meal);
+        test_person_meal.person = S!(TEST_PERSON);
+        test_person_meal.visibility = Some(S!("public"));
+
+        for body in [&jack_meal, &test_person_meal] {
+            let result = client
+                .post(&meal_url)
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            IncognitoRoutes::Meals.addr()
+        );
+        let result = client.get(url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+
+        let people = result.get("p").unwrap().as_object().unwrap();
+        assert_eq!(people.get(TEST_PERSON_INITIAL).unwrap(), TEST_PERSON);
+        assert!(people.get("D").is_none());
+
+        let date = jack_meal
+            .date
+            .chars()
+            .skip(2)
+            .collect::<String>()
+            .replace('-', "");
+        let date_meal = result
+            .get("m")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i.get("a").unwrap() == date.as_str())
+            .unwrap();
+        let date_people = date_meal.get("p").unwrap().as_object().unwrap();
+        assert!(date_people.get("J").is_none());
+        assert!(date_people.get(TEST_PERSON_INITIAL).is_some());
+    }
+
+    #[tokio::test]
+    /// A non-public person is included when their only public meal is a breakfast, or lunch, rather than a dinner
+    async fn api_router_incognito_get_food_meal_visibility_slot() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+
+        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(false);
+        body.person = S!(TEST_PERSON);
+        body.slot = Some(S!("breakfast"));
+        body.visibility = Some(S!("public"));
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        test_setup.warmed_meals_cache().await;
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            IncognitoRoutes::Meals.addr()
+        );
+        let result = client.get(url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["p"][TEST_PERSON_INITIAL], TEST_PERSON);
+        assert_eq!(
+            result["n"][TEST_PERSON_INITIAL]["d"]
+                .as_object()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    /// Public meals have a strong ETag, shared caches may store them, and a matching If-None-Match returns an empty 304
+    async fn api_router_incognito_meals_etag() {
+        let test_setup = start_both_servers().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            IncognitoRoutes::Meals.addr()
+        );
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            result.headers().get("cache-control").unwrap(),
+            "public, no-cache"
+        );
+        let etag = result
+            .headers()
+            .get("etag")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        let redis_etag: Option<String> = test_setup
+            .redis
+            .hget("cache::meals::public", "etag")
+            .await
+            .unwrap();
+        // reqwest accepts brotli, so the ETag is that of the brotli encoding
+        assert_eq!(format!("\"{}-br\"", redis_etag.unwrap()), etag);
+
+        let result = client
+            .get(&url)
+            .header("if-none-match", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(result.headers().get("etag").unwrap(), etag.as_str());
+        assert_eq!(
+            result.headers().get("cache-control").unwrap(),
+            "public, no-cache"
+        );
+        assert!(result.bytes().await.unwrap().is_empty());
+
+        let result = client
+            .get(&url)
+            .header("if-none-match", "\"abc\"")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(result.headers().get("etag").unwrap(), etag.as_str());
+    }
+
+    #[tokio::test]
+    /// On this day only returns public meals, and never includes the original photo
+    async fn api_router_incognito_meals_on_this_day() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+
+        let client = reqwest::Client::new();
+        let body = test_setup.gen_meal(true);
+        let meals = [("2018-06-15", Some("public")), ("2019-06-15", None)];
+        for (date, visibility) in meals {
+            let mut meal = C!(body);
+            meal.person = S!(TEST_PERSON);
+            meal.date = S!(date);
+            meal.visibility = visibility.map(|i| S!(i));
+            let result = client
+                .post(format!("{}/meal", base_url(&test_setup.app_env)))
+                .header("cookie", &authed_cookie)
+                .json(&meal)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let result = client
+            .get(format!(
+                "{}{}?date=2020-06-15",
+                base_url(&test_setup.app_env),
+                IncognitoRoutes::MealsOnThisDay.addr()
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let meals_found = result["years"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|year| year["meals"].as_array().unwrap())
+            .filter(|i| i["person"] == TEST_PERSON)
+            .collect::<Vec<_>>();
+        assert_eq!(meals_found.len(), 1);
+        assert_eq!(meals_found[0]["date"], "2018-06-15");
+        assert_eq!(
+            meals_found[0]["photos"][0]["converted"],
+            body.photos[0].converted
+        );
+        assert!(meals_found[0]["photos"][0]["original"].is_null());
+
+        let person = Person::try_from(TEST_PERSON).unwrap();
+        for (date, _) in meals {
+            test_setup.purge_meal(&person, date.parse().unwrap()).await;
+        }
+    }
+
+    #[tokio::test]
+    /// Search only returns public meals, and never includes the original photo
+    async fn api_router_incognito_meals_search_visibility() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let client = reqwest::Client::new();
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+        let body = test_setup.gen_meal(true);
+        let result = client
+            .post(&meal_url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}{}?q={}",
+            base_url(&test_setup.app_env),
+            IncognitoRoutes::MealsSearch.addr(),
+            body.description
+        );
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let meals = result["meals"].as_array().unwrap();
+        assert_eq!(meals.len(), 1);
+        assert_eq!(meals[0]["person"], "Jack");
+        assert_eq!(meals[0]["photos"][0]["converted"], body.photos[0].converted);
+        assert!(meals[0]["photos"][0]["original"].is_null());
+
+        let mut meal = C!(body);
+        meal.visibility = Some(S!("authenticated"));
+        let result = client
+            .patch(&meal_url)
+            .header("cookie", &authed_cookie)
+            .json(&TestBodyMealPatch {
+                original_date: C!(body.date),
+                original_slot: None,
+                meal,
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert!(result["meals"].as_array().unwrap().is_empty());
+        assert_eq!(result["total"], 0);
+    }
+
     #[tokio::test]
     /// Get the current hash of all meals, check that it gets inserted into redis cache
     async fn api_router_incognito_hash_unauth_ok() {
@@ -2062,10 +2461,61 @@ mod tests {
         // Check redis cache
         let redis_cache: Option<String> = test_setup
             .redis
-            .get("cache::jack_meals_hash")
+            .get("cache::meals_hash::public")
             .await
             .unwrap();
         assert!(redis_cache.is_some());
         assert_eq!(redis_cache.unwrap(), result);
     }
+
+    #[tokio::test]
+    /// Export only includes public meals, and never includes the original photo
+    async fn api_router_incognito_meals_export_visibility() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+
+        let client = reqwest::Client::new();
+        let meal_url = format!("{}/meal", base_url(&test_setup.app_env));
+
+        let mut jack_meal = test_setup.gen_meal(false);
+        jack_meal.visibility = Some(S!("admin"));
+        let mut test_person_meal = test_setup.gen_meal(true);
+        test_person_meal.person = S!(TEST_PERSON);
+        test_person_meal.visibility = Some(S!("public"));
+
+        for body in [&jack_meal, &test_person_meal] {
+            let result = client
+                .post(&meal_url)
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let url = format!(
+            "{}{}?format=jsonl&from={date}&to={date}",
+            base_url(&test_setup.app_env),
+            IncognitoRoutes::MealsExport.addr(),
+            date = jack_meal.date
+        );
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.text().await.unwrap();
+        let meals = result
+            .lines()
+            .map(|i| serde_json::from_str::<serde_json::Value>(i).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(meals.len(), 1);
+        assert_eq!(meals[0]["person"], TEST_PERSON);
+        assert_eq!(meals[0]["description"], test_person_meal.description);
+        assert_eq!(
+            meals[0]["photos"][0]["converted"],
+            test_person_meal.photos[0].converted
+        );
+        assert!(meals[0]["photos"][0]["original"].is_null());
+    }
 }
@@ -1,15 +1,19 @@
 use axum::{
     Router,
     extract::State,
+    http::{StatusCode, header},
     middleware,
-    routing::{delete, get, patch},
+    response::{AppendHeaders, IntoResponse},
+    routing::{delete, get, patch, post, put},
 };
 
 use crate::{
     C, S,
     api::{ApiRouter, ApiState},
     api_error::ApiError,
-    database::{FromModel, MealResponse, ModelMeal, ModelMissingFood, ModelUser},
+    database::{
+        FromModel, MealResponse, ModelMeal, ModelMealRevision, ModelMissingFood, ModelUser,
+    },
     define_routes,
     servers::{
         Outgoing,
@@ -22,8 +26,14 @@ define_routes! {
     MealRoutes,
     "/meal",
     Base => "",
+    Import => "/import",
     Missing => "/missing",
-    ParamDatePerson => "/{date}/{person}"
+    ParamDatePerson => "/{date}/{person}",
+    ParamDatePersonRevision => "/{date}/{person}/revision",
+    ParamDatePersonRevisionParam => "/{date}/{person}/revision/{revision}",
+    ParamDatePersonSlot => "/{date}/{person}/{slot}",
+    ParamDatePersonSlotRevision => "/{date}/{person}/{slot}/revision",
+    ParamDatePersonSlotRevisionParam => "/{date}/{person}/{slot}/revision/{revision}"
 }
 
 pub struct MealRouter;
@@ -32,6 +42,7 @@ impl ApiRouter for MealRouter {
     fn create_router(state: &ApiState) -> axum::Router<ApiState> {
         Router::new()
             .route(&MealRoutes::Missing.addr(), get(Self::missing_get))
+            .route(&MealRoutes::Import.addr(), post(Self::import_post))
             .route(
                 &MealRoutes::Base.addr(),
                 patch(Self::base_patch).post(Self::base_post),
@@ -40,30 +51,79 @@ impl ApiRouter for MealRouter {
                 &MealRoutes::ParamDatePerson.addr(),
                 delete(Self::param_date_person_delete).get(Self::param_date_person_get),
             )
+            .route(
+                &MealRoutes::ParamDatePersonRevision.addr(),
+                get(Self::param_date_person_revision_get),
+            )
+            .route(
+                &MealRoutes::ParamDatePersonRevisionParam.addr(),
+                put(Self::param_date_person_revision_param_put),
+            )
+            .route(
+                &MealRoutes::ParamDatePersonSlot.addr(),
+                delete(Self::param_date_person_delete).get(Self::param_date_person_get),
+            )
+            .route(
+                &MealRoutes::ParamDatePersonSlotRevision.addr(),
+                get(Self::param_date_person_revision_get),
+            )
+            .route(
+                &MealRoutes::ParamDatePersonSlotRevisionParam.addr(),
+                put(Self::param_date_person_revision_param_put),
+            )
             .layer(middleware::from_fn_with_state(C!(state), is_admin))
     }
 }
 
 impl MealRouter {
-    /// Update meal
+    /// Update meal, the body is a JSON merge patch of the current meal
+    /// If an `If-Match` header is given, it must match the current ETag of the meal
+    /// A patch that changes nothing doesn't update the meal, and returns the current ETag
     async fn base_patch(
         State(state): State<ApiState>,
         user: ModelUser,
+        ij::IfMatch(if_match): ij::IfMatch,
         ij::IncomingJson(body): ij::IncomingJson<ij::MealPatch>,
-    ) -> Result<axum::http::StatusCode, ApiError> {
-        match ModelMeal::get_by_date_person(&state.postgres, &body.meal.person, body.original_date)
+    ) -> Result<impl IntoResponse, ApiError> {
+        let person = body.person()?;
+        let Some(original_meal) = ModelMeal::get_by_date_person(
+            &state.postgres,
+            &person,
+            body.original_date,
+            body.original_slot,
+        )
+        .await?
+        else {
+            return Err(ApiError::InvalidValue(S!("unknown meal")));
+        };
+        let latest =
+            ModelMealRevision::latest(&state.postgres, original_meal.individual_meal_id).await?;
+        if if_match.is_some() && latest != if_match {
+            return Err(ApiError::Conflict(S!("meal has been modified")));
+        }
+        let meal = body.apply(serde_json::to_value(oj::Meal::from(C!(original_meal)))?)?;
+        // Applying a merge patch that changes nothing is a success, the meal is already as requested
+        if ij::Meal::from_model(&original_meal)? == meal {
+            return Ok((
+                StatusCode::OK,
+                AppendHeaders(latest.map(|i| (header::ETAG, oj::etag(i)))),
+            ));
+        }
+        if ModelMeal::get_by_date_person(&state.postgres, &meal.person, meal.date, meal.slot)
             .await?
+            .is_some_and(|i| i.individual_meal_id != original_meal.individual_meal_id)
         {
-            Some(original_meal) => {
-                if ij::Meal::from_model(&original_meal)? == body.meal {
-                    return Err(ApiError::InvalidValue(S!("no changes")));
-                }
-                ModelMeal::update(&state.postgres, &body.meal, &user, &original_meal).await?;
-                MealResponse::cache_delete(&state.redis).await?;
-                Ok(axum::http::StatusCode::OK)
-            }
-            _ => Err(ApiError::InvalidValue(S!("unknown meal"))),
+            return Err(ApiError::InvalidValue(S!(
+                "Meal already exists on date and person given"
+            )));
         }
+        let revision =
+            ModelMeal::update(&state.postgres, &meal, &user, &original_meal, if_match).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok((
+            StatusCode::OK,
+            AppendHeaders(revision.map(|i| (header::ETAG, oj::etag(i)))),
+        ))
     }
 
     /// insert new meal
@@ -72,7 +132,7 @@ impl MealRouter {
         user: ModelUser,
         ij::IncomingJson(body): ij::IncomingJson<ij::Meal>,
     ) -> Result<axum::http::StatusCode, ApiError> {
-        if ModelMeal::get_by_date_person(&state.postgres, &body.person, body.date)
+        if ModelMeal::get_by_date_person(&state.postgres, &body.person, body.date, body.slot)
             .await?
             .is_some()
         {
@@ -81,11 +141,73 @@ impl MealRouter {
             )))
         } else {
             ModelMeal::insert(&state.postgres, &body, &user).await?;
-            MealResponse::cache_delete(&state.redis).await?;
+            MealResponse::cache_delete(&state.postgres, &state.redis).await?;
             Ok(axum::http::StatusCode::OK)
         }
     }
 
+    /// Bulk insert meals, every row is validated, and checked against existing meals and the other rows
+    /// Only inserts, in a single transaction, if `dry_run` is false and every row is valid
+    async fn import_post(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Query(query): ij::Query<ij::MealImport>,
+        ij::ImportMeals(rows): ij::ImportMeals,
+    ) -> Result<Outgoing<oj::MealImport>, ApiError> {
+        let mut errors = vec![];
+        let mut meals: Vec<ij::Meal> = vec![];
+        for (index, row) in rows.into_iter().enumerate() {
+            let error = match row {
+                Ok(meal) => {
+                    if meals.iter().any(|i| {
+                        i.date == meal.date && i.person == meal.person && i.slot == meal.slot
+                    }) {
+                        Some(S!("duplicate meal"))
+                    } else if ModelMeal::get_by_date_person(
+                        &state.postgres,
+                        &meal.person,
+                        meal.date,
+                        meal.slot,
+                    )
+                    .await?
+                    .is_some()
+                    {
+                        Some(S!("Meal already exists on date and person given"))
+                    } else {
+                        meals.push(meal);
+                        None
+                    }
+                }
+                Err(e) => Some(e),
+            };
+            if let Some(error) = error {
+                errors.push(oj::ImportError {
+                    row: index + 1,
+                    error,
+                });
+            }
+        }
+
+        let (status, inserted) = if query.dry_run {
+            (axum::http::StatusCode::OK, 0)
+        } else if errors.is_empty() {
+            ModelMeal::insert_many(&state.postgres, &meals, &user).await?;
+            MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+            (axum::http::StatusCode::OK, meals.len())
+        } else {
+            (axum::http::StatusCode::BAD_REQUEST, 0)
+        };
+
+        Ok((
+            status,
+            oj::OutgoingJson::new(oj::MealImport {
+                rows: meals.len() + errors.len(),
+                inserted,
+                errors,
+            }),
+        ))
+    }
+
     /// get list of missing meals
     async fn missing_get(
         State(state): State<ApiState>,
@@ -96,33 +218,108 @@ impl MealRouter {
         ))
     }
 
-    /// Get the information on a single meal, based on date and person
+    /// Get the information on a single meal, based on date, person, and slot, with the meal's ETag
     async fn param_date_person_get(
         State(state): State<ApiState>,
-        ij::Path(ij::DatePerson { date, person }): ij::Path<ij::DatePerson>,
-    ) -> Result<Outgoing<oj::AdminMeal>, ApiError> {
+        ij::Path(ij::DatePerson { date, person, slot }): ij::Path<ij::DatePerson>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let meal = ModelMeal::get_by_date_person(&state.postgres, &person, date, slot).await?;
+        let revision = match &meal {
+            Some(meal) => {
+                ModelMealRevision::latest(&state.postgres, meal.individual_meal_id).await?
+            }
+            None => None,
+        };
         Ok((
-            axum::http::StatusCode::OK,
+            StatusCode::OK,
+            AppendHeaders(revision.map(|i| (header::ETAG, oj::etag(i)))),
             oj::OutgoingJson::new(oj::AdminMeal {
-                meal: ModelMeal::get_by_date_person(&state.postgres, &person, date)
-                    .await?
-                    .map(oj::Meal::from),
+                meal: meal.map(oj::Meal::from),
             }),
         ))
     }
 
-    /// Delete a single meal, based on date and person, requires password/token
+    /// Get the revision history of a single meal, newest first
+    async fn param_date_person_revision_get(
+        State(state): State<ApiState>,
+        ij::Path(ij::DatePerson { date, person, slot }): ij::Path<ij::DatePerson>,
+    ) -> Result<Outgoing<Vec<oj::MealRevision>>, ApiError> {
+        let Some(meal) =
+            ModelMeal::get_by_date_person(&state.postgres, &person, date, slot).await?
+        else {
+            return Err(ApiError::InvalidValue(S!("unknown meal")));
+        };
+        Ok((
+            axum::http::StatusCode::OK,
+            oj::OutgoingJson::new(
+                ModelMealRevision::get(&state.postgres, meal.individual_meal_id, None)
+                    .await?
+                    .into_iter()
+                    .map(oj::MealRevision::from)
+                    .collect(),
+            ),
+        ))
+    }
+
+    /// Revert a single meal to the state it was in at a given revision
+    async fn param_date_person_revision_param_put(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::Path(ij::DatePersonRevision {
+            date,
+            person,
+            slot,
+            revision,
+        }): ij::Path<ij::DatePersonRevision>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        let Some(original_meal) =
+            ModelMeal::get_by_date_person(&state.postgres, &person, date, slot).await?
+        else {
+            return Err(ApiError::InvalidValue(S!("unknown meal")));
+        };
+        let Some(revision) = ModelMealRevision::get(
+            &state.postgres,
+            original_meal.individual_meal_id,
+            Some(revision),
+        )
+        .await?
+        .pop() else {
+            return Err(ApiError::InvalidValue(S!("unknown revision")));
+        };
+
+        let current = ij::Meal::from_model(&original_meal)?;
+        let mut meal = ij::Meal::from_model(revision)?;
+        // Tags aren't recorded in the audit table, so keep the current ones
+        meal.tags = C!(current.tags);
+        if current == meal {
+            return Err(ApiError::InvalidValue(S!("no changes")));
+        }
+        // The revision may be on a different date, slot, or of a different person, that could now have its own meal
+        if ModelMeal::get_by_date_person(&state.postgres, &meal.person, meal.date, meal.slot)
+            .await?
+            .is_some_and(|i| i.individual_meal_id != original_meal.individual_meal_id)
+        {
+            return Err(ApiError::InvalidValue(S!(
+                "Meal already exists on date and person given"
+            )));
+        }
+        ModelMeal::update(&state.postgres, &meal, &user, &original_meal, None).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
+        Ok(axum::http::StatusCode::OK)
+    }
+
+    /// Delete a single meal, based on date, person, and slot, requires password/token
     async fn param_date_person_delete(
         State(state): State<ApiState>,
         user: ModelUser,
-        ij::Path(ij::DatePerson { date, person }): ij::Path<ij::DatePerson>,
+        ij::Path(ij::DatePerson { date, person, slot }): ij::Path<ij::DatePerson>,
         ij::IncomingJson(body): ij::IncomingJson<ij::PasswordToken>,
     ) -> Result<axum::http::StatusCode, ApiError> {
         if !authenticate_password_token(&user, &body.password, body.token, &state.postgres).await? {
             return Err(ApiError::Authorization);
         }
-        ModelMeal::delete(&state.postgres, &person, date).await?;
-        MealResponse::cache_delete(&state.redis).await?;
+        ModelMeal::delete(&state.postgres, &person, date, slot).await?;
+        MealResponse::cache_delete(&state.postgres, &state.redis).await?;
         Ok(axum::http::StatusCode::OK)
     }
 }
@@ -137,10 +334,15 @@ mod tests {
 
     use super::MealRoutes;
     use crate::{
-        C,
-        helpers::gen_random_hex,
-        servers::api_tests::{
-            Response, TEST_PASSWORD, TestBodyMealPatch, base_url, start_both_servers,
+        C, S,
+        database::{ModelMealRevision, ModelMealTrash, Person, admin_queries},
+        helpers::{gen_random_hex, now_utc},
+        servers::{
+            api_tests::{
+                Response, TEST_PASSWORD, TEST_PERSON, TestBodyMealPatch, TestSetup, base_url,
+                start_both_servers,
+            },
+            ij::PhotoName,
         },
     };
 
@@ -239,8 +441,38 @@ mod tests {
         assert_eq!(meal.takeaway, test_meal().takeaway);
         assert_eq!(meal.vegetarian, test_meal().vegetarian);
         assert_eq!(meal.restaurant, test_meal().restaurant);
-        assert!(meal.photo_converted.is_none());
-        assert!(meal.photo_original.is_none());
+        assert!(meal.photos.is_empty());
+    }
+
+    #[tokio::test]
+    /// Authenticated admin user able to add new meal, with a visibility override
+    async fn api_router_meal_base_admin_valid_visibility() {
+        let mut test_setup = start_both_servers().await;
+
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let mut body = test_setup.gen_meal(false);
+        body.visibility = Some(S!("admin"));
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let meal = test_setup.query_meal().await;
+        assert!(meal.is_some());
+        assert_eq!(meal.unwrap().visibility, Some(S!("admin")));
     }
 
     #[tokio::test]
@@ -283,8 +515,7 @@ mod tests {
         assert_eq!(meal.takeaway, test_meal().takeaway);
         assert_eq!(meal.vegetarian, test_meal().vegetarian);
         assert_eq!(meal.restaurant, test_meal().restaurant);
-        assert!(meal.photo_converted.is_none());
-        assert!(meal.photo_original.is_none());
+        assert!(meal.photos.is_empty());
     }
 
     #[tokio::test]
@@ -325,8 +556,16 @@ mod tests {
         assert_eq!(meal.takeaway, test_meal().takeaway);
         assert_eq!(meal.vegetarian, test_meal().vegetarian);
         assert_eq!(meal.restaurant, test_meal().restaurant);
-        assert!(meal.photo_converted.is_some());
-        assert!(meal.photo_original.is_some());
+        assert_eq!(meal.photos.len(), 1);
+        assert_eq!(
+            meal.photos[0].photo_converted,
+            test_meal().photos[0].converted
+        );
+        assert_eq!(
+            meal.photos[0].photo_original.as_deref(),
+            Some(test_meal().photos[0].original.as_str())
+        );
+        assert!(meal.photos[0].caption.is_none());
     }
 
     #[tokio::test]
@@ -365,7 +604,7 @@ mod tests {
     }
 
     #[tokio::test]
-    /// Authenticated admin unable to patch is new meal matches previous meal
+    /// Authenticated admin patch that matches the current meal returns the current ETag, without a new revision
     async fn api_router_meal_base_patch_no_changes() {
         let mut test_setup = start_both_servers().await;
 
@@ -391,9 +630,16 @@ mod tests {
 
         let body = TestBodyMealPatch {
             original_date: C!(body.date),
+            original_slot: None,
             meal: body,
         };
 
+        let meal = test_setup.query_meal().await.unwrap();
+        let latest = ModelMealRevision::latest(&test_setup.postgres, meal.individual_meal_id)
+            .await
+            .unwrap()
+            .unwrap();
+
         let result = client
             .patch(&url)
             .header("cookie", &authed_cookie)
@@ -401,9 +647,17 @@ mod tests {
             .send()
             .await
             .unwrap();
-        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "no changes");
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            result.headers()["etag"].to_str().unwrap(),
+            format!("\"{latest}\"")
+        );
+        assert_eq!(
+            ModelMealRevision::latest(&test_setup.postgres, meal.individual_meal_id)
+                .await
+                .unwrap(),
+            Some(latest)
+        );
     }
 
     #[tokio::test]
@@ -439,11 +693,11 @@ mod tests {
         new_meal.vegetarian = !body.vegetarian;
         new_meal.takeaway = !body.takeaway;
         new_meal.restaurant = !body.restaurant;
-        new_meal.photo_converted = None;
-        new_meal.photo_original = None;
+        new_meal.photos = vec![];
 
         let new_body = TestBodyMealPatch {
             original_date: C!(body.date),
+            original_slot: None,
             meal: new_meal,
         };
 
@@ -474,10 +728,13 @@ mod tests {
         .unwrap();
         assert!(result.is_none());
 
-        for i in ["cache::all_meals", "cache::last_id", "cache::category"] {
+        for i in ["cache::last_id", "cache::category"] {
             let redis_cache: Option<String> = test_setup.redis.get(i).await.unwrap();
             assert!(redis_cache.is_none());
         }
+        let redis_cache = test_setup.warmed_meals_cache().await;
+        assert!(redis_cache.contains(&new_description));
+        assert!(!redis_cache.contains(&body.description));
 
         let url = format!("{}/meal/{}/Jack", base_url(&test_setup.app_env), body.date);
         let result = client
@@ -512,225 +769,1175 @@ mod tests {
             new_body.meal.takeaway
         );
 
-        assert!(result.get("photo_original").unwrap().is_null());
-        assert!(result.get("photo_original").unwrap().is_null());
-    }
-
-    //////////////////////////////////////////////////
-
-    #[tokio::test]
-    /// Unauthenticated user unable to access "/missing" route
-    async fn api_router_meal_missing_unauthenticated() {
-        let test_setup = start_both_servers().await;
-        let url = format!(
-            "{}{}",
-            base_url(&test_setup.app_env),
-            MealRoutes::Base.addr()
-        );
-        let client = reqwest::Client::new();
-
-        let result = client.get(&url).send().await.unwrap();
-        assert_eq!(result.status(), StatusCode::FORBIDDEN);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "Invalid Authentication");
+        assert!(result.get("photos").unwrap().as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
-    /// Authenticated, but not admin user, unable to access "/missing" route
-    async fn api_router_meal_missing_not_admin() {
+    /// A patch only changes the given fields, a null removes an optional field, and the result must still be a valid meal
+    async fn api_router_meal_base_patch_merge() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
         let url = format!(
             "{}{}",
             base_url(&test_setup.app_env),
-            MealRoutes::Missing.addr()
+            MealRoutes::Base.addr()
         );
-        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(false);
+        body.visibility = Some(S!("public"));
+        client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
 
+        let new_description = gen_random_hex(8);
         let result = client
-            .get(&url)
+            .patch(&url)
             .header("cookie", &authed_cookie)
+            .header("content-type", "application/merge-patch+json")
+            .json(&serde_json::json!({
+                "original_date": body.date,
+                "original_person": body.person,
+                "meal": {"description": new_description, "visibility": null}
+            }))
             .send()
             .await
             .unwrap();
-        assert_eq!(result.status(), StatusCode::FORBIDDEN);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "Invalid Authentication");
+        assert_eq!(result.status(), StatusCode::OK);
+        let meal = test_setup.query_meal().await.unwrap();
+        assert_eq!(meal.description, new_description);
+        assert_eq!(meal.category, body.category);
+        assert_eq!(meal.takeaway, body.takeaway);
+        assert_eq!(meal.photos.len(), body.photos.len());
+        assert!(meal.visibility.is_none());
+
+        for (patch, status, expected) in [
+            (
+                serde_json::json!({"original_date": body.date, "meal": {"vegetarian": true}}),
+                StatusCode::BAD_REQUEST,
+                "missing person",
+            ),
+            (
+                serde_json::json!({"original_date": body.date, "meal": {"person": body.person, "category": null}}),
+                StatusCode::BAD_REQUEST,
+                "missing category",
+            ),
+            (
+                serde_json::json!({"original_date": body.date, "meal": {"person": body.person, "slot": "supper"}}),
+                StatusCode::BAD_REQUEST,
+                "slot",
+            ),
+        ] {
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&patch)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), status);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+
+        // An empty patch changes nothing
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&serde_json::json!({"original_date": body.date, "meal": {"person": body.person}}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            test_setup.query_meal().await.unwrap().description,
+            new_description
+        );
     }
 
     #[tokio::test]
-    /// Get list of missing meals - assumes that the db_data isn't up to date!
-    async fn api_router_meal_missing_admin_valid() {
+    /// A meal has an ETag, a patch with a stale If-Match header is rejected
+    #[allow(clippy::too_many_lines)]
+    async fn api_router_meal_base_patch_if_match() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
         test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
         let url = format!(
             "{}{}",
             base_url(&test_setup.app_env),
-            MealRoutes::Missing.addr()
+            MealRoutes::Base.addr()
         );
-        let client = reqwest::Client::new();
-
-        let result = client
-            .get(&url)
+        let body = test_setup.gen_meal(false);
+        client
+            .post(&url)
             .header("cookie", &authed_cookie)
+            .json(&body)
             .send()
             .await
             .unwrap();
-        assert_eq!(result.status(), StatusCode::OK);
-
-        let result = result.json::<Response>().await.unwrap().response;
-        assert!(result.is_array());
-
-        assert!(result.as_array().unwrap().len() > 1);
-        assert!(result.as_array().unwrap()[0]["date"].is_string());
-        assert!(result.as_array().unwrap()[0]["person"].is_string());
-    }
-
-    #[tokio::test]
-    /// Unauthenticated user unable to [GET, DELETE] access "/missing" route
-    async fn api_router_meal_date_person_unauthenticated() {
-        let test_setup = start_both_servers().await;
-        let url = format!("{}/meal/2020-01-01/Jack", base_url(&test_setup.app_env),);
 
-        let client = reqwest::Client::new();
-
-        let result = client.get(&url).send().await.unwrap();
-        assert_eq!(result.status(), StatusCode::FORBIDDEN);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "Invalid Authentication");
+        let get_url = format!("{url}/{}/{}", body.date, body.person);
+        let get_etag = || {
+            let client = C!(client);
+            let get_url = C!(get_url);
+            let authed_cookie = C!(authed_cookie);
+            async move {
+                let result = client
+                    .get(&get_url)
+                    .header("cookie", &authed_cookie)
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(result.status(), StatusCode::OK);
+                result.headers()["etag"].to_str().unwrap().to_owned()
+            }
+        };
+        let patch = |description: &str| {
+            serde_json::json!({
+                "original_date": body.date,
+                "meal": {"person": body.person, "description": description}
+            })
+        };
 
-        let result = client.delete(&url).send().await.unwrap();
-        assert_eq!(result.status(), StatusCode::FORBIDDEN);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "Invalid Authentication");
-    }
+        let etag = get_etag().await;
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
 
-    #[tokio::test]
-    /// Authenticated, but not admin user, unable to [GET, DELETE] "/missing" route
-    async fn api_router_meal_date_person_not_admin() {
-        let mut test_setup = start_both_servers().await;
-        let authed_cookie = test_setup.authed_user_cookie().await;
-        let url = format!("{}/meal/2020-01-01/Jack", base_url(&test_setup.app_env),);
-        let client = reqwest::Client::new();
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .header("if-match", &etag)
+            .json(&patch("first"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let new_etag = result.headers()["etag"].to_str().unwrap().to_owned();
+        assert_ne!(new_etag, etag);
+        assert_eq!(get_etag().await, new_etag);
 
+        // Another user, still using the original ETag, can't overwrite the first update
         let result = client
-            .get(&url)
+            .patch(&url)
             .header("cookie", &authed_cookie)
+            .header("if-match", &etag)
+            .json(&patch("second"))
             .send()
             .await
             .unwrap();
-        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        assert_eq!(result.status(), StatusCode::CONFLICT);
         let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "Invalid Authentication");
+        assert_eq!(result, "meal has been modified");
+        assert_eq!(test_setup.query_meal().await.unwrap().description, "first");
 
         let result = client
-            .delete(&url)
+            .patch(&url)
             .header("cookie", &authed_cookie)
+            .header("if-match", "not_an_etag")
+            .json(&patch("second"))
             .send()
             .await
             .unwrap();
-        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
         let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!(result, "Invalid Authentication");
+        assert_eq!(result, "If-Match");
+
+        for (if_match, description) in [
+            (Some(new_etag.as_str()), "second"),
+            (Some("*"), "third"),
+            (None, "fourth"),
+        ] {
+            let mut request = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&patch(description));
+            if let Some(if_match) = if_match {
+                request = request.header("if-match", if_match);
+            }
+            let result = request.send().await.unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            assert_eq!(
+                test_setup.query_meal().await.unwrap().description,
+                description
+            );
+        }
     }
 
     #[tokio::test]
-    /// params invalid, return 400 error
-    async fn api_router_meal_date_person_invalid_params() {
+    /// Patching a meal replaces its tags, a tag-only change is a valid patch
+    async fn api_router_meal_base_patch_tags() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
         test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let tags = [
+            test_setup.insert_test_tag().await,
+            test_setup.insert_test_tag().await,
+        ];
 
-        async fn person_test(base_url: &str, cookie: &str, person: &str) {
-            let client = reqwest::Client::new();
-            let url = format!("{base_url}/meal/2020-01-01/{person}");
-
+        let client = reqwest::Client::new();
+        let mut body = test_setup.gen_meal(false);
+        body.tags = vec![tags[0]];
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(test_setup.query_meal().await.unwrap().tags, vec![tags[0]]);
+
+        for (meal_tags, status, expected) in [
+            (vec![tags[0]], StatusCode::OK, vec![tags[0]]),
+            (vec![tags[1], -1], StatusCode::BAD_REQUEST, vec![tags[0]]),
+            (vec![tags[1], tags[0]], StatusCode::OK, tags.to_vec()),
+            (vec![tags[1]], StatusCode::OK, vec![tags[1]]),
+            (vec![], StatusCode::OK, vec![]),
+        ] {
+            let mut meal = C!(body);
+            meal.tags = meal_tags;
             let result = client
-                .get(&url)
-                .header("cookie", cookie)
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&TestBodyMealPatch {
+                    original_date: C!(body.date),
+                    original_slot: None,
+                    meal,
+                })
                 .send()
                 .await
                 .unwrap();
-            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
-            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result.status(), status);
+            assert_eq!(test_setup.query_meal().await.unwrap().tags, expected);
+        }
+    }
+
+    #[tokio::test]
+    /// A venue must exist, and can only be set on a restaurant or takeaway meal
+    async fn api_router_meal_base_venue() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let meal_venue_id = test_setup.insert_test_venue().await;
+
+        let client = reqwest::Client::new();
+        let body = test_setup.gen_meal(false);
+        for (venue_id, takeaway, expected) in [
+            (Some(0), true, "venue_id"),
+            (Some(meal_venue_id + 1000), true, "unknown venue"),
+            (Some(meal_venue_id), false, "venue"),
+        ] {
+            let mut meal = C!(body);
+            meal.venue_id = venue_id;
+            meal.takeaway = takeaway;
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(&meal)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
+        assert!(test_setup.query_meal().await.is_none());
+
+        let mut meal = C!(body);
+        meal.venue_id = Some(meal_venue_id);
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&meal)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_eq!(
+            test_setup.query_meal().await.unwrap().meal_venue_id,
+            Some(meal_venue_id)
+        );
+
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&TestBodyMealPatch {
+                original_date: C!(body.date),
+                original_slot: None,
+                meal: C!(body),
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(
+            test_setup
+                .query_meal()
+                .await
+                .unwrap()
+                .meal_venue_id
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    /// Patching a meal replaces its photos, in the given order, photos no longer used by any meal are removed
+    async fn api_router_meal_base_patch_photos() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let mut body = test_setup.gen_meal(true);
+        body.photos.push(TestSetup::gen_meal_photo());
+        body.photos[1].caption = Some(S!("  second photo "));
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let photos = test_setup.query_meal().await.unwrap().photos;
+        assert_eq!(photos.len(), 2);
+        assert_eq!(photos[0].photo_converted, body.photos[0].converted);
+        assert_eq!(photos[1].photo_converted, body.photos[1].converted);
+        assert!(photos[0].caption.is_none());
+        assert_eq!(photos[1].caption.as_deref(), Some("second photo"));
+
+        let in_use = |name: String| {
+            let postgres = C!(test_setup.postgres);
+            async move {
+                admin_queries::ActivePhoto::in_use(&postgres, &PhotoName::try_from(name).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+        assert!(in_use(C!(body.photos[0].original)).await);
+        assert!(in_use(C!(body.photos[1].converted)).await);
+
+        let mut duplicate = C!(body.photos[0]);
+        duplicate.caption = Some(S!("duplicate"));
+        let mut reversed = C!(body.photos);
+        reversed.reverse();
+        for (photos, status) in [
+            (vec![C!(body.photos[0]), duplicate], StatusCode::BAD_REQUEST),
+            (
+                (0..11).map(|_| TestSetup::gen_meal_photo()).collect(),
+                StatusCode::BAD_REQUEST,
+            ),
+            (reversed.iter().take(1).cloned().collect(), StatusCode::OK),
+        ] {
+            let mut meal = C!(body);
+            meal.photos = photos;
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&TestBodyMealPatch {
+                    original_date: C!(body.date),
+                    original_slot: None,
+                    meal,
+                })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), status);
+            if status == StatusCode::BAD_REQUEST {
+                let result = result.json::<Response>().await.unwrap().response;
+                assert_eq!(result, "photos");
+            }
+        }
+
+        let photos = test_setup.query_meal().await.unwrap().photos;
+        assert_eq!(photos.len(), 1);
+        assert_eq!(photos[0].photo_converted, body.photos[1].converted);
+        assert_eq!(photos[0].caption.as_deref(), Some("second photo"));
+        assert!(!in_use(C!(body.photos[0].original)).await);
+        assert!(in_use(C!(body.photos[1].original)).await);
+        let removed = sqlx::query!(
+            "SELECT meal_photo_id FROM meal_photo WHERE photo_converted = $1",
+            body.photos[0].converted
+        )
+        .fetch_optional(&test_setup.postgres)
+        .await
+        .unwrap();
+        assert!(removed.is_none());
+    }
+
+    //////////////////////////////////////////////////
+
+    #[tokio::test]
+    /// Unauthenticated user unable to access "/missing" route
+    async fn api_router_meal_missing_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Authenticated, but not admin user, unable to access "/missing" route
+    async fn api_router_meal_missing_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Missing.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Get list of missing meals - assumes that the db_data isn't up to date!
+    async fn api_router_meal_missing_admin_valid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Missing.addr()
+        );
+        let client = reqwest::Client::new();
+
+        // Missing meals start at a person's first meal, so make sure there's a meal before the missing dates
+        let date = now_utc().date() - jiff::Span::new().days(5);
+        let mut body = test_setup.gen_meal(false);
+        body.date = date.to_string();
+        let inserted = client
+            .post(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                MealRoutes::Base.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .status()
+            == StatusCode::OK;
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = result.json::<Response>().await.unwrap().response;
+        assert!(result.is_array());
+
+        assert!(result.as_array().unwrap().len() > 1);
+        assert!(result.as_array().unwrap()[0]["date"].is_string());
+        assert!(result.as_array().unwrap()[0]["person"].is_string());
+
+        if inserted {
+            test_setup
+                .purge_meal(&Person::try_from("Jack").unwrap(), date)
+                .await;
+        }
+    }
+
+    #[tokio::test]
+    /// A person's missing meals start at their first meal, so a new person without any meals has none
+    async fn api_router_meal_missing_new_person() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Missing.addr()
+        );
+        let client = reqwest::Client::new();
+
+        let missing = |result: serde_json::Value| {
+            result
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|i| i["person"] == TEST_PERSON)
+                .map(|i| S!(i["date"].as_str().unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert!(missing(result).is_empty());
+
+        let today = now_utc().date();
+        let date = today - jiff::Span::new().days(3);
+        let mut body = test_setup.gen_meal(false);
+        body.person = S!(TEST_PERSON);
+        body.date = date.to_string();
+        let result = client
+            .post(format!(
+                "{}{}",
+                base_url(&test_setup.app_env),
+                MealRoutes::Base.addr()
+            ))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(
+            missing(result),
+            vec![
+                (today - jiff::Span::new().days(1)).to_string(),
+                (today - jiff::Span::new().days(2)).to_string()
+            ]
+        );
+
+        test_setup
+            .purge_meal(&Person::try_from(TEST_PERSON).unwrap(), date)
+            .await;
+        test_setup.delete_test_person().await;
+    }
+
+    #[tokio::test]
+    /// Unauthenticated user unable to [GET, DELETE] access "/missing" route
+    async fn api_router_meal_date_person_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let url = format!("{}/meal/2020-01-01/Jack", base_url(&test_setup.app_env),);
+
+        let client = reqwest::Client::new();
+
+        let result = client.get(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+
+        let result = client.delete(&url).send().await.unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Authenticated, but not admin user, unable to [GET, DELETE] "/missing" route
+    async fn api_router_meal_date_person_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let url = format!("{}/meal/2020-01-01/Jack", base_url(&test_setup.app_env),);
+        let client = reqwest::Client::new();
+
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+
+        let result = client
+            .delete(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// params invalid, return 400 error
+    async fn api_router_meal_date_person_invalid_params() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+
+        async fn person_test(base_url: &str, cookie: &str, person: &str) {
+            let client = reqwest::Client::new();
+            let url = format!("{base_url}/meal/2020-01-01/{person}");
+
+            let result = client
+                .get(&url)
+                .header("cookie", cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
             assert_eq!(result, "invalid person param");
         }
 
-        let base_url = base_url(&test_setup.app_env);
+        let base_url = base_url(&test_setup.app_env);
+
+        person_test(&base_url, &authed_cookie, "jack").await;
+        person_test(&base_url, &authed_cookie, "dave").await;
+        person_test(&base_url, &authed_cookie, "JACK").await;
+        person_test(&base_url, &authed_cookie, "DAVE").await;
+        person_test(&base_url, &authed_cookie, "2020-01-01").await;
+        person_test(&base_url, &authed_cookie, &gen_random_hex(4)).await;
+
+        async fn date_test(base_url: &str, cookie: &str, date: &str) {
+            let client = reqwest::Client::new();
+            let url = format!("{base_url}/meal/{date}/Jack");
+
+            let result = client
+                .get(&url)
+                .header("cookie", cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, "invalid date param");
+        }
+
+        date_test(&base_url, &authed_cookie, "2020-14-01").await;
+        date_test(&base_url, &authed_cookie, "2020-01-40").await;
+        date_test(&base_url, &authed_cookie, "2013-05-05").await;
+        date_test(&base_url, &authed_cookie, "2018-*5-05").await;
+        date_test(&base_url, &authed_cookie, &gen_random_hex(10)).await;
+    }
+
+    #[tokio::test]
+    /// Valid params, return known meal object
+    async fn api_router_meal_date_person_get_valid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/meal/2020-01-01/Jack", base_url(&test_setup.app_env));
+        let result = client
+            .get(&url)
+            .header("cookie", authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+
+        assert!(result["meal"].is_object());
+        let result = result["meal"].as_object().unwrap();
+
+        assert_eq!(result.get("date").unwrap(), "2020-01-01");
+        assert_eq!(result.get("category").unwrap(), "DUCK");
+        assert_eq!(result.get("person").unwrap(), "Jack");
+        assert_eq!(result.get("restaurant").unwrap(), false);
+        assert_eq!(result.get("takeaway").unwrap(), false);
+        assert_eq!(result.get("vegetarian").unwrap(), false);
+        assert_eq!(
+            result.get("description").unwrap(),
+            "Peking duck, pancakes, cabbage,"
+        );
+        assert_eq!(
+            result.get("photos").unwrap()[0]["original"],
+            "01dxh6kawgcs6wbfxppqtryn7p10.jpg"
+        );
+        assert_eq!(
+            result.get("photos").unwrap()[0]["converted"],
+            "01dxh6kawgpetaws6t9g4946z911.jpg"
+        );
+    }
+
+    #[tokio::test]
+    /// Valid params, valiud body, delete meal
+    async fn api_router_meal_date_person_delete_valid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let body = test_setup.gen_meal(false);
+        client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+
+        let original = test_setup.query_meal().await.unwrap();
+        let delete_url = format!("{}/meal/{}/Jack", base_url(&test_setup.app_env), body.date);
+
+        let password = HashMap::from([("password", TEST_PASSWORD)]);
+        let result = client
+            .delete(&delete_url)
+            .json(&password)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let meal = test_setup.query_meal().await;
+        assert!(meal.is_none());
+
+        // The meal is kept in the trash, and no longer blocks its slot
+        let trash = ModelMealTrash::get_by_id(&test_setup.postgres, original.individual_meal_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(trash.meal, original);
+        let result = client
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert_ne!(
+            test_setup.query_meal().await.unwrap().individual_meal_id,
+            original.individual_meal_id
+        );
+    }
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    /// A person can have a meal in each slot of a date, the slot defaults to dinner
+    async fn api_router_meal_slots() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Base.addr()
+        );
+        let dinner = test_setup.gen_meal(false);
+        let mut lunch = test_setup.gen_meal(false);
+        lunch.slot = Some(S!("lunch"));
+
+        for body in [&dinner, &lunch] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+        }
+
+        let mut invalid = C!(lunch);
+        invalid.slot = Some(S!("supper"));
+        for (body, expected) in [
+            (&lunch, "Meal already exists on date and person given"),
+            (&invalid, "slot"),
+        ] {
+            let result = client
+                .post(&url)
+                .header("cookie", &authed_cookie)
+                .json(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
 
-        person_test(&base_url, &authed_cookie, "jack").await;
-        person_test(&base_url, &authed_cookie, "dave").await;
-        person_test(&base_url, &authed_cookie, "JACK").await;
-        person_test(&base_url, &authed_cookie, "DAVE").await;
-        person_test(&base_url, &authed_cookie, "2020-01-01").await;
-        person_test(&base_url, &authed_cookie, &gen_random_hex(4)).await;
+        let date_url = format!("{url}/{}/Jack", dinner.date);
+        for (slot_url, expected) in [
+            (C!(date_url), &dinner),
+            (format!("{date_url}/dinner"), &dinner),
+            (format!("{date_url}/lunch"), &lunch),
+        ] {
+            let result = client
+                .get(&slot_url)
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result["meal"]["description"], expected.description);
+            assert_eq!(
+                result["meal"]["slot"],
+                expected.slot.as_deref().unwrap_or("dinner")
+            );
+        }
+        let result = client
+            .get(format!("{date_url}/breakfast"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert!(result["meal"].is_null());
+        let result = client
+            .get(format!("{date_url}/supper"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "invalid slot param");
 
-        async fn date_test(base_url: &str, cookie: &str, date: &str) {
-            let client = reqwest::Client::new();
-            let url = format!("{base_url}/meal/{date}/Jack");
+        let result = client
+            .get(format!("{date_url}/lunch/revision"))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result[0]["slot"], "lunch");
+
+        // Moving the lunch into the dinner slot is blocked, into the breakfast slot is allowed
+        for (slot, status) in [
+            ("dinner", StatusCode::BAD_REQUEST),
+            ("breakfast", StatusCode::OK),
+        ] {
+            let mut meal = C!(lunch);
+            meal.slot = Some(S!(slot));
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&TestBodyMealPatch {
+                    original_date: C!(lunch.date),
+                    original_slot: Some(S!("lunch")),
+                    meal,
+                })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), status);
+        }
+        lunch.slot = Some(S!("breakfast"));
+        test_setup.test_meal = Some(C!(lunch));
+        let meal = test_setup.query_meal().await.unwrap();
+        assert_eq!(meal.slot, "breakfast");
+        assert_eq!(meal.description, lunch.description);
+
+        let result = client
+            .delete(format!("{date_url}/breakfast"))
+            .json(&HashMap::from([("password", TEST_PASSWORD)]))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        assert!(test_setup.query_meal().await.is_none());
+        test_setup.test_meal = Some(dinner);
+        assert!(test_setup.query_meal().await.is_some());
+    }
+
+    #[tokio::test]
+    /// Authenticated, but not admin, user unable to import meals
+    async fn api_router_meal_import_not_admin() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let url = format!(
+            "{}{}?dry_run=true",
+            base_url(&test_setup.app_env),
+            MealRoutes::Import.addr()
+        );
+        let body = vec![test_setup.gen_meal(false)];
+        let result = reqwest::Client::new()
+            .post(&url)
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Import requires a csv or json content type, and dry_run query param
+    async fn api_router_meal_import_invalid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Import.addr()
+        );
 
+        for (query, content_type, body, expected) in [
+            ("", "text/csv", "date\n2020-01-01", "missing dry_run"),
+            (
+                "?dry_run=true",
+                "text/plain",
+                "date\n2020-01-01",
+                "\"text/csv\" or \"application/json\" header",
+            ),
+            ("?dry_run=true", "text/csv", "date\n\"2020-01-01", "csv"),
+            ("?dry_run=true", "text/csv", "date", "empty"),
+            ("?dry_run=true", "application/json", "[]", "empty"),
+            ("?dry_run=true", "application/json", "{}", "JSON"),
+        ] {
             let result = client
-                .get(&url)
-                .header("cookie", cookie)
+                .post(format!("{url}{query}"))
+                .header("cookie", &authed_cookie)
+                .header("content-type", content_type)
+                .body(body)
                 .send()
                 .await
                 .unwrap();
             assert_eq!(result.status(), StatusCode::BAD_REQUEST);
             let result = result.json::<Response>().await.unwrap().response;
-            assert_eq!(result, "invalid date param");
+            assert_eq!(result, expected);
         }
+    }
 
-        date_test(&base_url, &authed_cookie, "2020-14-01").await;
-        date_test(&base_url, &authed_cookie, "2020-01-40").await;
-        date_test(&base_url, &authed_cookie, "2013-05-05").await;
-        date_test(&base_url, &authed_cookie, "2018-*5-05").await;
-        date_test(&base_url, &authed_cookie, &gen_random_hex(10)).await;
+    #[tokio::test]
+    /// Every invalid csv row is reported, and no meals are inserted in either dry run or commit mode
+    async fn api_router_meal_import_csv_errors() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Import.addr()
+        );
+
+        let meal = test_setup.gen_meal(false);
+        let row = format!(
+            "{},{},Jack,false,true,false,\"{}, with a comma\"",
+            meal.date, meal.category, meal.description
+        );
+        let csv = [
+            S!("date,category,person,restaurant,takeaway,vegetarian,description"),
+            C!(row),
+            format!(
+                "{},{},Nobody,false,true,false,desc",
+                meal.date, meal.category
+            ),
+            C!(row),
+            format!(",{},Jack,false,true,false,desc", meal.category),
+            format!("{},{},Jack,maybe,true,false,desc", meal.date, meal.category),
+            S!("2020-01-01,too,few"),
+        ]
+        .join("\n");
+
+        for (dry_run, status) in [("true", StatusCode::OK), ("false", StatusCode::BAD_REQUEST)] {
+            let result = client
+                .post(format!("{url}?dry_run={dry_run}"))
+                .header("cookie", &authed_cookie)
+                .header("content-type", "text/csv")
+                .body(C!(csv))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), status);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result["rows"], 6);
+            assert_eq!(result["inserted"], 0);
+            let errors = result["errors"].as_array().unwrap();
+            let rows = errors
+                .iter()
+                .map(|i| i["row"].as_u64().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(rows, [2, 3, 4, 5, 6]);
+            assert_eq!(errors[0]["error"], "person");
+            assert_eq!(errors[1]["error"], "duplicate meal");
+            assert_eq!(errors[2]["error"], "missing date");
+            assert_eq!(errors[4]["error"], "columns");
+            assert!(test_setup.query_meal().await.is_none());
+        }
     }
 
     #[tokio::test]
-    /// Valid params, return known meal object
-    async fn api_router_meal_date_person_get_valid() {
+    /// A valid json array of meals is inserted, and the meal cache is removed
+    async fn api_router_meal_import_json_ok() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
         test_setup.make_user_admin().await;
+        test_setup.insert_test_person().await;
         let client = reqwest::Client::new();
-        let url = format!("{}/meal/2020-01-01/Jack", base_url(&test_setup.app_env));
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            MealRoutes::Import.addr()
+        );
+
+        let jack_meal = test_setup.gen_meal(false);
+        let mut test_person_meal = C!(jack_meal);
+        test_person_meal.person = S!(TEST_PERSON);
+        test_person_meal.description = gen_random_hex(24);
+        let body = vec![jack_meal, test_person_meal];
+
         let result = client
-            .get(&url)
-            .header("cookie", authed_cookie)
+            .post(format!("{url}?dry_run=true"))
+            .header("cookie", &authed_cookie)
+            .json(&body)
             .send()
             .await
             .unwrap();
         assert_eq!(result.status(), StatusCode::OK);
         let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["rows"], 2);
+        assert_eq!(result["inserted"], 0);
+        assert!(result["errors"].as_array().unwrap().is_empty());
+        assert!(test_setup.query_meal().await.is_none());
+
+        test_setup
+            .redis
+            .set::<(), &str, &str>("cache::meals::admin", "cache", None, None, false)
+            .await
+            .unwrap();
 
-        assert!(result["meal"].is_object());
-        let result = result["meal"].as_object().unwrap();
+        let result = client
+            .post(format!("{url}?dry_run=false"))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["rows"], 2);
+        assert_eq!(result["inserted"], 2);
 
-        assert_eq!(result.get("date").unwrap(), "2020-01-01");
-        assert_eq!(result.get("category").unwrap(), "DUCK");
-        assert_eq!(result.get("person").unwrap(), "Jack");
-        assert_eq!(result.get("restaurant").unwrap(), false);
-        assert_eq!(result.get("takeaway").unwrap(), false);
-        assert_eq!(result.get("vegetarian").unwrap(), false);
+        let meal = test_setup.query_meal().await.unwrap();
+        assert_eq!(meal.description, body[0].description);
+        let redis_cache = test_setup.warmed_meals_cache().await;
+        assert!(redis_cache.contains(&body[0].description));
+
+        // Importing again fails, as the meals now exist
+        let result = client
+            .post(format!("{url}?dry_run=true"))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["errors"].as_array().unwrap().len(), 2);
         assert_eq!(
-            result.get("description").unwrap(),
-            "Peking duck, pancakes, cabbage,"
+            result["errors"][0]["error"],
+            "Meal already exists on date and person given"
         );
-        assert_eq!(
-            result.get("photo_original").unwrap(),
-            "01dxh6kawgcs6wbfxppqtryn7p10.jpg"
+    }
+
+    #[tokio::test]
+    /// Unknown meal, or unknown revision, returns an error
+    async fn api_router_meal_revision_invalid() {
+        let mut test_setup = start_both_servers().await;
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        test_setup.make_user_admin().await;
+        let client = reqwest::Client::new();
+
+        let body = test_setup.gen_meal(false);
+        let result = client
+            .post(format!("{}/meal", base_url(&test_setup.app_env)))
+            .header("cookie", &authed_cookie)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+
+        let url = format!(
+            "{}/meal/{}/Dave/revision",
+            base_url(&test_setup.app_env),
+            body.date
         );
-        assert_eq!(
-            result.get("photo_converted").unwrap(),
-            "01dxh6kawgpetaws6t9g4946z911.jpg"
+        let result = client
+            .get(&url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "unknown meal");
+
+        let url = format!(
+            "{}/meal/{}/Jack/revision",
+            base_url(&test_setup.app_env),
+            body.date
         );
+        for (revision, status, expected) in [
+            ("0", StatusCode::BAD_REQUEST, "invalid id param"),
+            ("1", StatusCode::BAD_REQUEST, "unknown revision"),
+        ] {
+            let result = client
+                .put(format!("{url}/{revision}"))
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), status);
+            let result = result.json::<Response>().await.unwrap().response;
+            assert_eq!(result, expected);
+        }
     }
 
     #[tokio::test]
-    /// Valid params, valiud body, delete meal
-    async fn api_router_meal_date_person_delete_valid() {
+    /// Revision history lists the insert and the update, with deleted names resolved, and the meal can be reverted to the insert
+    /// The photos of the insert, removed by the update, are restored by the revert
+    async fn api_router_meal_revision_revert_valid() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
         test_setup.make_user_admin().await;
@@ -740,28 +1947,110 @@ mod tests {
             base_url(&test_setup.app_env),
             MealRoutes::Base.addr()
         );
-        let body = test_setup.gen_meal(false);
-        client
+
+        let body = test_setup.gen_meal(true);
+        let result = client
             .post(&url)
             .header("cookie", &authed_cookie)
             .json(&body)
             .send()
             .await
             .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
 
-        let url = format!("{}/meal/{}/Jack", base_url(&test_setup.app_env), body.date);
+        let mut new_meal = C!(body);
+        new_meal.description = gen_random_hex(8);
+        new_meal.takeaway = !body.takeaway;
+        new_meal.photos = vec![];
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&TestBodyMealPatch {
+                original_date: C!(body.date),
+                original_slot: None,
+                meal: C!(new_meal),
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
 
-        let body = HashMap::from([("password", TEST_PASSWORD)]);
+        let revision_url = format!("{url}/{}/Jack/revision", body.date);
         let result = client
-            .delete(&url)
-            .json(&body)
+            .get(&revision_url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::OK);
+        let result = result.json::<Response>().await.unwrap().response;
+        let revisions = result.as_array().unwrap();
+        assert_eq!(revisions.len(), 2);
+
+        assert_eq!(revisions[0]["action"], "update");
+        assert_eq!(revisions[0]["description"], new_meal.description);
+        assert!(revisions[0]["photos"].as_array().unwrap().is_empty());
+        let changed = revisions[0]["changed"].as_array().unwrap();
+        for field in ["description", "takeaway"] {
+            assert!(changed.contains(&serde_json::Value::from(field)));
+        }
+        assert!(!changed.contains(&serde_json::Value::from("category")));
+
+        // The original description row has been deleted, so is resolved from the audit tables
+        assert_eq!(revisions[1]["action"], "insert");
+        assert!(revisions[1]["changed"].as_array().unwrap().is_empty());
+        assert_eq!(revisions[1]["date"], body.date);
+        assert_eq!(revisions[1]["person"], "Jack");
+        assert_eq!(revisions[1]["category"], body.category.to_uppercase());
+        assert_eq!(revisions[1]["description"], body.description);
+        assert_eq!(revisions[1]["takeaway"], body.takeaway);
+        // The photo row was deleted by the update, so its names are resolved from the audit tables
+        let photos = revisions[1]["photos"].as_array().unwrap();
+        assert_eq!(photos.len(), 1);
+        assert_eq!(photos[0]["original"], body.photos[0].original);
+        assert_eq!(photos[0]["converted"], body.photos[0].converted);
+
+        let revert_url = format!("{revision_url}/{}", revisions[1]["revision"]);
+        let result = client
+            .put(&revert_url)
             .header("cookie", &authed_cookie)
             .send()
             .await
             .unwrap();
         assert_eq!(result.status(), StatusCode::OK);
 
-        let meal = test_setup.query_meal().await;
-        assert!(meal.is_none());
+        let result = client
+            .get(format!("{url}/{}/Jack", body.date))
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result["meal"]["description"], body.description);
+        assert_eq!(result["meal"]["takeaway"], body.takeaway);
+        let photos = result["meal"]["photos"].as_array().unwrap();
+        assert_eq!(photos.len(), 1);
+        assert_eq!(photos[0]["original"], body.photos[0].original);
+        assert_eq!(photos[0]["converted"], body.photos[0].converted);
+
+        // Already at that revision
+        let result = client
+            .put(&revert_url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "no changes");
+
+        let result = client
+            .get(&revision_url)
+            .header("cookie", &authed_cookie)
+            .send()
+            .await
+            .unwrap();
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result.as_array().unwrap().len(), 3);
     }
 }
@@ -11,7 +11,6 @@ use crate::{
         Outgoing,
         api::{ApiRouter, ApiState},
         authentication::is_admin,
-        deserializer::IncomingDeserializer,
         ij, oj,
     },
 };
@@ -74,30 +73,18 @@ impl PhotoRouter {
     ) -> Result<Outgoing<oj::Photo>, ApiError> {
         match multipart.next_field().await? {
             Some(field) => {
-                let file_name = field
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string()
-                    .split_once('.')
-                    .unwrap_or_default()
-                    .0
-                    .to_owned();
-
+                // The file name isn't used, the saved photos are given random names, and the meals that use a photo set its visibility
                 let content_type = field.content_type().unwrap_or_default().to_string();
                 let data = field.bytes().await?;
 
-                if !Self::validate_mime_type(&content_type)
-                    || !IncomingDeserializer::parse_photo_name(&file_name)
-                    || data.is_empty()
-                {
+                if !Self::validate_mime_type(&content_type) || data.is_empty() {
                     return Err(ApiError::InvalidValue(
                         PhotoResponses::ImageInvalid.to_string(),
                     ));
                 }
 
                 let converted =
-                    PhotoConvertor::convert_photo(Photo { file_name, data }, &state.photo_env)
-                        .await?;
+                    PhotoConvertor::convert_photo(Photo { data }, &state.photo_env).await?;
                 Ok((
                     axum::http::StatusCode::OK,
                     oj::OutgoingJson::new(oj::Photo {
@@ -221,8 +208,8 @@ mod tests {
     }
 
     #[tokio::test]
-    /// Invalid image name
-    async fn api_router_photo_post_invalid_name() {
+    /// The file name of an upload isn't used, so it doesn't need to be a person's initial
+    async fn api_router_photo_post_any_name() {
         let mut test_setup = start_both_servers().await;
         let authed_cookie = test_setup.authed_user_cookie().await;
         test_setup.make_user_admin().await;
@@ -234,58 +221,36 @@ mod tests {
         let client = reqwest::Client::new();
 
         let test_file = std::fs::read("/workspaces/backend/docker/data/test_image.jpg").unwrap();
-        let part = reqwest::multipart::Part::bytes(C!(test_file))
-            .file_name("2022-01-01_J")
-            .mime_str("image/jpeg")
-            .unwrap();
-
-        let form = reqwest::multipart::Form::new().part("file", part);
-
-        let result = client
-            .post(&url)
-            .multipart(form)
-            .header("cookie", &authed_cookie)
-            .send()
-            .await
-            .unwrap();
-
-        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!("Image invalid", result);
-
-        let part = reqwest::multipart::Part::bytes(C!(test_file))
-            .file_name("2022-01-01_I.jpg")
-            .mime_str("image/jpeg")
-            .unwrap();
-        let form = reqwest::multipart::Form::new().part("file", part);
-        let result = client
-            .post(&url)
-            .multipart(form)
-            .header("cookie", &authed_cookie)
-            .send()
-            .await
+        for name in ["2022-01-01_J", "Q.jpg"] {
+            let part = reqwest::multipart::Part::bytes(C!(test_file))
+                .file_name(name)
+                .mime_str("image/jpeg")
+                .unwrap();
+            let form = reqwest::multipart::Form::new().part("file", part);
+
+            let result = client
+                .post(&url)
+                .multipart(form)
+                .header("cookie", &authed_cookie)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+            let result = result.json::<Response>().await.unwrap().response;
+
+            std::fs::remove_file(format!(
+                "{}/{}",
+                test_setup.app_env.location_photo_original,
+                result["original"].as_str().unwrap()
+            ))
             .unwrap();
-
-        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!("Image invalid", result);
-
-        let part = reqwest::multipart::Part::bytes(test_file)
-            .file_name("2022-13-01_J.jpg")
-            .mime_str("image/jpeg")
+            std::fs::remove_file(format!(
+                "{}/{}",
+                test_setup.app_env.location_photo_converted,
+                result["converted"].as_str().unwrap()
+            ))
             .unwrap();
-        let form = reqwest::multipart::Form::new().part("file", part);
-        let result = client
-            .post(&url)
-            .multipart(form)
-            .header("cookie", &authed_cookie)
-            .send()
-            .await
-            .unwrap();
-
-        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
-        let result = result.json::<Response>().await.unwrap().response;
-        assert_eq!("Image invalid", result);
+        }
     }
 
     #[tokio::test]
@@ -29,6 +29,7 @@ define_routes! {
     Base => "",
     Signout => "/signout",
     Password => "/password",
+    Reminder => "/reminder",
     SetupTwoFA => "/setup/twofa",
     TwoFA => "/twofa"
 }
@@ -59,6 +60,7 @@ impl ApiRouter for UserRouter {
             .route(&UserRoutes::Base.addr(), get(Self::user_get))
             .route(&UserRoutes::Signout.addr(), post(Self::signout_post))
             .route(&UserRoutes::Password.addr(), patch(Self::password_patch))
+            .route(&UserRoutes::Reminder.addr(), patch(Self::reminder_patch))
             .route(
                 &UserRoutes::SetupTwoFA.addr(),
                 delete(Self::setup_two_fa_delete)
@@ -361,6 +363,17 @@ impl UserRouter {
         Ok(axum::http::StatusCode::OK)
     }
 
+    /// Enable, or disable, missing meal reminder emails
+    async fn reminder_patch(
+        State(state): State<ApiState>,
+        user: ModelUser,
+        ij::IncomingJson(body): ij::IncomingJson<ij::MealReminder>,
+    ) -> Result<axum::http::StatusCode, ApiError> {
+        user.update_meal_reminder(&state.postgres, body.meal_reminder)
+            .await?;
+        Ok(axum::http::StatusCode::OK)
+    }
+
     /// Update user password
     async fn password_patch(
         user: ModelUser,
@@ -431,6 +444,7 @@ mod tests {
     use crate::servers::api_tests::{
         Response, TEST_EMAIL, TEST_PASSWORD, TestSetup, base_url, get_keys, start_both_servers,
     };
+    use crate::servers::ij::MealReminder;
     use crate::{S, tmp_file};
 
     use fred::interfaces::{HashesInterface, KeysInterface, SetsInterface};
@@ -482,6 +496,7 @@ mod tests {
         assert_eq!(result["two_fa_active"], false);
         assert_eq!(result["two_fa_always_required"], false);
         assert_eq!(result["two_fa_count"], 0);
+        assert_eq!(result["meal_reminder"], true);
     }
 
     #[tokio::test]
@@ -512,6 +527,7 @@ mod tests {
         assert_eq!(result["two_fa_active"], true);
         assert_eq!(result["two_fa_always_required"], false);
         assert_eq!(result["two_fa_count"], 0);
+        assert_eq!(result["meal_reminder"], true);
     }
 
     #[tokio::test]
@@ -581,6 +597,66 @@ mod tests {
     }
 
     /// Patch passwords
+    #[tokio::test]
+    /// Unauthenticated user can't access patch reminder route
+    async fn api_router_user_reminder_patch_unauthenticated() {
+        let test_setup = start_both_servers().await;
+        let client = reqwest::Client::new();
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            UserRoutes::Reminder.addr()
+        );
+        let body = MealReminder {
+            meal_reminder: false,
+        };
+
+        let result = client.patch(&url).json(&body).send().await.unwrap();
+
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        let result = result.json::<Response>().await.unwrap().response;
+        assert_eq!(result, "Invalid Authentication");
+    }
+
+    #[tokio::test]
+    /// Authenticated user can opt out of, and back in to, missing meal reminders
+    async fn api_router_user_reminder_patch_authenticated() {
+        let mut test_setup = start_both_servers().await;
+        let client = reqwest::Client::new();
+        let authed_cookie = test_setup.authed_user_cookie().await;
+        let url = format!(
+            "{}{}",
+            base_url(&test_setup.app_env),
+            UserRoutes::Reminder.addr()
+        );
+
+        let result = client
+            .patch(&url)
+            .header("cookie", &authed_cookie)
+            .json(&HashMap::from([("meal_reminder", "false")]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+
+        for meal_reminder in [false, true] {
+            let result = client
+                .patch(&url)
+                .header("cookie", &authed_cookie)
+                .json(&MealReminder { meal_reminder })
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(result.status(), StatusCode::OK);
+
+            let user = ModelUser::get(&test_setup.postgres, TEST_EMAIL)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(user.meal_reminder, meal_reminder);
+        }
+    }
+
     #[derive(Debug, Serialize)]
     struct TestPatchPassword {
         current_password: String,
@@ -0,0 +1,217 @@
+use axum::http::{HeaderMap, header};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{C, S, api_error::ApiError, helpers::quality_values};
+
+/// The serialization format of a request, or response, body
+/// The binary formats mirror the json exactly, so clients can switch formats without any other changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl BodyFormat {
+    /// The Content-Type of a response in this format
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MessagePack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// Every media type that is accepted as this format
+    const fn media_types(self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &["application/json"],
+            Self::MessagePack => &[
+                "application/msgpack",
+                "application/x-msgpack",
+                "application/vnd.msgpack",
+            ],
+            Self::Cbor => &["application/cbor"],
+        }
+    }
+
+    /// Get the format of a request body from its Content-Type header, None if it isn't one of the binary formats
+    pub fn from_content_type(headers: &HeaderMap) -> Option<Self> {
+        let media = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|i| i.to_str().ok())?
+            .split(';')
+            .next()?
+            .trim()
+            .to_ascii_lowercase();
+        [Self::MessagePack, Self::Cbor]
+            .into_iter()
+            .find(|i| i.media_types().contains(&media.as_str()))
+    }
+
+    /// The preferred response format from the Accept header, a binary format is only used if it's explicitly preferred over json
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let weights = headers
+            .get(header::ACCEPT)
+            .and_then(|i| i.to_str().ok())
+            .map(quality_values)
+            .unwrap_or_default();
+        let weight = |media_types: &[&str]| {
+            weights
+                .iter()
+                .filter(|(media, _)| media_types.contains(&media.as_str()))
+                .map(|(_, q)| *q)
+                .fold(0.0, f32::max)
+        };
+
+        let mut output = (
+            Self::Json,
+            weight(&["application/json", "application/*", "*/*"]),
+        );
+        for format in [Self::MessagePack, Self::Cbor] {
+            let q = weight(format.media_types());
+            if q > output.1 {
+                output = (format, q);
+            }
+        }
+        if output.1 > 0.0 { output.0 } else { Self::Json }
+    }
+
+    /// Serialize a value into this format
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<Vec<u8>, ApiError> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            Self::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(|e| ApiError::Internal(e.to_string()))
+            }
+            Self::Cbor => {
+                let mut output = vec![];
+                ciborium::into_writer(value, &mut output)
+                    .map_err(|e| ApiError::Internal(e.to_string()))?;
+                Ok(output)
+            }
+        }
+    }
+
+    /// Convert a serialized json body into this format
+    pub fn transcode(self, json: &[u8]) -> Result<Vec<u8>, ApiError> {
+        self.serialize(&serde_json::from_slice::<serde_json::Value>(json)?)
+    }
+
+    /// Deserialize a request body in this format, errors are converted in the same way as the errors of a json body
+    pub fn deserialize<T: DeserializeOwned>(self, body: &[u8]) -> Result<T, ApiError> {
+        let message = match self {
+            Self::Json => {
+                return serde_json::from_slice(body)
+                    .map_err(|_| ApiError::InvalidValue(S!("JSON")));
+            }
+            Self::MessagePack => match rmp_serde::from_slice(body) {
+                Ok(value) => return Ok(value),
+                Err(
+                    rmp_serde::decode::Error::Syntax(message)
+                    | rmp_serde::decode::Error::Uncategorized(message),
+                ) => message,
+                Err(_) => return Err(ApiError::InvalidValue(S!("MessagePack"))),
+            },
+            Self::Cbor => match ciborium::from_reader(body) {
+                Ok(value) => return Ok(value),
+                Err(ciborium::de::Error::Semantic(_, message)) => message,
+                Err(_) => return Err(ApiError::InvalidValue(S!("CBOR"))),
+            },
+        };
+        if message.starts_with("unknown field") {
+            return Err(ApiError::InvalidValue(S!("invalid input")));
+        }
+        Err(message.strip_prefix("missing field `").map_or_else(
+            || ApiError::InvalidValue(C!(message)),
+            |field| ApiError::MissingKey(field.trim_end_matches('`').to_owned()),
+        ))
+    }
+}
+
+/// cargo watch -q -c -w src/ -x 'test body_format -- --test-threads=1 --nocapture'
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn accept(value: &str) -> BodyFormat {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        BodyFormat::from_accept(&headers)
+    }
+
+    #[test]
+    fn body_format_from_accept() {
+        assert_eq!(BodyFormat::from_accept(&HeaderMap::new()), BodyFormat::Json);
+        assert_eq!(accept("*/*"), BodyFormat::Json);
+        assert_eq!(accept("text/html"), BodyFormat::Json);
+        assert_eq!(accept("application/json"), BodyFormat::Json);
+        assert_eq!(accept("application/msgpack"), BodyFormat::MessagePack);
+        assert_eq!(
+            accept("application/x-msgpack, */*;q=0.8"),
+            BodyFormat::MessagePack
+        );
+        assert_eq!(accept("application/cbor"), BodyFormat::Cbor);
+        assert_eq!(
+            accept("application/cbor;q=0.5, application/msgpack;q=0.9"),
+            BodyFormat::MessagePack
+        );
+        assert_eq!(
+            accept("application/cbor, application/json"),
+            BodyFormat::Json
+        );
+        assert_eq!(accept("application/cbor;q=0"), BodyFormat::Json);
+    }
+
+    #[test]
+    fn body_format_deserialize() {
+        #[derive(Debug, serde::Deserialize, Serialize, PartialEq)]
+        #[serde(deny_unknown_fields)]
+        struct Body {
+            name: String,
+        }
+        let body = Body { name: S!("name") };
+        for format in [BodyFormat::Json, BodyFormat::MessagePack, BodyFormat::Cbor] {
+            let serialized = format.serialize(&body).unwrap();
+            assert_eq!(format.deserialize::<Body>(&serialized).unwrap(), body);
+
+            let serialized = format.serialize(&serde_json::json!({})).unwrap();
+            let result = format.deserialize::<Body>(&serialized);
+            if format == BodyFormat::Json {
+                assert!(matches!(result, Err(ApiError::InvalidValue(_))));
+            } else {
+                assert!(matches!(result, Err(ApiError::MissingKey(i)) if i == "name"));
+            }
+
+            let serialized = format
+                .serialize(&serde_json::json!({"name": "name", "other": 1}))
+                .unwrap();
+            assert!(
+                matches!(format.deserialize::<Body>(&serialized), Err(ApiError::InvalidValue(i)) if i == "invalid input" || i == "JSON")
+            );
+        }
+        assert!(
+            matches!(BodyFormat::MessagePack.deserialize::<Body>(&[0xc1]), Err(ApiError::InvalidValue(i)) if i == "MessagePack")
+        );
+        assert!(
+            matches!(BodyFormat::Cbor.deserialize::<Body>(&[0x1c]), Err(ApiError::InvalidValue(i)) if i == "CBOR")
+        );
+    }
+
+    #[test]
+    fn body_format_transcode() {
+        let json = br#"{"response":{"d":{"1":"curry"},"m":[{"a":"200101"}]}}"#;
+        for format in [BodyFormat::MessagePack, BodyFormat::Cbor] {
+            let transcoded = format.transcode(json).unwrap();
+            assert_eq!(
+                format
+                    .deserialize::<serde_json::Value>(&transcoded)
+                    .unwrap(),
+                serde_json::from_slice::<serde_json::Value>(json).unwrap()
+            );
+        }
+    }
+}
@@ -8,14 +8,17 @@ use std::{net::IpAddr, sync::LazyLock};
 use ulid::Ulid;
 
 use crate::{
-    database::{Person, backup::BackupType},
+    database::{ChangeCursor, MealSlot, Person, VenueType, Visibility, backup::BackupType},
     helpers::genesis_date,
 };
 
-use super::{ij::LimitKey, incoming_json::ij};
+use super::{export::ExportFormat, ij::LimitKey, incoming_json::ij};
 
 pub struct IncomingDeserializer;
 
+/// Max number of photos a single meal can have
+const MAX_MEAL_PHOTOS: usize = 10;
+
 #[expect(clippy::unwrap_used)]
 static REGEX_EMAIL: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#"(?:[a-z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-z0-9!#$%&'*+/=?^_`{|}~-]+)*|"(?:[\x01-\x08\x0b\x0c\x0e-\x1f\x21\x23-\x5b\x5d-\x7f]|\\[\x01-\x09\x0b\x0c\x0e-\x7f])*")@(?:(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+[a-z0-9](?:[a-z0-9-]*[a-z0-9])?|\[(?:(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.){3}(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?|[a-z0-9-]*[a-z0-9]:(?:[\x01-\x08\x0b\x0c\x0e-\x1f\x21-\x5a\x53-\x7f]|\\[\x01-\x09\x0b\x0c\x0e-\x7f])+)\])"#).unwrap()
@@ -40,16 +43,6 @@ impl IncomingDeserializer {
             None
         }
     }
-    // D/J - for uploading an image, name is set in clientside code
-    pub fn parse_photo_name(file_name: &str) -> bool {
-        if file_name.chars().count() != 1 {
-            return false;
-        }
-
-        // Validate data correctly here
-        Self::valid_person_initial(file_name)
-    }
-
     /// Validate a date as being valid
     fn valid_meal_date(year: i16, month: i8, day: i8) -> Option<Date> {
         Date::new(year, month, day).ok()
@@ -88,8 +81,9 @@ impl IncomingDeserializer {
         })
     }
 
-    fn valid_person_initial(x: &str) -> bool {
-        x == "J" || x == "D"
+    /// A single uppercase ascii letter
+    fn valid_initial(x: &str) -> bool {
+        x.chars().count() == 1 && x.chars().all(|c| c.is_ascii_uppercase())
     }
 
     /// mealpedant_yyyy-mm-dd_hh.mm.ss_[NAME]_[a-f0-9]{8}.tar.gz.age
@@ -203,6 +197,16 @@ impl IncomingDeserializer {
 
         Self::valid_email(&parsed).ok_or_else(|| de::Error::custom(name))
     }
+    pub fn option_email<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::email(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
     /// Check email isn't empty, lowercase it, contains an '@' sign, and matches a 99.9% email regex
     pub fn vec_email<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
     where
@@ -261,17 +265,6 @@ impl IncomingDeserializer {
         }
     }
 
-    // TEST ME
-    pub fn option_photo_name_hex<'de, D>(deserializer: D) -> Result<Option<ij::PhotoName>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        match Option::<String>::deserialize(deserializer)? {
-            Some(x) => Ok(Some(Self::photo_name_hex(x.into_deserializer())?)),
-            _ => Ok(None),
-        }
-    }
-
     /// Only allow photo names in the format: [ulid][1/0][1/0].jpg
     pub fn photo_name_hex<'de, D>(deserializer: D) -> Result<ij::PhotoName, D::Error>
     where
@@ -355,7 +348,7 @@ impl IncomingDeserializer {
         )
     }
 
-    /// Only allows "Dave" or "Jack"
+    /// Only allows a person that exists in the meal_person registry
     pub fn person<'de, D>(deserializer: D) -> Result<Person, D::Error>
     where
         D: Deserializer<'de>,
@@ -366,6 +359,59 @@ impl IncomingDeserializer {
         Person::try_from(parsed.as_str()).map_or(Err(de::Error::custom(name)), Ok)
     }
 
+    pub fn option_person<'de, D>(deserializer: D) -> Result<Option<Person>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::person(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Only allows a single uppercase ascii letter
+    pub fn initial<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "initial";
+        let parsed = Self::parse_string(deserializer, name)?;
+        if Self::valid_initial(&parsed) {
+            Ok(parsed)
+        } else {
+            Err(de::Error::custom(name))
+        }
+    }
+
+    /// Only allow "public", "authenticated", or "admin"
+    pub fn visibility<'de, D>(deserializer: D) -> Result<Visibility, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "visibility";
+        let parsed = Self::parse_string(deserializer, name)?;
+        Visibility::try_from(parsed.as_str()).map_err(|_| de::Error::custom(name))
+    }
+
+    pub fn option_visibility<'de, D>(deserializer: D) -> Result<Option<Visibility>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::visibility(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn slot<'de, D>(deserializer: D) -> Result<MealSlot, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "slot";
+        let parsed = Self::parse_string(deserializer, name)?;
+        MealSlot::try_from(parsed.as_str()).map_err(|_| de::Error::custom(name))
+    }
+
     /// Only allow strings, and trim said string
     pub fn trimmed<'de, D>(deserializer: D) -> Result<String, D::Error>
     where
@@ -377,6 +423,332 @@ impl IncomingDeserializer {
         Ok(parsed.trim().to_owned())
     }
 
+    pub fn option_trimmed<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::trimmed(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Only allow a valid change cursor, four positive ids joined by a hyphen
+    pub fn cursor<'de, D>(deserializer: D) -> Result<ChangeCursor, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "cursor";
+        let parsed = Self::parse_string(deserializer, name)?;
+        ChangeCursor::try_from(parsed.as_str()).map_err(|_| de::Error::custom(name))
+    }
+
+    pub fn option_cursor<'de, D>(deserializer: D) -> Result<Option<ChangeCursor>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::cursor(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Only allow search terms that, once trimmed, are between 1 and 100 chars long
+    pub fn search<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "search";
+        let parsed = Self::parse_string(deserializer, name)?;
+        let parsed = parsed.trim();
+
+        let allowed_len = 1..=100;
+        if !allowed_len.contains(&parsed.chars().count()) {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed.to_owned())
+    }
+
+    /// Only allow non-empty strings, trimmed and uppercased, as categories are always stored uppercase
+    pub fn category<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "category";
+        let parsed = Self::parse_string(deserializer, name)?;
+        let parsed = parsed.trim();
+        if parsed.is_empty() {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed.to_uppercase())
+    }
+
+    /// Comma separated categories, each is trimmed and uppercased, like a meal category
+    pub fn exclude<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "exclude";
+        let parsed = Self::parse_string(deserializer, name)?;
+        parsed
+            .split(',')
+            .map(|i| Self::category(i.to_owned().into_deserializer()))
+            .collect::<Result<Vec<_>, D::Error>>()
+            .map_err(|_| de::Error::custom(name))
+    }
+
+    /// Only allow a trimmed, lowercase, tag of between 1 and 32 chars
+    pub fn tag<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "tag";
+        let parsed = Self::parse_string(deserializer, name)?;
+        let parsed = parsed.trim().to_lowercase();
+        if !(1..=32).contains(&parsed.chars().count()) {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed)
+    }
+
+    /// Only allow positive tag ids, either as an array, or as a comma separated string, as used by query params and csv cells
+    /// The ids are sorted and deduplicated
+    pub fn tags<'de, D>(deserializer: D) -> Result<Vec<i64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Tags {
+            Ids(Vec<i64>),
+            Joined(String),
+        }
+
+        let name = "tags";
+        let mut parsed =
+            match Tags::deserialize(deserializer).map_err(|_| de::Error::custom(name))? {
+                Tags::Ids(ids) => ids,
+                Tags::Joined(joined) => joined
+                    .split(',')
+                    .map(|i| i.trim().parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| de::Error::custom(name))?,
+            };
+        if parsed.iter().any(|i| *i < 1) {
+            return Err(de::Error::custom(name));
+        }
+        parsed.sort_unstable();
+        parsed.dedup();
+        Ok(parsed)
+    }
+
+    pub fn option_tags<'de, D>(deserializer: D) -> Result<Option<Vec<i64>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::tags(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Trimmed, and between 1 and 256 chars
+    pub fn option_caption<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "caption";
+        Option::<String>::deserialize(deserializer)
+            .map_err(|_| de::Error::custom(name))?
+            .map(|x| x.trim().to_owned())
+            .map_or(Ok(None), |caption| {
+                if (1..=256).contains(&caption.chars().count()) {
+                    Ok(Some(caption))
+                } else {
+                    Err(de::Error::custom(name))
+                }
+            })
+    }
+
+    /// Either a sequence of photos, or a JSON encoded sequence, as used by csv imports
+    /// Originals and converted names must be of the correct type, and a photo can't be used twice
+    pub fn photos<'de, D>(deserializer: D) -> Result<Vec<ij::MealPhoto>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Photos {
+            List(Vec<ij::MealPhoto>),
+            Encoded(String),
+        }
+
+        let name = "photos";
+        let parsed = match Photos::deserialize(deserializer).map_err(|_| de::Error::custom(name))? {
+            Photos::List(photos) => photos,
+            Photos::Encoded(encoded) => serde_json::from_str::<Vec<ij::MealPhoto>>(&encoded)
+                .map_err(|_| de::Error::custom(name))?,
+        };
+        let mut names = parsed
+            .iter()
+            .flat_map(|i| [i.original.to_string(), i.converted.to_string()])
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        if parsed.len() > MAX_MEAL_PHOTOS
+            || names.len() != parsed.len() * 2
+            || parsed.iter().any(|i| {
+                !matches!(i.original, ij::PhotoName::Original(_))
+                    || !matches!(i.converted, ij::PhotoName::Converted(_))
+            })
+        {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed)
+    }
+
+    /// Trimmed, and between 1 and 64 chars
+    fn parse_venue_text<'de, D>(deserializer: D, name: &str) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let parsed = Self::parse_string(deserializer, name)?;
+        let parsed = parsed.trim();
+        if !(1..=64).contains(&parsed.chars().count()) {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed.to_owned())
+    }
+
+    pub fn venue_name<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::parse_venue_text(deserializer, "name")
+    }
+
+    pub fn city<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::parse_venue_text(deserializer, "city")
+    }
+
+    pub fn venue_type<'de, D>(deserializer: D) -> Result<VenueType, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "venue_type";
+        let parsed = Self::parse_string(deserializer, name)?;
+        VenueType::try_from(parsed.as_str()).map_err(|_| de::Error::custom(name))
+    }
+
+    /// Parse a finite f64, that is within the given inclusive range
+    fn parse_coordinate<'de, D>(deserializer: D, name: &str, max: f64) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let parsed = f64::deserialize(deserializer).map_err(|_| de::Error::custom(name))?;
+        if !parsed.is_finite() || parsed.abs() > max {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed)
+    }
+
+    pub fn latitude<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::parse_coordinate(deserializer, "latitude", 90.0)
+    }
+
+    pub fn longitude<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::parse_coordinate(deserializer, "longitude", 180.0)
+    }
+
+    /// Only allow a positive id, either as a number, or as a string, as used by csv cells
+    pub fn option_venue_id<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum VenueId {
+            Id(i64),
+            Text(String),
+        }
+
+        let name = "venue_id";
+        let parsed = match Option::<VenueId>::deserialize(deserializer)
+            .map_err(|_| de::Error::custom(name))?
+        {
+            Some(VenueId::Id(id)) => id,
+            Some(VenueId::Text(text)) => text
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| de::Error::custom(name))?,
+            None => return Ok(None),
+        };
+        if parsed < 1 {
+            return Err(de::Error::custom(name));
+        }
+        Ok(Some(parsed))
+    }
+
+    /// Only allow non-empty strings, trimmed
+    pub fn description<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "description";
+        let parsed = Self::parse_string(deserializer, name)?;
+        let parsed = parsed.trim();
+        if parsed.is_empty() {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed.to_owned())
+    }
+
+    /// Only allow a non-empty vec of strings, with no empty strings, descriptions are matched exactly so aren't trimmed
+    pub fn descriptions<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "descriptions";
+        let parsed =
+            Vec::<String>::deserialize(deserializer).map_err(|_| de::Error::custom(name))?;
+        if parsed.is_empty() || parsed.iter().any(|i| i.trim().is_empty()) {
+            return Err(de::Error::custom(name));
+        }
+        Ok(parsed)
+    }
+
+    /// Only allow a number between 0.1 and 1, a pg_trgm similarity threshold
+    pub fn option_threshold<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "threshold";
+        match Option::<f32>::deserialize(deserializer).map_err(|_| de::Error::custom(name))? {
+            Some(x) if (0.1..=1.0).contains(&x) => Ok(Some(x)),
+            Some(_) => Err(de::Error::custom(name)),
+            None => Ok(None),
+        }
+    }
+
+    /// Only allow "csv", "jsonl", or "ics"
+    pub fn export_format<'de, D>(deserializer: D) -> Result<ExportFormat, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = "format";
+        let parsed = Self::parse_string(deserializer, name)?;
+        ExportFormat::try_from(parsed.as_str()).map_err(|_| de::Error::custom(name))
+    }
+
     /// Only allows dates, yyyy-mm-dd, that are equal to, or greater than, the genesis date
     pub fn date<'de, D>(deserializer: D) -> Result<Date, D::Error>
     where
@@ -402,6 +774,16 @@ impl IncomingDeserializer {
         Err(de::Error::custom(name))
     }
 
+    pub fn option_date<'de, D>(deserializer: D) -> Result<Option<Date>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(x) => Ok(Some(Self::date(x.into_deserializer())?)),
+            _ => Ok(None),
+        }
+    }
+
     /// Only allows strings > 12 && string < 100
     pub fn invite<'de, D>(deserializer: D) -> Result<String, D::Error>
     where
@@ -441,12 +823,15 @@ impl IncomingDeserializer {
 #[expect(clippy::pedantic, clippy::unwrap_used)]
 mod tests {
     use serde::de::value::{Error as ValueError, SeqDeserializer, StringDeserializer};
-    use serde::de::{IntoDeserializer, value::I64Deserializer};
+    use serde::de::{
+        IntoDeserializer,
+        value::{F64Deserializer, I64Deserializer},
+    };
 
     use rand::{Rng, distributions::Alphanumeric};
 
     use crate::helpers::gen_random_hex;
-    use crate::servers::api_tests::{ANON_EMAIL, TEST_EMAIL};
+    use crate::servers::api_tests::{ANON_EMAIL, TEST_EMAIL, TestBodyMealPhoto, TestSetup};
     use crate::{C, S};
 
     use super::*;
@@ -569,16 +954,136 @@ mod tests {
     }
 
     #[test]
-    fn incoming_serializer_photo() {
-        // Valid
-        assert!(IncomingDeserializer::parse_photo_name("J"));
-        assert!(IncomingDeserializer::parse_photo_name("D"));
+    fn incoming_serializer_initial_ok() {
+        let test = |initial: &str| {
+            let deserializer: StringDeserializer<ValueError> =
+                initial.to_owned().into_deserializer();
+            let result = IncomingDeserializer::initial(deserializer);
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap(), initial);
+        };
+        test("A");
+        test("J");
+        test("Z");
+    }
+
+    #[test]
+    fn incoming_serializer_initial_err() {
+        let test = |initial: &str| {
+            let deserializer: StringDeserializer<ValueError> =
+                initial.to_owned().into_deserializer();
+            let result = IncomingDeserializer::initial(deserializer);
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err().to_string(), "initial");
+        };
+        test("");
+        test("j");
+        test("JD");
+        test("1");
+        test(" J");
+        test("É");
+    }
+
+    #[test]
+    fn incoming_serializer_visibility_ok() {
+        let test = |visibility: &str, expected: Visibility| {
+            let deserializer: StringDeserializer<ValueError> =
+                visibility.to_owned().into_deserializer();
+            let result = IncomingDeserializer::visibility(deserializer);
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap(), expected);
+        };
+        test("public", Visibility::Public);
+        test("authenticated", Visibility::Authenticated);
+        test("admin", Visibility::Admin);
+    }
+
+    #[test]
+    fn incoming_serializer_visibility_err() {
+        let test = |visibility: &str| {
+            let deserializer: StringDeserializer<ValueError> =
+                visibility.to_owned().into_deserializer();
+            let result = IncomingDeserializer::visibility(deserializer);
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err().to_string(), "visibility");
+        };
+        test("");
+        test("Public");
+        test("private");
+        test(" admin");
+    }
+
+    #[test]
+    fn incoming_serializer_slot() {
+        let test = |slot: &str, expected: MealSlot| {
+            let deserializer: StringDeserializer<ValueError> = slot.to_owned().into_deserializer();
+            let result = IncomingDeserializer::slot(deserializer);
+            assert_eq!(result.unwrap(), expected);
+        };
+        test("breakfast", MealSlot::Breakfast);
+        test("lunch", MealSlot::Lunch);
+        test("dinner", MealSlot::Dinner);
+
+        for slot in ["", "Dinner", "supper", " lunch"] {
+            let deserializer: StringDeserializer<ValueError> = slot.to_owned().into_deserializer();
+            let result = IncomingDeserializer::slot(deserializer);
+            assert_eq!(result.unwrap_err().to_string(), "slot");
+        }
+    }
+
+    #[test]
+    fn incoming_serializer_search_ok() {
+        let test = |search: &str, expected: &str| {
+            let deserializer: StringDeserializer<ValueError> =
+                search.to_owned().into_deserializer();
+            let result = IncomingDeserializer::search(deserializer);
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap(), expected);
+        };
+        test("a", "a");
+        test(" chicken curry ", "chicken curry");
+        test(&"a".repeat(100), &"a".repeat(100));
+    }
 
-        // Invalid
-        assert!(!IncomingDeserializer::parse_photo_name("j"));
-        assert!(!IncomingDeserializer::parse_photo_name("d"));
-        assert!(!IncomingDeserializer::parse_photo_name("1"));
-        assert!(!IncomingDeserializer::parse_photo_name("p"));
+    #[test]
+    fn incoming_serializer_search_err() {
+        let test = |search: &str| {
+            let deserializer: StringDeserializer<ValueError> =
+                search.to_owned().into_deserializer();
+            let result = IncomingDeserializer::search(deserializer);
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err().to_string(), "search");
+        };
+        test("");
+        test("   ");
+        test(&"a".repeat(101));
+    }
+
+    #[test]
+    fn incoming_serializer_cursor() {
+        let test = |cursor: &str| {
+            let deserializer: StringDeserializer<ValueError> =
+                cursor.to_owned().into_deserializer();
+            IncomingDeserializer::cursor(deserializer)
+        };
+        for cursor in ["0-0-0-0", "1204-16-840-210"] {
+            let result = test(cursor);
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap().to_string(), cursor);
+        }
+        for cursor in [
+            "",
+            "1-2-3",
+            "1-2-3-4-5",
+            "1-2-3-a",
+            "-1-2-3-4",
+            "1--2-3-4",
+            " 1-2-3-4",
+        ] {
+            let result = test(cursor);
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err().to_string(), "cursor");
+        }
     }
 
     #[test]
@@ -1051,6 +1556,115 @@ mod tests {
         assert_eq!(result.unwrap_err().to_string(), "password");
     }
 
+    #[test]
+    fn incoming_serializer_tags() {
+        let test = |tags: &str, expected: Vec<i64>| {
+            let deserializer: StringDeserializer<ValueError> = S!(tags).into_deserializer();
+            let result = IncomingDeserializer::tags(deserializer);
+            assert_eq!(result.unwrap(), expected);
+        };
+        test("1", vec![1]);
+        test("3,1, 2,3", vec![1, 2, 3]);
+
+        let deserializer: SeqDeserializer<_, ValueError> =
+            SeqDeserializer::new(vec![4_i64, 2, 4].into_iter());
+        let result = IncomingDeserializer::tags(deserializer);
+        assert_eq!(result.unwrap(), vec![2, 4]);
+
+        for tags in ["", "1,", "a", "1,0", "-1"] {
+            let deserializer: StringDeserializer<ValueError> = S!(tags).into_deserializer();
+            let result = IncomingDeserializer::tags(deserializer);
+            assert_eq!(result.unwrap_err().to_string(), "tags");
+        }
+    }
+
+    #[test]
+    fn incoming_serializer_venue() {
+        let deserializer: StringDeserializer<ValueError> = S!("pub").into_deserializer();
+        let result = IncomingDeserializer::venue_type(deserializer);
+        assert_eq!(result.unwrap(), VenueType::Pub);
+        let deserializer: StringDeserializer<ValueError> = S!("Pub").into_deserializer();
+        let result = IncomingDeserializer::venue_type(deserializer);
+        assert_eq!(result.unwrap_err().to_string(), "venue_type");
+
+        for (venue_id, expected) in [
+            (serde_json::json!(12), Some(12)),
+            (serde_json::json!(" 12"), Some(12)),
+            (serde_json::Value::Null, None),
+        ] {
+            let result = IncomingDeserializer::option_venue_id(venue_id);
+            assert_eq!(result.unwrap(), expected);
+        }
+        for venue_id in [
+            serde_json::json!(0),
+            serde_json::json!("-1"),
+            serde_json::json!("a"),
+            serde_json::json!(1.5),
+        ] {
+            let result = IncomingDeserializer::option_venue_id(venue_id);
+            assert_eq!(result.unwrap_err().to_string(), "venue_id");
+        }
+
+        for (latitude, valid) in [
+            (90.0, true),
+            (-90.0, true),
+            (90.1, false),
+            (f64::NAN, false),
+        ] {
+            let deserializer: F64Deserializer<ValueError> = latitude.into_deserializer();
+            assert_eq!(IncomingDeserializer::latitude(deserializer).is_ok(), valid);
+        }
+        for (longitude, valid) in [(180.0, true), (-180.1, false)] {
+            let deserializer: F64Deserializer<ValueError> = longitude.into_deserializer();
+            assert_eq!(IncomingDeserializer::longitude(deserializer).is_ok(), valid);
+        }
+    }
+
+    #[test]
+    fn incoming_serializer_photos() {
+        let test = |photos: &str| {
+            let deserializer: StringDeserializer<ValueError> = S!(photos).into_deserializer();
+            IncomingDeserializer::photos(deserializer)
+        };
+        let (first, second) = (TestSetup::gen_meal_photo(), TestSetup::gen_meal_photo());
+        let pair = |photo: &TestBodyMealPhoto, caption: &str| {
+            format!(
+                r#"{{"original":"{}","converted":"{}","caption":"{caption}"}}"#,
+                photo.original, photo.converted
+            )
+        };
+
+        let result = test(&format!("[{},{}]", pair(&second, " b "), pair(&first, "a"))).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].converted.to_string(), second.converted);
+        assert_eq!(result[0].caption.as_deref(), Some("b"));
+        assert_eq!(result[1].original.to_string(), first.original);
+        assert!(test("[]").unwrap().is_empty());
+
+        let swapped = TestBodyMealPhoto {
+            original: C!(first.converted),
+            converted: C!(first.original),
+            caption: None,
+        };
+        for photos in [
+            S!(""),
+            S!("{}"),
+            format!("[{},{}]", pair(&first, "a"), pair(&first, "b")),
+            format!("[{}]", pair(&first, "")),
+            format!("[{}]", pair(&first, &"a".repeat(257))),
+            format!("[{}]", pair(&swapped, "a")),
+            format!(
+                "[{}]",
+                (0..11)
+                    .map(|_| pair(&TestSetup::gen_meal_photo(), "a"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        ] {
+            assert_eq!(test(&photos).unwrap_err().to_string(), "photos");
+        }
+    }
+
     #[test]
     fn incoming_serializer_invite() {
         let test = |invite: String| {
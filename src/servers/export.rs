@@ -0,0 +1,318 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{AppendHeaders, IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use jiff::{Timestamp, civil::Date};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::{
+    S,
+    api_error::ApiError,
+    database::{ModelMeal, Visibility},
+    helpers::csv_row,
+};
+
+use super::{ij, oj};
+
+/// Number of formatted meals to buffer, before waiting for the client to catch up
+const EXPORT_BUFFER: usize = 64;
+
+/// Max length, in octets, of an iCalendar content line, longer lines must be folded
+const ICS_LINE_LEN: usize = 75;
+
+/// The column names of the csv export, matches the meal import field names, so an export can be re-imported
+const CSV_HEADER: [&str; 12] = [
+    "date",
+    "category",
+    "person",
+    "slot",
+    "restaurant",
+    "takeaway",
+    "vegetarian",
+    "description",
+    "photos",
+    "visibility",
+    "tags",
+    "venue_id",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Ics,
+}
+
+impl TryFrom<&str> for ExportFormat {
+    type Error = ApiError;
+    fn try_from(x: &str) -> Result<Self, ApiError> {
+        match x {
+            "csv" => Ok(Self::Csv),
+            "jsonl" => Ok(Self::Jsonl),
+            "ics" => Ok(Self::Ics),
+            _ => Err(ApiError::InvalidValue(S!("format"))),
+        }
+    }
+}
+
+impl ExportFormat {
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/jsonl; charset=utf-8",
+            Self::Ics => "text/calendar; charset=utf-8",
+        }
+    }
+
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+            Self::Ics => "ics",
+        }
+    }
+}
+
+/// Escape an iCalendar TEXT value
+fn ics_escape(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\r', "")
+        .replace('\n', "\\n")
+}
+
+/// Create an iCalendar content line, folded into multiple lines if it's longer than 75 octets
+fn ics_line(line: &str) -> String {
+    let mut output = String::with_capacity(line.len() + 2);
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > ICS_LINE_LEN {
+            output.push_str("\r\n ");
+            len = 1;
+        }
+        output.push(c);
+        len += c.len_utf8();
+    }
+    output.push_str("\r\n");
+    output
+}
+
+/// iCalendar date value, yyyymmdd
+fn ics_date(date: Date) -> String {
+    date.strftime("%Y%m%d").to_string()
+}
+
+/// Convert meals into a given format, one meal at a time
+struct MealExporter {
+    format: ExportFormat,
+    domain: String,
+    timestamp: String,
+}
+
+impl MealExporter {
+    fn new(format: ExportFormat, domain: &str) -> Self {
+        Self {
+            format,
+            domain: domain.to_owned(),
+            timestamp: Timestamp::now().strftime("%Y%m%dT%H%M%SZ").to_string(),
+        }
+    }
+
+    fn header(&self) -> String {
+        match self.format {
+            ExportFormat::Csv => csv_row(&CSV_HEADER),
+            ExportFormat::Jsonl => String::new(),
+            ExportFormat::Ics => [
+                S!("BEGIN:VCALENDAR"),
+                S!("VERSION:2.0"),
+                format!(
+                    "PRODID:-//{}//{} {}//EN",
+                    self.domain,
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                ),
+                S!("CALSCALE:GREGORIAN"),
+                S!("X-WR-CALNAME:Meal Pedant"),
+            ]
+            .iter()
+            .map(|i| ics_line(i))
+            .collect(),
+        }
+    }
+
+    fn footer(&self) -> String {
+        match self.format {
+            ExportFormat::Csv | ExportFormat::Jsonl => String::new(),
+            ExportFormat::Ics => ics_line("END:VCALENDAR"),
+        }
+    }
+
+    fn meal(&self, meal: ModelMeal) -> Result<String, serde_json::Error> {
+        match self.format {
+            ExportFormat::Csv => {
+                let date = meal.meal_date.to_jiff().to_string();
+                let tags = meal
+                    .tags
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                // A JSON array, as there can be multiple photos, each with an optional caption
+                let photos = if meal.photos.is_empty() {
+                    String::new()
+                } else {
+                    serde_json::to_string(
+                        &meal
+                            .photos
+                            .into_iter()
+                            .map(oj::MealPhoto::from)
+                            .collect::<Vec<_>>(),
+                    )?
+                };
+                Ok(csv_row(&[
+                    &date,
+                    &meal.category,
+                    &meal.person,
+                    &meal.slot,
+                    &meal.restaurant.to_string(),
+                    &meal.takeaway.to_string(),
+                    &meal.vegetarian.to_string(),
+                    &meal.description,
+                    &photos,
+                    meal.visibility.as_deref().unwrap_or_default(),
+                    &tags,
+                    &meal
+                        .meal_venue_id
+                        .map(|i| i.to_string())
+                        .unwrap_or_default(),
+                ]))
+            }
+            ExportFormat::Jsonl => {
+                let mut line = serde_json::to_string(&oj::Meal::from(meal))?;
+                line.push('\n');
+                Ok(line)
+            }
+            ExportFormat::Ics => Ok(self.ics_event(&meal)),
+        }
+    }
+
+    /// An all-day event, the uid is based on the meal id, so re-importing a calendar updates, rather than duplicates, events
+    fn ics_event(&self, meal: &ModelMeal) -> String {
+        let date = meal.meal_date.to_jiff();
+        let flags = [
+            (meal.restaurant, "restaurant"),
+            (meal.takeaway, "takeaway"),
+            (meal.vegetarian, "vegetarian"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect::<Vec<_>>();
+
+        let mut lines = vec![
+            S!("BEGIN:VEVENT"),
+            format!("UID:meal-{}@{}", meal.individual_meal_id, self.domain),
+            format!("DTSTAMP:{}", self.timestamp),
+            format!("DTSTART;VALUE=DATE:{}", ics_date(date)),
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                ics_date(date.tomorrow().unwrap_or(date))
+            ),
+            format!(
+                "SUMMARY:{}: {}",
+                ics_escape(&meal.person),
+                ics_escape(&meal.description)
+            ),
+            format!("CATEGORIES:{}", ics_escape(&meal.category)),
+        ];
+        if !flags.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", ics_escape(&flags.join(", "))));
+        }
+        lines.push(S!("END:VEVENT"));
+        lines.iter().map(|i| ics_line(i)).collect()
+    }
+}
+
+/// Stream every meal visible at the given visibility level, in the requested format, as a file attachment
+/// Meals are formatted as they are read from postgres, so the whole export is never held in memory
+pub fn export_response(
+    postgres: &PgPool,
+    domain: &str,
+    export: ij::MealExport,
+    visibility: Visibility,
+) -> Result<Response, ApiError> {
+    ModelMeal::check_date_range(export.from, export.to)?;
+
+    let format = export.format;
+    let exporter = MealExporter::new(format, domain);
+    let postgres = postgres.clone();
+    let (mut tx, rx) = futures::channel::mpsc::channel::<Result<String, ApiError>>(EXPORT_BUFFER);
+
+    tokio::spawn(async move {
+        if tx.send(Ok(exporter.header())).await.is_err() {
+            return;
+        }
+        let mut meals = ModelMeal::export(&postgres, &export, visibility);
+        while let Some(meal) = meals.next().await {
+            let chunk = match meal {
+                Ok(meal) => exporter.meal(meal).map_err(ApiError::from),
+                Err(e) => Err(ApiError::from(e)),
+            };
+            let failed = chunk.is_err();
+            if let Err(e) = &chunk {
+                error!("meal export: {e:?}");
+            }
+            // Sending an error aborts the response, so the client won't mistake a partial export for a complete one
+            if tx.send(chunk).await.is_err() || failed {
+                return;
+            }
+        }
+        tx.send(Ok(exporter.footer())).await.ok();
+    });
+
+    let attach = format!("attachment; filename=\"mealpedant.{}\"", format.extension());
+    let headers = AppendHeaders([
+        (header::CONTENT_TYPE, format.content_type().to_owned()),
+        (header::CONTENT_DISPOSITION, attach),
+    ]);
+    Ok((headers, Body::from_stream(rx)).into_response())
+}
+
+/// cargo watch -q -c -w src/ -x 'test export_ -- --test-threads=1 --nocapture'
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_ics_escape() {
+        assert_eq!(ics_escape("a, b; c\\d\r\ne"), "a\\, b\\; c\\\\d\\ne");
+    }
+
+    #[test]
+    fn export_ics_line() {
+        assert_eq!(ics_line("SUMMARY:short"), "SUMMARY:short\r\n");
+
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let result = ics_line(&line);
+        for i in result.split("\r\n") {
+            assert!(i.len() <= ICS_LINE_LEN);
+        }
+        assert_eq!(result.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn export_format_try_from() {
+        assert_eq!(ExportFormat::try_from("csv").unwrap(), ExportFormat::Csv);
+        assert_eq!(
+            ExportFormat::try_from("jsonl").unwrap(),
+            ExportFormat::Jsonl
+        );
+        assert_eq!(ExportFormat::try_from("ics").unwrap(), ExportFormat::Ics);
+        assert!(ExportFormat::try_from("xml").is_err());
+    }
+}
@@ -2,8 +2,14 @@ pub mod ij {
     use crate::{
         C, S,
         api_error::ApiError,
-        database::{FromModel, ModelMeal, Person},
-        servers::deserializer::IncomingDeserializer as is,
+        database::{
+            ChangeCursor, FromModel, MealEncoding, MealSlot, ModelMeal, ModelMealPhoto,
+            ModelMealRevision, ModelVenue, Person, VenueType, Visibility,
+        },
+        helpers::{parse_csv, quality_values},
+        servers::{
+            body_format::BodyFormat, deserializer::IncomingDeserializer as is, export::ExportFormat,
+        },
     };
 
     use std::{error::Error, fmt, net::IpAddr};
@@ -13,8 +19,9 @@ pub mod ij {
             FromRequest, FromRequestParts,
             rejection::{JsonDataError, JsonRejection},
         },
-        http::{Request, request::Parts},
+        http::{Request, header, request::Parts},
     };
+    use bytes::Bytes;
     use jiff::civil::Date;
     use serde::{self, Deserialize, de::DeserializeOwned};
     use tracing::trace;
@@ -88,22 +95,14 @@ pub mod ij {
 
     #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
     #[cfg_attr(test, derive(Serialize))]
-    /// [ulid:26][Dave/Jack][Original,Converted].jpg
-    /// [ulid:26][0/1]      [0/1]               .jpg
+    /// [ulid:26][Private/Public][Original,Converted].jpg
+    /// [ulid:26][0/1]           [0/1]               .jpg
     pub enum PhotoName {
         Original(String),
         Converted(String),
     }
 
     impl PhotoName {
-        /// Dave photos 27th (26 using  0 index) char is always 0
-        pub fn is_dave(&self) -> bool {
-            match self {
-                Self::Original(filename) | Self::Converted(filename) => {
-                    filename.chars().nth(26) == Some('0')
-                }
-            }
-        }
         /// Verify that a string matches the expected format
         pub fn valid_name(file_name: &str) -> bool {
             if file_name.chars().count() != 32 {
@@ -174,9 +173,11 @@ pub mod ij {
     /// Implement custom error handing for JSON extraction on incoming JSON
     /// Either return valid json (meeting a struct spec listed below), or return an ApiError
     /// Then each route handler, can use `IncomingJson(body): IncomingJson<T>`, to extract T into param body
+    /// A MessagePack, or CBOR, body is also accepted, based on the Content-Type header
     impl<S, T> FromRequest<S> for IncomingJson<T>
     where
         axum::Json<T>: FromRequest<S, Rejection = JsonRejection>,
+        T: DeserializeOwned,
         S: Send + Sync,
     {
         type Rejection = ApiError;
@@ -185,6 +186,13 @@ pub mod ij {
             req: Request<axum::body::Body>,
             state: &S,
         ) -> Result<Self, Self::Rejection> {
+            if let Some(format) = BodyFormat::from_content_type(req.headers()) {
+                let body = Bytes::from_request(req, state).await.map_err(|e| {
+                    trace!(%e);
+                    ApiError::InvalidValue(S!("Bytes Rejected"))
+                })?;
+                return format.deserialize(&body).map(Self);
+            }
             match axum::Json::<T>::from_request(req, state).await {
                 Ok(value) => Ok(Self(value.0)),
                 Err(rejection) => match rejection {
@@ -206,6 +214,100 @@ pub mod ij {
             }
         }
     }
+    /// Convert a serde_json error from a single value into an error message, usually the name of the invalid field
+    fn value_error(e: &serde_json::Error) -> String {
+        let text = e.to_string();
+        if text.contains("missing field") {
+            format!("missing {}", text.split('`').nth(1).unwrap_or_default())
+        } else if text.contains("unknown field") {
+            S!("invalid input")
+        } else {
+            text
+        }
+    }
+
+    /// A csv cell is a bool if it is "true" or "false", an empty cell is treated as a missing field
+    fn csv_row_to_value(headers: &[String], row: Vec<String>) -> Result<serde_json::Value, String> {
+        if row.len() != headers.len() {
+            return Err(S!("columns"));
+        }
+        Ok(serde_json::Value::Object(
+            headers
+                .iter()
+                .zip(row)
+                .filter(|(_, cell)| !cell.is_empty())
+                .map(|(header, cell)| {
+                    let value = match cell.as_str() {
+                        "true" => serde_json::Value::Bool(true),
+                        "false" => serde_json::Value::Bool(false),
+                        _ => serde_json::Value::String(cell),
+                    };
+                    (C!(header), value)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Bulk import meals, either a "text/csv" body, with a header row of Meal field names, or an "application/json" array of Meals
+    /// Each row is validated individually, so that every error can be reported
+    pub struct ImportMeals(pub Vec<Result<Meal, String>>);
+
+    impl<S> FromRequest<S> for ImportMeals
+    where
+        S: Send + Sync,
+    {
+        type Rejection = ApiError;
+
+        async fn from_request(
+            req: Request<axum::body::Body>,
+            state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            let content_type = req
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|i| i.to_str().ok())
+                .unwrap_or_default()
+                .to_owned();
+            let body = String::from_request(req, state)
+                .await
+                .map_err(|_| ApiError::InvalidValue(S!("body")))?;
+
+            let values = if content_type.starts_with("text/csv") {
+                let mut rows = parse_csv(&body)
+                    .ok_or_else(|| ApiError::InvalidValue(S!("csv")))?
+                    .into_iter();
+                let headers = rows.next().unwrap_or_default();
+                rows.map(|row| csv_row_to_value(&headers, row))
+                    .collect::<Vec<_>>()
+            } else if content_type.starts_with("application/json") {
+                serde_json::from_str::<Vec<serde_json::Value>>(&body)
+                    .map_err(|_| ApiError::InvalidValue(S!("JSON")))?
+                    .into_iter()
+                    .map(Ok)
+                    .collect::<Vec<_>>()
+            } else {
+                return Err(ApiError::InvalidValue(S!(
+                    "\"text/csv\" or \"application/json\" header"
+                )));
+            };
+
+            if values.is_empty() {
+                return Err(ApiError::InvalidValue(S!("empty")));
+            }
+
+            Ok(Self(
+                values
+                    .into_iter()
+                    .map(|value| {
+                        value.and_then(|value| {
+                            serde_json::from_value::<Meal>(value).map_err(|e| value_error(&e))
+                        })
+                    })
+                    .collect(),
+            ))
+        }
+    }
+
     pub struct Path<T>(pub T);
 
     impl<S, T> FromRequestParts<S> for Path<T>
@@ -223,6 +325,39 @@ pub mod ij {
         }
     }
 
+    pub struct Query<T>(pub T);
+
+    /// Extract query params, a missing param is a MissingKey, otherwise return the deserializer error, which is usually the param type
+    impl<S, T> FromRequestParts<S> for Query<T>
+    where
+        T: DeserializeOwned,
+        S: Send + Sync,
+    {
+        type Rejection = ApiError;
+        async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+            match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+                Ok(value) => Ok(Self(value.0)),
+                Err(e) => {
+                    let text = e.body_text();
+                    // Strip the axum prefix, and the field path, e.g. "q: search" becomes "search"
+                    let text = text
+                        .split_once("query string: ")
+                        .map_or(text.as_str(), |f| f.1);
+                    let text = text.split_once(": ").map_or(text, |f| f.1);
+                    if text.contains("missing field") {
+                        Err(ApiError::MissingKey(
+                            text.split('`').nth(1).unwrap_or_default().to_owned(),
+                        ))
+                    } else if text.contains("unknown field") {
+                        Err(ApiError::InvalidValue(S!("invalid input")))
+                    } else {
+                        Err(ApiError::InvalidValue(text.to_owned()))
+                    }
+                }
+            }
+        }
+    }
+
     #[derive(Deserialize, Debug)]
     #[serde(deny_unknown_fields)]
     pub struct Register {
@@ -298,6 +433,13 @@ pub mod ij {
         pub token: Option<Token>,
     }
 
+    #[derive(Deserialize, Debug)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct MealReminder {
+        pub meal_reminder: bool,
+    }
+
     #[derive(Deserialize, Debug)]
     #[serde(deny_unknown_fields)]
     pub struct BothPhoto {
@@ -327,6 +469,47 @@ pub mod ij {
         pub date: Date,
         #[serde(deserialize_with = "is::person")]
         pub person: Person,
+        /// Routes without a slot param are for the dinner slot
+        #[serde(deserialize_with = "is::slot")]
+        #[serde(default)]
+        pub slot: MealSlot,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct DatePersonRevision {
+        #[serde(deserialize_with = "is::date")]
+        pub date: Date,
+        #[serde(deserialize_with = "is::person")]
+        pub person: Person,
+        #[serde(deserialize_with = "is::slot")]
+        #[serde(default)]
+        pub slot: MealSlot,
+        #[serde(deserialize_with = "is::id")]
+        pub revision: i64,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct MealPhoto {
+        #[serde(deserialize_with = "is::photo_name_hex")]
+        pub original: PhotoName,
+        #[serde(deserialize_with = "is::photo_name_hex")]
+        pub converted: PhotoName,
+        #[serde(deserialize_with = "is::option_caption")]
+        #[serde(default)]
+        pub caption: Option<String>,
+    }
+
+    impl MealPhoto {
+        /// None if the original photo isn't visible
+        fn from_model(photo: &ModelMealPhoto) -> Option<Self> {
+            Some(Self {
+                original: PhotoName::Original(C!(photo.photo_original)?),
+                converted: PhotoName::Converted(C!(photo.photo_converted)),
+                caption: C!(photo.caption),
+            })
+        }
     }
 
     #[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
@@ -338,17 +521,31 @@ pub mod ij {
         pub category: String,
         #[serde(deserialize_with = "is::person")]
         pub person: Person,
+        /// Defaults to dinner
+        #[serde(deserialize_with = "is::slot")]
+        #[serde(default)]
+        pub slot: MealSlot,
         pub restaurant: bool,
         pub takeaway: bool,
         pub vegetarian: bool,
         #[serde(deserialize_with = "is::trimmed")]
         pub description: String,
-        #[serde(deserialize_with = "is::option_photo_name_hex")]
+        /// Ordered, the first photo is the main photo of the meal
+        #[serde(deserialize_with = "is::photos")]
         #[serde(default)]
-        pub photo_original: Option<PhotoName>,
-        #[serde(deserialize_with = "is::option_photo_name_hex")]
+        pub photos: Vec<MealPhoto>,
+        /// Override the visibility of the person, for this meal only
+        #[serde(deserialize_with = "is::option_visibility")]
         #[serde(default)]
-        pub photo_converted: Option<PhotoName>,
+        pub visibility: Option<Visibility>,
+        /// Tag ids
+        #[serde(deserialize_with = "is::tags")]
+        #[serde(default)]
+        pub tags: Vec<i64>,
+        /// Only for restaurant or takeaway meals
+        #[serde(deserialize_with = "is::option_venue_id")]
+        #[serde(default)]
+        pub venue_id: Option<i64>,
     }
 
     impl FromModel<ModelMeal> for Meal {
@@ -358,13 +555,62 @@ pub mod ij {
                 date: meal.meal_date.to_jiff(),
                 category: meal.category,
                 person: Person::try_from(meal.person.as_str())?,
+                slot: MealSlot::try_from(meal.slot.as_str())?,
                 restaurant: meal.restaurant,
                 takeaway: meal.takeaway,
                 vegetarian: meal.vegetarian,
                 description: meal.description,
-                // photo try
-                photo_original: meal.photo_original.map(PhotoName::Original),
-                photo_converted: meal.photo_converted.map(PhotoName::Converted),
+                photos: meal
+                    .photos
+                    .iter()
+                    .filter_map(MealPhoto::from_model)
+                    .collect(),
+                visibility: meal
+                    .visibility
+                    .as_deref()
+                    .map(Visibility::try_from)
+                    .transpose()?,
+                tags: meal.tags,
+                venue_id: meal.meal_venue_id,
+            })
+        }
+    }
+
+    /// A revision can only be converted if every field could be resolved, and the person still exists
+    impl FromModel<ModelMealRevision> for Meal {
+        type Item = Self;
+        fn from_model(revision: ModelMealRevision) -> Result<Self, ApiError> {
+            let unavailable = || ApiError::InvalidValue(S!("revision unavailable"));
+            let (Some(date), Some(person), Some(category), Some(description)) = (
+                revision.date,
+                revision.person,
+                revision.category,
+                revision.description,
+            ) else {
+                return Err(unavailable());
+            };
+            Ok(Self {
+                date: date.parse().map_err(|_| unavailable())?,
+                category,
+                person: Person::try_from(person.as_str()).map_err(|_| unavailable())?,
+                slot: MealSlot::try_from(revision.slot.as_str())?,
+                restaurant: revision.restaurant,
+                takeaway: revision.takeaway,
+                vegetarian: revision.vegetarian,
+                description,
+                photos: revision
+                    .photos
+                    .iter()
+                    .filter_map(MealPhoto::from_model)
+                    .collect(),
+                visibility: revision
+                    .visibility
+                    .as_deref()
+                    .map(Visibility::try_from)
+                    .transpose()?,
+                // Tags aren't recorded in the audit table
+                tags: vec![],
+                venue_id: revision.venue_id,
             })
         }
     }
@@ -376,28 +622,439 @@ pub mod ij {
                 date: meal.meal_date.to_jiff(),
                 category: C!(meal.category),
                 person: Person::try_from(meal.person.as_str())?,
+                slot: MealSlot::try_from(meal.slot.as_str())?,
                 restaurant: meal.restaurant,
                 takeaway: meal.takeaway,
                 vegetarian: meal.vegetarian,
                 description: C!(meal.description),
-                photo_original: meal
-                    .photo_original
-                    .as_ref()
-                    .map(|original| PhotoName::Original(C!(original))),
-                photo_converted: meal
-                    .photo_converted
-                    .as_ref()
-                    .map(|converted| PhotoName::Converted(C!(converted))),
+                photos: meal
+                    .photos
+                    .iter()
+                    .filter_map(MealPhoto::from_model)
+                    .collect(),
+                visibility: meal
+                    .visibility
+                    .as_deref()
+                    .map(Visibility::try_from)
+                    .transpose()?,
+                tags: C!(meal.tags),
+                venue_id: meal.meal_venue_id,
             })
         }
     }
 
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct MealImport {
+        /// Only validate the meals, don't insert them
+        pub dry_run: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct MealChanges {
+        /// No cursor means every change
+        #[serde(default, deserialize_with = "is::option_cursor")]
+        pub since: Option<ChangeCursor>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct MealSuggest {
+        #[serde(deserialize_with = "is::person")]
+        pub person: Person,
+        /// Comma separated categories
+        #[serde(default, deserialize_with = "is::exclude")]
+        pub exclude: Vec<String>,
+        /// Only suggest vegetarian, or non-vegetarian, dishes
+        pub vegetarian: Option<bool>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct OnThisDay {
+        /// No date means today
+        #[serde(default, deserialize_with = "is::option_date")]
+        pub date: Option<Date>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct MealSearch {
+        #[serde(deserialize_with = "is::search")]
+        pub q: String,
+        #[serde(default, deserialize_with = "is::option_person")]
+        pub person: Option<Person>,
+        #[serde(default, deserialize_with = "is::option_trimmed")]
+        pub category: Option<String>,
+        #[serde(default, deserialize_with = "is::option_date")]
+        pub from: Option<Date>,
+        #[serde(default, deserialize_with = "is::option_date")]
+        pub to: Option<Date>,
+        pub restaurant: Option<bool>,
+        pub takeaway: Option<bool>,
+        pub vegetarian: Option<bool>,
+        /// Comma separated tag ids, meals must have every tag
+        #[serde(default, deserialize_with = "is::option_tags")]
+        pub tags: Option<Vec<i64>>,
+        /// Page number, starting at 1
+        #[serde(default, deserialize_with = "is::option_id")]
+        pub page: Option<i64>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct TagPost {
+        #[serde(deserialize_with = "is::tag")]
+        pub tag: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct TagPatch {
+        #[serde(deserialize_with = "is::id")]
+        pub meal_tag_id: i64,
+        #[serde(deserialize_with = "is::tag")]
+        pub tag: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct TagParam {
+        #[serde(deserialize_with = "is::id")]
+        pub param: i64,
+    }
+
+    #[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct Coordinates {
+        #[serde(deserialize_with = "is::latitude")]
+        pub latitude: f64,
+        #[serde(deserialize_with = "is::longitude")]
+        pub longitude: f64,
+    }
+
+    #[derive(Debug, Deserialize, Clone, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    #[expect(clippy::struct_field_names)]
+    pub struct Venue {
+        #[serde(deserialize_with = "is::venue_name")]
+        pub name: String,
+        #[serde(deserialize_with = "is::city")]
+        pub city: String,
+        #[serde(deserialize_with = "is::venue_type")]
+        pub venue_type: VenueType,
+        #[serde(default)]
+        pub coordinates: Option<Coordinates>,
+    }
+
+    impl FromModel<&ModelVenue> for Venue {
+        type Item = Self;
+        fn from_model(venue: &ModelVenue) -> Result<Self, ApiError> {
+            Ok(Self {
+                name: C!(venue.name),
+                city: C!(venue.city),
+                venue_type: VenueType::try_from(venue.venue_type.as_str())?,
+                coordinates: venue
+                    .latitude
+                    .zip(venue.longitude)
+                    .map(|(latitude, longitude)| Coordinates {
+                        latitude,
+                        longitude,
+                    }),
+            })
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct VenuePatch {
+        #[serde(deserialize_with = "is::id")]
+        pub meal_venue_id: i64,
+        pub venue: Venue,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct VenueParam {
+        #[serde(deserialize_with = "is::id")]
+        pub param: i64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct TrashParam {
+        #[serde(deserialize_with = "is::id")]
+        pub param: i64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct CategoryPatch {
+        #[serde(deserialize_with = "is::category")]
+        pub original_category: String,
+        #[serde(deserialize_with = "is::category")]
+        pub category: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct CategoryMerge {
+        #[serde(deserialize_with = "is::category")]
+        pub from: String,
+        #[serde(deserialize_with = "is::category")]
+        pub into: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct DescriptionSimilar {
+        #[serde(default, deserialize_with = "is::option_threshold")]
+        pub threshold: Option<f32>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct DescriptionMerge {
+        /// The description every meal will use, can be one of the merged descriptions, or a new description
+        #[serde(deserialize_with = "is::description")]
+        pub description: String,
+        #[serde(deserialize_with = "is::descriptions")]
+        pub descriptions: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct MealExport {
+        #[serde(deserialize_with = "is::export_format")]
+        pub format: ExportFormat,
+        #[serde(default, deserialize_with = "is::option_person")]
+        pub person: Option<Person>,
+        #[serde(default, deserialize_with = "is::option_date")]
+        pub from: Option<Date>,
+        #[serde(default, deserialize_with = "is::option_date")]
+        pub to: Option<Date>,
+        /// Comma separated tag ids, meals must have every tag
+        #[serde(default, deserialize_with = "is::option_tags")]
+        pub tags: Option<Vec<i64>>,
+    }
+
     #[derive(Debug, Deserialize)]
     #[serde(deny_unknown_fields)]
     pub struct MealPatch {
         #[serde(deserialize_with = "is::date")]
         pub original_date: Date,
-        pub meal: Meal,
+        #[serde(deserialize_with = "is::slot")]
+        #[serde(default)]
+        pub original_slot: MealSlot,
+        /// Defaults to the person given in the patch
+        #[serde(deserialize_with = "is::option_person")]
+        #[serde(default)]
+        pub original_person: Option<Person>,
+        /// A JSON merge patch, applied to the current state of the meal
+        pub meal: serde_json::Map<String, serde_json::Value>,
+    }
+
+    impl MealPatch {
+        /// The person of the meal to update
+        pub fn person(&self) -> Result<Person, ApiError> {
+            match (&self.original_person, self.meal.get("person")) {
+                (Some(person), _) => Ok(C!(person)),
+                (None, Some(person)) => {
+                    is::person(C!(person)).map_err(|_| ApiError::InvalidValue(S!("person")))
+                }
+                (None, None) => Err(ApiError::MissingKey(S!("person"))),
+            }
+        }
+
+        /// Apply the patch to the current meal, and validate the result as a complete meal
+        pub fn apply(&self, mut current: serde_json::Value) -> Result<Meal, ApiError> {
+            merge_patch(&mut current, &serde_json::Value::Object(C!(self.meal)));
+            serde_json::from_value::<Meal>(current)
+                .map_err(|e| ApiError::InvalidValue(value_error(&e)))
+        }
+    }
+
+    /// Apply a JSON merge patch (RFC 7396), a null in the patch removes the key from the target
+    pub fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+        let serde_json::Value::Object(patch) = patch else {
+            *target = C!(patch);
+            return;
+        };
+        if !target.is_object() {
+            *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if let serde_json::Value::Object(target) = target {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_patch(target.entry(key).or_insert(serde_json::Value::Null), value);
+                }
+            }
+        }
+    }
+
+    /// The revision given in an `If-Match` header, None if the header is missing, or is "*"
+    pub struct IfMatch(pub Option<i64>);
+
+    impl<S> FromRequestParts<S> for IfMatch
+    where
+        S: Send + Sync,
+    {
+        type Rejection = ApiError;
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            let Some(value) = parts.headers.get(header::IF_MATCH) else {
+                return Ok(Self(None));
+            };
+            match value.to_str().map(str::trim) {
+                Ok("*") => Ok(Self(None)),
+                Ok(value) => value
+                    .trim_matches('"')
+                    .parse::<i64>()
+                    .map(|revision| Self(Some(revision)))
+                    .map_err(|_| ApiError::InvalidValue(S!("If-Match"))),
+                Err(_) => Err(ApiError::InvalidValue(S!("If-Match"))),
+            }
+        }
+    }
+
+    /// The preferred encoding of the response, from the `Accept-Encoding` header, brotli is preferred over gzip when equally weighted
+    /// Falls back to identity if the header is missing, or neither brotli nor gzip are acceptable
+    pub struct AcceptEncoding(pub MealEncoding);
+
+    impl AcceptEncoding {
+        fn negotiate(header: &str) -> MealEncoding {
+            let weights = quality_values(header);
+            let weight = |coding: &str| {
+                weights
+                    .iter()
+                    .find(|(c, _)| c == coding)
+                    .or_else(|| weights.iter().find(|(c, _)| c == "*"))
+                    .map_or(0.0, |(_, q)| *q)
+            };
+
+            let mut output = (MealEncoding::Identity, 0.0);
+            for encoding in [MealEncoding::Brotli, MealEncoding::Gzip] {
+                let q = weight(encoding.name());
+                if q > output.1 {
+                    output = (encoding, q);
+                }
+            }
+            output.0
+        }
+    }
+
+    impl<S> FromRequestParts<S> for AcceptEncoding
+    where
+        S: Send + Sync,
+    {
+        type Rejection = ApiError;
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Ok(Self(Self::negotiate(
+                parts
+                    .headers
+                    .get(header::ACCEPT_ENCODING)
+                    .and_then(|i| i.to_str().ok())
+                    .unwrap_or_default(),
+            )))
+        }
+    }
+
+    /// The preferred format of the response, from the `Accept` header
+    pub struct AcceptFormat(pub BodyFormat);
+
+    impl<S> FromRequestParts<S> for AcceptFormat
+    where
+        S: Send + Sync,
+    {
+        type Rejection = ApiError;
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Ok(Self(BodyFormat::from_accept(&parts.headers)))
+        }
+    }
+
+    /// The raw `If-None-Match` header, None if missing or not valid ascii
+    pub struct IfNoneMatch(Option<String>);
+
+    impl IfNoneMatch {
+        /// Check if any of the given entity tags, or "*", match the given ETag, uses the weak comparison, as required for `If-None-Match`
+        pub fn matches(&self, etag: &str) -> bool {
+            self.0.as_ref().is_some_and(|header| {
+                header.split(',').map(str::trim).any(|i| {
+                    i == "*"
+                        || i.strip_prefix("W/").unwrap_or(i)
+                            == etag.strip_prefix("W/").unwrap_or(etag)
+                })
+            })
+        }
+    }
+
+    impl<S> FromRequestParts<S> for IfNoneMatch
+    where
+        S: Send + Sync,
+    {
+        type Rejection = ApiError;
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Ok(Self(
+                parts
+                    .headers
+                    .get(header::IF_NONE_MATCH)
+                    .and_then(|i| i.to_str().ok())
+                    .map(str::to_owned),
+            ))
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct PersonPost {
+        #[serde(deserialize_with = "is::name")]
+        pub person: String,
+        #[serde(deserialize_with = "is::initial")]
+        pub initial: String,
+        #[serde(deserialize_with = "is::visibility")]
+        pub visibility: Visibility,
+        /// The user to send missing meal reminders to
+        #[serde(default, deserialize_with = "is::option_email")]
+        pub email: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(test, derive(Serialize))]
+    pub struct PersonPatch {
+        #[serde(deserialize_with = "is::person")]
+        pub original_person: Person,
+        pub person: PersonPost,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct PersonParam {
+        #[serde(deserialize_with = "is::person")]
+        pub param: Person,
     }
 
     #[derive(Debug, Deserialize)]
@@ -406,6 +1063,13 @@ pub mod ij {
         pub with_photos: bool,
     }
 
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct OrphanPost {
+        /// Only report the orphans, without removing them
+        pub dry_run: bool,
+    }
+
     #[derive(Debug, Deserialize)]
     #[serde(deny_unknown_fields)]
     pub struct BackupDelete {
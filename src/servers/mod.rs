@@ -5,9 +5,9 @@ use ulid::Ulid;
 
 use axum::{
     extract::{ConnectInfo, FromRef, FromRequestParts, State},
-    http::{HeaderMap, Request},
+    http::{HeaderMap, HeaderValue, Request, StatusCode, header},
     middleware::Next,
-    response::Response,
+    response::{AppendHeaders, IntoResponse, Response},
 };
 use axum_extra::extract::{PrivateCookieJar, cookie::Key};
 use std::{
@@ -25,15 +25,18 @@ pub mod static_serve;
 use crate::{
     C, S,
     api_error::ApiError,
-    database::{RateLimit, backup::BackupEnv},
+    database::{MealEncoding, MealResponse, ModelPerson, RateLimit, Visibility, backup::BackupEnv},
     emailer::EmailerEnv,
     parse_env::{AppEnv, RunMode},
     photo_convertor::PhotoLocationEnv,
 };
 
+mod body_format;
+mod export;
 mod incoming_json;
 mod outgoing_json;
 
+pub use body_format::BodyFormat;
 pub use incoming_json::ij;
 pub use outgoing_json::oj;
 
@@ -145,6 +148,53 @@ pub fn get_cookie_ulid(state: &ApiState, jar: &PrivateCookieJar) -> Option<Ulid>
         .and_then(|i| Ulid::from_string(i.value()).ok())
 }
 
+/// All the meals visible at the given visibility level, served straight from the cache in the preferred format, and encoding
+/// Each format, and encoding, has its own strong ETag, and a 304 response is returned if the client's copy is still current
+/// Authenticated responses mustn't be stored by shared caches, and every response must be revalidated before reuse
+async fn meals_response(
+    state: &ApiState,
+    visibility: Visibility,
+    if_none_match: &ij::IfNoneMatch,
+    accept_format: ij::AcceptFormat,
+    accept_encoding: ij::AcceptEncoding,
+) -> Result<Response, ApiError> {
+    // The binary formats are already compact, so are only cached uncompressed
+    let encoding = match accept_format.0 {
+        BodyFormat::Json => accept_encoding.0,
+        BodyFormat::MessagePack => MealEncoding::MessagePack,
+        BodyFormat::Cbor => MealEncoding::Cbor,
+    };
+    let cache_control = if visibility == Visibility::Public {
+        "public, no-cache"
+    } else {
+        "private, no-cache"
+    };
+    let meals = MealResponse::get_all(&state.postgres, &state.redis, visibility, encoding).await?;
+    let etag = if encoding == MealEncoding::Identity {
+        oj::etag(meals.etag)
+    } else {
+        oj::etag(format!("{}-{}", meals.etag, encoding.name()))
+    };
+    let mut headers = vec![
+        (header::ETAG, C!(etag)),
+        (header::CACHE_CONTROL, S!(cache_control)),
+        (header::VARY, S!("Accept, Accept-Encoding")),
+    ];
+    if if_none_match.matches(&etag) {
+        return Ok((StatusCode::NOT_MODIFIED, AppendHeaders(headers)).into_response());
+    }
+    if let Some(content_encoding) = encoding.content_encoding() {
+        headers.push((header::CONTENT_ENCODING, S!(content_encoding)));
+    }
+    Ok((
+        StatusCode::OK,
+        AppendHeaders(headers),
+        [(header::CONTENT_TYPE, encoding.format().content_type())],
+        meals.body,
+    )
+        .into_response())
+}
+
 /// get a bind-able SocketAddr from the AppEnv
 fn get_addr(host: &str, port: u16) -> Result<SocketAddr, ApiError> {
     match (C!(host), port).to_socket_addrs() {
@@ -173,6 +223,56 @@ async fn rate_limiting(
     Ok(next.run(Request::from_parts(parts, body)).await)
 }
 
+/// Refresh the in memory person registry, if another instance has changed a person, before the request is deserialized
+async fn sync_people(
+    State(state): State<ApiState>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    ModelPerson::sync(&state.postgres, &state.redis).await?;
+    Ok(next.run(req).await)
+}
+
+/// Convert a json response into MessagePack, or CBOR, if the Accept header prefers either of them
+/// Responses that aren't json, or are already content encoded, such as exports, photos, and the cached meals, are left untouched
+async fn negotiate_format(
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let format = BodyFormat::from_accept(req.headers());
+    let mut response = next.run(req).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|i| i == BodyFormat::Json.content_type())
+        && !response.headers().contains_key(header::CONTENT_ENCODING);
+    if !is_json {
+        return Ok(response);
+    }
+    if !response.headers().contains_key(header::VARY) {
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("Accept"));
+    }
+    if format == BodyFormat::Json {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Ok(Response::from_parts(
+        parts,
+        axum::body::Body::from(format.transcode(&body)?),
+    ))
+}
+
 #[expect(clippy::expect_used)]
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -206,7 +306,7 @@ async fn shutdown_signal() {
 #[expect(clippy::unwrap_used, clippy::nursery)]
 pub mod api_tests {
     use fred::clients::Pool;
-    use fred::interfaces::{ClientLike, KeysInterface};
+    use fred::interfaces::{ClientLike, HashesInterface, KeysInterface};
     use fred::types::scan::Scanner;
     use futures::TryStreamExt;
     use jiff::civil::Date;
@@ -222,8 +322,8 @@ pub mod api_tests {
     use crate::C;
     use crate::S;
     use crate::database::{
-        DbRedis, ModelMeal, ModelTwoFA, ModelUser, ModelUserAgentIp, Person, RedisNewUser,
-        RedisTwoFASetup, ReqUserAgentIp, db_postgres,
+        DbRedis, MealSlot, ModelMeal, ModelMealTrash, ModelPerson, ModelTwoFA, ModelUser,
+        ModelUserAgentIp, Person, RedisNewUser, RedisTwoFASetup, ReqUserAgentIp, db_postgres,
     };
     use crate::helpers::{gen_random_hex, now_utc};
     use crate::parse_env;
@@ -254,6 +354,9 @@ pub mod api_tests {
     pub const ANON_PASSWORD_HASH: &str = "$argon2id$v=19$m=4096,t=1,p=1$ODYzbGwydnl4YzAwMDAwMA$x0HG3MOFFlMEDQoVNNacku3lj7yx2Mniacytc+ULPxU8GPj+";
     pub const ANON_FULL_NAME: &str = "Anon user full name";
 
+    pub const TEST_PERSON: &str = "Test Person";
+    pub const TEST_PERSON_INITIAL: &str = "X";
+
     static RATELIMIT_REGEX: LazyLock<Regex> =
         LazyLock::new(|| Regex::new("rate limited for ([5][0-9]|60) seconds").unwrap());
 
@@ -277,21 +380,59 @@ pub mod api_tests {
         pub category: String,
         pub description: String,
         pub person: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub slot: Option<String>,
         pub restaurant: bool,
         pub takeaway: bool,
         pub vegetarian: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub photos: Vec<TestBodyMealPhoto>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub visibility: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub tags: Vec<i64>,
         #[serde(skip_serializing_if = "Option::is_none")]
-        pub photo_original: Option<String>,
+        pub venue_id: Option<i64>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct TestBodyMealPhoto {
+        pub original: String,
+        pub converted: String,
         #[serde(skip_serializing_if = "Option::is_none")]
-        pub photo_converted: Option<String>,
+        pub caption: Option<String>,
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct TestBodyMealPatch {
         pub original_date: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub original_slot: Option<String>,
+        #[serde(serialize_with = "TestBodyMealPatch::full_meal")]
         pub meal: TestBodyMeal,
     }
 
+    impl TestBodyMealPatch {
+        /// The patch is a merge patch, so explicitly clear any empty fields, so that the whole meal is replaced
+        fn full_meal<S: serde::Serializer>(
+            meal: &TestBodyMeal,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            let mut value = serde_json::to_value(meal).map_err(serde::ser::Error::custom)?;
+            if let Some(value) = value.as_object_mut() {
+                for (key, empty) in [
+                    ("photos", Value::Array(vec![])),
+                    ("tags", Value::Array(vec![])),
+                    ("visibility", Value::Null),
+                    ("venue_id", Value::Null),
+                ] {
+                    value.entry(key).or_insert(empty);
+                }
+            }
+            value.serialize(serializer)
+        }
+    }
+
     #[derive(Serialize, Deserialize, Debug)]
     pub struct TestBodySignin {
         pub email: String,
@@ -308,6 +449,9 @@ pub mod api_tests {
         async fn clean_up(&mut self) {
             self.delete_two_fa_secret().await;
             self.delete_meal().await;
+            self.delete_test_person().await;
+            self.delete_tags().await;
+            self.delete_venues().await;
             // delete admin
             self.delete_test_user().await;
             self.delete_useragent_ip().await;
@@ -322,6 +466,24 @@ pub mod api_tests {
         pub async fn flush_redis(&self) {
             self.redis.flushall::<()>(true).await.unwrap();
         }
+
+        /// Wait for the meals caches to be re-warmed in the background, after they've been deleted, and return the admin meals response body
+        /// The admin cache is the last to be warmed
+        pub async fn warmed_meals_cache(&self) -> String {
+            let mut cache = None;
+            for _ in 0..200 {
+                if let Ok(Some(body)) = self
+                    .redis
+                    .hget::<Option<String>, _, _>("cache::meals::admin", "json")
+                    .await
+                {
+                    cache = Some(body);
+                    break;
+                }
+                sleep!(10);
+            }
+            cache.unwrap()
+        }
         /// generate user ip address, user agent, normally done in middleware automatically by server
         pub fn gen_req() -> ReqUserAgentIp {
             ReqUserAgentIp {
@@ -330,55 +492,83 @@ pub mod api_tests {
             }
         }
 
+        /// Generate a pair of photo names, without a caption
+        pub fn gen_meal_photo() -> TestBodyMealPhoto {
+            TestBodyMealPhoto {
+                original: format!(
+                    "{ulid}10.jpg",
+                    ulid = ulid::Ulid::new().to_string().to_lowercase(),
+                ),
+                converted: format!(
+                    "{ulid}11.jpg",
+                    ulid = ulid::Ulid::new().to_string().to_lowercase(),
+                ),
+                caption: None,
+            }
+        }
+
         /// Generate a meal for tomorrow
         pub fn gen_meal(&mut self, with_photo: bool) -> TestBodyMeal {
             let category = gen_random_hex(10);
             let description = gen_random_hex(24);
             let date = format!("{}", now_utc().tomorrow().unwrap().date());
-            let photo_original = if with_photo {
-                Some(format!(
-                    "{ulid}10.jpg",
-                    ulid = ulid::Ulid::new().to_string().to_lowercase(),
-                ))
-            } else {
-                None
-            };
-
-            let photo_converted = if with_photo {
-                Some(format!(
-                    "{ulid}11.jpg",
-                    ulid = ulid::Ulid::new().to_string().to_lowercase(),
-                ))
+            let photos = if with_photo {
+                vec![Self::gen_meal_photo()]
             } else {
-                None
+                vec![]
             };
             let body = TestBodyMeal {
                 date,
                 category,
                 description,
                 person: S!("Jack"),
+                slot: None,
                 restaurant: false,
                 takeaway: true,
                 vegetarian: false,
-                photo_original,
-                photo_converted,
+                photos,
+                visibility: None,
+                tags: vec![],
+                venue_id: None,
             };
             self.test_meal = Some(C!(body));
             body
         }
 
+        /// Delete the test meals, in every slot, for both Jack and the test person (if they exist)
         async fn delete_meal(&mut self) {
             let meal = self.gen_meal(true);
-            let person = Person::try_from(meal.person.as_str()).unwrap();
             let date = meal.date.parse::<Date>().unwrap();
-            ModelMeal::delete(&self.postgres, &person, date).await.ok();
+            for person in [meal.person.as_str(), TEST_PERSON] {
+                if let Ok(person) = Person::try_from(person) {
+                    self.purge_meal(&person, date).await;
+                }
+            }
+        }
+
+        /// Permanently delete every meal, in every slot, of a person on a given date, including any already in the trash
+        pub async fn purge_meal(&self, person: &Person, date: Date) {
+            for slot in [MealSlot::Breakfast, MealSlot::Lunch, MealSlot::Dinner] {
+                ModelMeal::delete(&self.postgres, person, date, slot)
+                    .await
+                    .ok();
+            }
+            for meal in ModelMealTrash::get_all(&self.postgres).await.unwrap() {
+                if meal.meal.person == person.to_string() && meal.meal.meal_date.to_jiff() == date {
+                    meal.purge(&self.postgres).await.unwrap();
+                }
+            }
         }
 
         pub async fn query_meal(&self) -> Option<ModelMeal> {
             if let Some(meal) = self.test_meal.as_ref() {
                 let person = Person::try_from(meal.person.as_str()).unwrap();
                 let date = meal.date.parse::<Date>().unwrap();
-                ModelMeal::get_by_date_person(&self.postgres, &person, date)
+                let slot = meal
+                    .slot
+                    .as_deref()
+                    .map_or(MealSlot::Dinner, |i| MealSlot::try_from(i).unwrap());
+                ModelMeal::get_by_date_person(&self.postgres, &person, date, slot)
                     .await
                     .unwrap()
             } else {
@@ -386,6 +576,80 @@ pub mod api_tests {
             }
         }
 
+        /// Insert the test person, without a meal
+        pub async fn insert_test_person(&self) {
+            sqlx::query!(
+                "INSERT INTO meal_person(person, initial, visibility) VALUES($1, $2, 'authenticated')",
+                TEST_PERSON,
+                TEST_PERSON_INITIAL
+            )
+            .execute(&self.postgres)
+            .await
+            .unwrap();
+            ModelPerson::load(&self.postgres).await.unwrap();
+        }
+
+        /// Remove the test person from postgres, and reload the person registry
+        pub async fn delete_test_person(&self) {
+            sqlx::query!(
+                "DELETE FROM meal_person WHERE person = $1 OR initial = $2",
+                TEST_PERSON,
+                TEST_PERSON_INITIAL
+            )
+            .execute(&self.postgres)
+            .await
+            .unwrap();
+            ModelPerson::load(&self.postgres).await.unwrap();
+        }
+
+        /// Insert a random meal tag, created by the test user, returns the tag id
+        pub async fn insert_test_tag(&self) -> i64 {
+            sqlx::query_scalar!(
+                "INSERT INTO meal_tag(tag, registered_user_id) VALUES($1, $2) RETURNING meal_tag_id",
+                gen_random_hex(12).to_lowercase(),
+                self.model_user.as_ref().unwrap().registered_user_id
+            )
+            .fetch_one(&self.postgres)
+            .await
+            .unwrap()
+        }
+
+        /// Delete any meal tags created by the test users
+        pub async fn delete_tags(&self) {
+            sqlx::query!(
+                "DELETE FROM meal_tag WHERE registered_user_id IN (SELECT registered_user_id FROM registered_user WHERE email IN ($1, $2))",
+                TEST_EMAIL,
+                ANON_EMAIL
+            )
+            .execute(&self.postgres)
+            .await
+            .unwrap();
+        }
+
+        /// Insert a venue, with a random name, returning the meal_venue_id
+        pub async fn insert_test_venue(&self) -> i64 {
+            sqlx::query_scalar!(
+                "INSERT INTO meal_venue(name, city, venue_type, registered_user_id) VALUES($1, 'London', 'restaurant', $2) RETURNING meal_venue_id",
+                gen_random_hex(12).to_lowercase(),
+                self.model_user.as_ref().unwrap().registered_user_id
+            )
+            .fetch_one(&self.postgres)
+            .await
+            .unwrap()
+        }
+
+        /// Delete any venues created by the test users
+        pub async fn delete_venues(&self) {
+            sqlx::query!(
+                "DELETE FROM meal_venue WHERE registered_user_id IN (SELECT registered_user_id FROM registered_user WHERE email IN ($1, $2))",
+                TEST_EMAIL,
+                ANON_EMAIL
+            )
+            .execute(&self.postgres)
+            .await
+            .unwrap();
+        }
+
         /// Delete emails that were written to disk
         pub async fn delete_login_attempts(&self) {
             sqlx::query!("DELETE FROM login_attempt")
@@ -745,6 +1009,7 @@ pub mod api_tests {
         let app_env = parse_env::AppEnv::get_env();
         let postgres = db_postgres::db_pool(&app_env).await.unwrap();
         let redis = DbRedis::get_pool(&app_env).await.unwrap();
+        ModelPerson::load(&postgres).await.unwrap();
         let mut test_setup = TestSetup {
             app_env,
             postgres,
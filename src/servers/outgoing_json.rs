@@ -1,17 +1,27 @@
 pub mod oj {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     use axum::Json;
     use serde::{Deserialize, Serialize};
 
     use crate::{
-        S,
+        C, S,
         api_error::ApiError,
-        database::{ModelDateMeal, ModelMeal, ModelMissingFood, ModelUser, Person},
+        database::{
+            MealSlot, ModelCategory, ModelDateMeal, ModelDescription, ModelMeal, ModelMealPhoto,
+            ModelMealRevision, ModelMealSearch, ModelMealTrash, ModelMissingFood, ModelOrphans,
+            ModelPerson, ModelTag, ModelUser, ModelVenue, ModelVenueVisits, Person,
+            SEARCH_PAGE_SIZE, Visibility,
+        },
     };
 
     pub type AsJsonRes<T> = Json<OutgoingJson<T>>;
 
+    /// A meal revision, or a meals cache hash, as a strong ETag header value
+    pub fn etag(tag: impl std::fmt::Display) -> String {
+        format!("\"{tag}\"")
+    }
+
     /// Used to skip serializtion if value is None or false
     #[expect(clippy::trivially_copy_pass_by_ref, clippy::ref_option)]
     pub fn none_or_false(x: &Option<bool>) -> bool {
@@ -64,6 +74,34 @@ pub mod oj {
         pub original: String,
     }
 
+    #[derive(Serialize)]
+    pub struct Orphans {
+        pub dry_run: bool,
+        pub categories: Vec<String>,
+        pub dates: Vec<String>,
+        pub descriptions: Vec<String>,
+        pub photos: Vec<Photo>,
+    }
+
+    impl Orphans {
+        pub fn from_model(orphans: ModelOrphans, dry_run: bool) -> Self {
+            Self {
+                dry_run,
+                categories: orphans.categories,
+                dates: orphans.dates,
+                descriptions: orphans.descriptions,
+                photos: orphans
+                    .photos
+                    .into_iter()
+                    .map(|(converted, original)| Photo {
+                        converted,
+                        original,
+                    })
+                    .collect(),
+            }
+        }
+    }
+
     #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
     pub struct MissingFood {
         pub date: String,
@@ -83,6 +121,7 @@ pub mod oj {
         }
     }
 
+    #[expect(clippy::struct_excessive_bools)]
     #[derive(Serialize)]
     pub struct AuthenticatedUser {
         pub email: String,
@@ -90,6 +129,7 @@ pub mod oj {
         pub two_fa_active: bool,
         pub two_fa_always_required: bool,
         pub two_fa_count: i64,
+        pub meal_reminder: bool,
     }
 
     impl From<ModelUser> for AuthenticatedUser {
@@ -100,6 +140,7 @@ pub mod oj {
                 two_fa_active: user.two_fa_secret.is_some(),
                 two_fa_always_required: user.two_fa_always_required,
                 two_fa_count: user.two_fa_backup_count,
+                meal_reminder: user.meal_reminder,
             }
         }
     }
@@ -114,17 +155,37 @@ pub mod oj {
         pub backups: Vec<String>,
     }
 
+    #[derive(Serialize)]
+    pub struct MealPhoto {
+        pub original: Option<String>,
+        pub converted: String,
+        pub caption: Option<String>,
+    }
+
+    impl From<ModelMealPhoto> for MealPhoto {
+        fn from(photo: ModelMealPhoto) -> Self {
+            Self {
+                original: photo.photo_original,
+                converted: photo.photo_converted,
+                caption: photo.caption,
+            }
+        }
+    }
+
     #[derive(Serialize)]
     pub struct Meal {
         pub date: String,
         pub category: String,
         pub person: String,
+        pub slot: String,
         pub restaurant: bool,
         pub takeaway: bool,
         pub vegetarian: bool,
         pub description: String,
-        pub photo_original: Option<String>,
-        pub photo_converted: Option<String>,
+        pub photos: Vec<MealPhoto>,
+        pub visibility: Option<String>,
+        pub tags: Vec<i64>,
+        pub venue_id: Option<i64>,
     }
 
     impl From<ModelMeal> for Meal {
@@ -133,16 +194,102 @@ pub mod oj {
                 date: meal.meal_date.to_jiff().to_string(),
                 category: meal.category,
                 person: meal.person,
+                slot: meal.slot,
                 restaurant: meal.restaurant,
                 takeaway: meal.takeaway,
                 vegetarian: meal.vegetarian,
                 description: meal.description,
-                photo_original: meal.photo_original,
-                photo_converted: meal.photo_converted,
+                photos: meal.photos.into_iter().map(MealPhoto::from).collect(),
+                visibility: meal.visibility,
+                tags: meal.tags,
+                venue_id: meal.meal_venue_id,
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct MealSearch {
+        pub meals: Vec<Meal>,
+        pub page: i64,
+        pub page_size: i64,
+        pub total: i64,
+    }
+
+    impl MealSearch {
+        pub fn from_model(data: ModelMealSearch, page: Option<i64>) -> Self {
+            Self {
+                total: data.total,
+                meals: data.meals.into_iter().map(Meal::from).collect(),
+                page: page.unwrap_or(1),
+                page_size: SEARCH_PAGE_SIZE,
             }
         }
     }
 
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    pub struct MealSuggestion {
+        pub description: String,
+        pub category: String,
+        pub vegetarian: bool,
+        pub times: i64,
+        pub last_eaten: String,
+        pub days_since: i32,
+        /// Between 0 and 1, higher is a better suggestion
+        pub score: f64,
+    }
+
+    #[derive(Serialize)]
+    pub struct OnThisDayYear {
+        pub year: i16,
+        pub date: String,
+        pub meals: Vec<Meal>,
+    }
+
+    /// Meals from the same month and day of previous years, grouped by year, newest first
+    #[derive(Serialize)]
+    pub struct OnThisDay {
+        pub date: String,
+        pub years: Vec<OnThisDayYear>,
+    }
+
+    impl OnThisDay {
+        /// The meals must be ordered by date
+        pub fn from_model(date: jiff::civil::Date, meals: Vec<ModelMeal>) -> Self {
+            let mut years: Vec<OnThisDayYear> = vec![];
+            for meal in meals {
+                let meal_date = meal.meal_date.to_jiff();
+                match years.last_mut() {
+                    Some(previous) if previous.year == meal_date.year() => {
+                        previous.meals.push(Meal::from(meal));
+                    }
+                    _ => years.push(OnThisDayYear {
+                        year: meal_date.year(),
+                        date: meal_date.to_string(),
+                        meals: vec![Meal::from(meal)],
+                    }),
+                }
+            }
+            Self {
+                date: date.to_string(),
+                years,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct ImportError {
+        /// Row number, starting at 1, excluding any csv header row
+        pub row: usize,
+        pub error: String,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct MealImport {
+        pub rows: usize,
+        pub inserted: usize,
+        pub errors: Vec<ImportError>,
+    }
+
     #[derive(Serialize)]
     pub struct BackupFile {
         pub file_name: String,
@@ -159,6 +306,69 @@ pub mod oj {
         pub meal: Option<Meal>,
     }
 
+    #[derive(Serialize)]
+    pub struct MealRevision {
+        pub revision: i64,
+        /// "insert" or "update"
+        pub action: String,
+        pub timestamp: String,
+        /// The meal fields changed by this revision, empty for an insert
+        pub changed: Vec<String>,
+        pub date: Option<String>,
+        pub person: Option<String>,
+        pub slot: String,
+        pub category: Option<String>,
+        pub description: Option<String>,
+        pub restaurant: bool,
+        pub takeaway: bool,
+        pub vegetarian: bool,
+        pub visibility: Option<String>,
+        pub venue_id: Option<i64>,
+        pub photos: Vec<MealPhoto>,
+    }
+
+    impl From<ModelMealRevision> for MealRevision {
+        fn from(revision: ModelMealRevision) -> Self {
+            Self {
+                revision: revision.revision,
+                action: if revision.action == "i" {
+                    S!("insert")
+                } else {
+                    S!("update")
+                },
+                timestamp: revision.timestamp,
+                // Convert the individual_meal column names into meal field names, ignoring columns that aren't part of a meal
+                changed: revision
+                    .changed
+                    .into_iter()
+                    .filter_map(|column| match column.as_str() {
+                        "meal_date_id" => Some(S!("date")),
+                        "meal_person_id" => Some(S!("person")),
+                        "meal_category_id" => Some(S!("category")),
+                        "meal_description_id" => Some(S!("description")),
+                        "meal_photo_id" => Some(S!("photo")),
+                        "meal_slot" => Some(S!("slot")),
+                        "meal_venue_id" => Some(S!("venue_id")),
+                        "deleted_at" => Some(S!("deleted")),
+                        "restaurant" | "takeaway" | "vegetarian" | "visibility" => Some(column),
+                        _ => None,
+                    })
+                    .collect(),
+                date: revision.date,
+                person: revision.person,
+                slot: revision.slot,
+                category: revision.category,
+                description: revision.description,
+                restaurant: revision.restaurant,
+                takeaway: revision.takeaway,
+                vegetarian: revision.vegetarian,
+                visibility: revision.visibility,
+                venue_id: revision.venue_id,
+                photos: revision.photos.0.into_iter().map(MealPhoto::from).collect(),
+            }
+        }
+    }
+
     #[derive(Serialize)]
     pub struct AdminMemory {
         pub uptime: u64,
@@ -177,6 +387,155 @@ pub mod oj {
         pub meal_date: Option<String>,
     }
 
+    #[derive(Debug, Serialize)]
+    pub struct AdminCategory {
+        pub category: String,
+        pub meals: i64,
+    }
+
+    impl From<ModelCategory> for AdminCategory {
+        fn from(category: ModelCategory) -> Self {
+            Self {
+                category: category.category,
+                meals: category.meals,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct AdminTag {
+        pub meal_tag_id: i64,
+        pub tag: String,
+        pub meals: i64,
+    }
+
+    impl From<ModelTag> for AdminTag {
+        fn from(tag: ModelTag) -> Self {
+            Self {
+                meal_tag_id: tag.meal_tag_id,
+                tag: tag.tag,
+                meals: tag.meals,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+    pub struct Coordinates {
+        pub latitude: f64,
+        pub longitude: f64,
+    }
+
+    impl Coordinates {
+        fn from_model(latitude: Option<f64>, longitude: Option<f64>) -> Option<Self> {
+            latitude.zip(longitude).map(|(latitude, longitude)| Self {
+                latitude,
+                longitude,
+            })
+        }
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct AdminVenue {
+        pub meal_venue_id: i64,
+        pub name: String,
+        pub city: String,
+        pub venue_type: String,
+        pub coordinates: Option<Coordinates>,
+        pub meals: i64,
+    }
+
+    impl From<ModelVenue> for AdminVenue {
+        fn from(venue: ModelVenue) -> Self {
+            Self {
+                meal_venue_id: venue.meal_venue_id,
+                name: venue.name,
+                city: venue.city,
+                venue_type: venue.venue_type,
+                coordinates: Coordinates::from_model(venue.latitude, venue.longitude),
+                meals: venue.meals,
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct TrashMeal {
+        pub id: i64,
+        pub deleted_at: String,
+        #[serde(flatten)]
+        pub meal: Meal,
+    }
+
+    impl From<ModelMealTrash> for TrashMeal {
+        fn from(trash: ModelMealTrash) -> Self {
+            Self {
+                id: trash.meal.individual_meal_id,
+                deleted_at: trash.deleted_at,
+                meal: Meal::from(trash.meal),
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    pub struct VenueVisits {
+        pub meal_venue_id: i64,
+        pub name: String,
+        pub city: String,
+        pub venue_type: String,
+        pub coordinates: Option<Coordinates>,
+        pub visits: i64,
+        pub first_visit: String,
+        pub last_visit: String,
+    }
+
+    impl From<ModelVenueVisits> for VenueVisits {
+        fn from(venue: ModelVenueVisits) -> Self {
+            Self {
+                meal_venue_id: venue.meal_venue_id,
+                name: venue.name,
+                city: venue.city,
+                venue_type: venue.venue_type,
+                coordinates: Coordinates::from_model(venue.latitude, venue.longitude),
+                visits: venue.visits,
+                first_visit: venue.first_visit,
+                last_visit: venue.last_visit,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct AdminDescription {
+        pub description: String,
+        pub meals: i64,
+    }
+
+    impl From<ModelDescription> for AdminDescription {
+        fn from(description: ModelDescription) -> Self {
+            Self {
+                description: description.description,
+                meals: description.meals,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct AdminPerson {
+        pub person: String,
+        pub initial: String,
+        pub visibility: Visibility,
+        pub email: Option<String>,
+    }
+
+    impl From<ModelPerson> for AdminPerson {
+        fn from(person: ModelPerson) -> Self {
+            Self {
+                visibility: person.visibility(),
+                person: person.person,
+                initial: person.initial,
+                email: person.email,
+            }
+        }
+    }
+
     #[derive(Serialize, Debug)]
     pub struct Limit {
         pub key: String,
@@ -196,6 +555,8 @@ pub mod oj {
         pub original: Option<String>,
         #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
         pub converted: Option<String>,
+        #[serde(rename = "t", default, skip_serializing_if = "Option::is_none")]
+        pub caption: Option<String>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
@@ -228,71 +589,123 @@ pub mod oj {
 
     pub type MealDescriptionMap = HashMap<i64, String>;
     pub type MealCategoryMap = HashMap<i64, String>;
+    pub type MealTagMap = HashMap<i64, String>;
+    /// Person initial as key, person name as value
+    pub type PersonMap = HashMap<String, String>;
+    /// Person initial as key
+    pub type MealCountMap = HashMap<String, MealCounts>;
+
+    /// How many times a person has eaten each category, and each description, id as key, count as value
+    #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct MealCounts {
+        #[serde(rename = "c")]
+        pub categories: HashMap<i64, usize>,
+        #[serde(rename = "d")]
+        pub descriptions: HashMap<i64, usize>,
+    }
+
+    impl MealCounts {
+        /// Count the categories, and descriptions, of every meal slot, per person
+        pub fn count(date_meals: &[DateMeal]) -> MealCountMap {
+            let mut output = MealCountMap::new();
+            for (person, meal) in date_meals.iter().flat_map(DateMeal::meals) {
+                let counts = output.entry(C!(person)).or_default();
+                *counts.categories.entry(meal.category_id).or_default() += 1;
+                *counts
+                    .descriptions
+                    .entry(meal.meal_description_id)
+                    .or_default() += 1;
+            }
+            output
+        }
+    }
 
-    #[allow(non_snake_case)]
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
     pub struct DateMeal {
         #[serde(rename = "a")]
         pub date: String,
-        #[serde(rename = "d", skip_serializing_if = "Option::is_none")]
-        pub Dave: Option<PersonMeal>,
-        #[serde(rename = "j", skip_serializing_if = "Option::is_none")]
-        pub Jack: Option<PersonMeal>,
+        /// Person initial as key, BTreeMap so that the output, and therefore hash, is consistently ordered
+        /// The dinner slot, which every person is expected to have, the "p" is kept from when dinner was the only slot
+        #[serde(rename = "p")]
+        pub dinner: BTreeMap<String, PersonMeal>,
+        #[serde(rename = "b", default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub breakfast: BTreeMap<String, PersonMeal>,
+        #[serde(rename = "l", default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub lunch: BTreeMap<String, PersonMeal>,
     }
 
     impl From<ModelDateMeal> for DateMeal {
         fn from(value: ModelDateMeal) -> Self {
-            let photo = if let Some(original) = &value.photo_original
-                && let Some(converted) = &value.photo_converted
-            {
-                Some(PersonPhoto {
-                    original: Some(S!(original)),
-                    converted: Some(S!(converted)),
-                })
-            } else {
-                value.photo_converted.map(|converted| PersonPhoto {
-                    original: None,
-                    converted: Some(converted),
+            let photos = value
+                .photos
+                .0
+                .into_iter()
+                .map(|photo| PersonPhoto {
+                    original: photo.photo_original,
+                    converted: Some(photo.photo_converted),
+                    caption: photo.caption,
                 })
+                .collect();
+
+            let person_meal = BTreeMap::from([(
+                value.initial,
+                PersonMeal {
+                    meal_description_id: value.meal_description_id,
+                    category_id: value.meal_category_id,
+                    restaurant: value.restaurant,
+                    vegetarian: value.vegetarian,
+                    takeaway: value.takeaway,
+                    photos,
+                    tags: value.tags,
+                },
+            )]);
+            let mut output = Self {
+                dinner: BTreeMap::new(),
+                breakfast: BTreeMap::new(),
+                lunch: BTreeMap::new(),
+                date: Self::short_date(&value.date_of_meal),
             };
+            match MealSlot::try_from(value.meal_slot.as_str()).unwrap_or_default() {
+                MealSlot::Breakfast => output.breakfast = person_meal,
+                MealSlot::Lunch => output.lunch = person_meal,
+                MealSlot::Dinner => output.dinner = person_meal,
+            }
+            output
+        }
+    }
 
-            if value.person == "Jack" {
-                Self {
-                    Dave: None,
-                    Jack: Some(PersonMeal {
-                        meal_description_id: value.meal_description_id,
-                        category_id: value.meal_category_id,
-                        restaurant: value.restaurant,
-                        vegetarian: value.vegetarian,
-                        takeaway: value.takeaway,
-                        photo,
-                    }),
-                    date: value
-                        .date_of_meal
-                        .chars()
-                        .skip(2)
-                        .collect::<String>()
-                        .replace('-', ""),
-                }
-            } else {
-                Self {
-                    Dave: Some(PersonMeal {
-                        meal_description_id: value.meal_description_id,
-                        category_id: value.meal_category_id,
-                        restaurant: value.restaurant,
-                        vegetarian: value.vegetarian,
-                        takeaway: value.takeaway,
-                        photo,
-                    }),
-                    Jack: None,
-                    date: value
-                        .date_of_meal
-                        .chars()
-                        .skip(2)
-                        .collect::<String>()
-                        .replace('-', ""),
+    impl DateMeal {
+        /// Convert, and group, date ordered ModelDateMeals into DateMeals, one entry per date
+        pub fn group(data: Vec<ModelDateMeal>) -> Vec<Self> {
+            let mut date_meals: Vec<Self> = vec![];
+            for i in data.into_iter().map(Self::from) {
+                if let Some(given) = date_meals.iter_mut().find(|x| x.date == i.date) {
+                    given.dinner.extend(i.dinner);
+                    given.breakfast.extend(i.breakfast);
+                    given.lunch.extend(i.lunch);
+                } else {
+                    date_meals.push(i);
                 }
             }
+            date_meals
+        }
+
+        /// Every meal of the date, in every slot, with the person initial
+        pub fn meals(&self) -> impl Iterator<Item = (&String, &PersonMeal)> {
+            self.dinner
+                .iter()
+                .chain(self.breakfast.iter())
+                .chain(self.lunch.iter())
+        }
+
+        /// Check if a person has a meal, in any slot, on this date
+        pub fn has_person(&self, initial: &str) -> bool {
+            self.meals().any(|(person, _)| person == initial)
+        }
+
+        /// Convert a yyyy-mm-dd date into the shortened yymmdd format
+        pub fn short_date(date: &str) -> String {
+            date.chars().skip(2).collect::<String>().replace('-', "")
         }
     }
 
@@ -308,16 +721,81 @@ pub mod oj {
         pub vegetarian: Option<i32>,
         #[serde(rename = "t", skip_serializing_if = "none_or_zero")]
         pub takeaway: Option<i32>,
-        #[serde(rename = "p", skip_serializing_if = "Option::is_none")]
-        pub photo: Option<PersonPhoto>,
+        /// Ordered by position
+        #[serde(rename = "p", default, skip_serializing_if = "Vec::is_empty")]
+        pub photos: Vec<PersonPhoto>,
+        /// Tag ids
+        #[serde(rename = "g", default, skip_serializing_if = "Vec::is_empty")]
+        pub tags: Vec<i64>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct RepeatGap {
+        pub description: String,
+        pub days: i32,
+        pub from: String,
+        pub to: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct StatsSummary {
+        pub total: usize,
+        /// Category name as key, count as value
+        pub categories: HashMap<String, usize>,
+        /// Percentages of total meals
+        pub restaurant: f64,
+        pub takeaway: f64,
+        pub vegetarian: f64,
+        /// Most repeated descriptions, with their count, most repeated first
+        pub most_repeated: Vec<(String, usize)>,
+        /// Longest run of consecutive days of vegetarian meals
+        pub longest_vegetarian_streak: usize,
+        /// Longest number of days between two instances of the same description
+        pub longest_repeat_gap: Option<RepeatGap>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct PersonStats {
+        pub person: String,
+        pub all: StatsSummary,
+        pub years: BTreeMap<i32, StatsSummary>,
+    }
+
+    pub type MealStats = Vec<PersonStats>;
+
+    /// Every change since a given cursor, same short keys as MealInfo
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct MealChanges {
+        #[serde(rename = "p")]
+        pub people: PersonMap,
+        #[serde(rename = "d")]
+        pub meal_descriptions: MealDescriptionMap,
+        #[serde(rename = "c")]
+        pub meal_categories: MealCategoryMap,
+        #[serde(rename = "g")]
+        pub meal_tags: MealTagMap,
+        /// Inserted or updated dates, each date contains all its meals
+        #[serde(rename = "m")]
+        pub date_meals: Vec<DateMeal>,
+        /// Dates, yymmdd, that no longer have any meals
+        #[serde(rename = "x")]
+        pub deleted: Vec<String>,
+        pub cursor: String,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
     pub struct MealInfo {
+        #[serde(rename = "p")]
+        pub people: PersonMap,
         #[serde(rename = "d")]
         pub meal_descriptions: MealDescriptionMap,
         #[serde(rename = "c")]
         pub meal_categories: MealCategoryMap,
+        #[serde(rename = "g")]
+        pub meal_tags: MealTagMap,
+        /// Derived from date_meals, so is covered by the date_meals hash
+        #[serde(rename = "n")]
+        pub counts: MealCountMap,
         #[serde(rename = "m")]
         pub date_meals: Vec<DateMeal>,
     }
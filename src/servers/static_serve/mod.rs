@@ -23,7 +23,7 @@ use axum::{
 use crate::{
     C, S,
     api_error::ApiError,
-    database::RedisSession,
+    database::{MealResponse, RedisSession, Visibility},
     define_routes,
     parse_env::{AppEnv, RunMode},
     servers::{get_addr, ij::PhotoName, rate_limiting, shutdown_signal},
@@ -161,42 +161,38 @@ impl StaticRouter {
         };
 
         let user = if let Some(ulid) = get_cookie_ulid(&state, &jar) {
-            RedisSession::exists(&state.redis, &ulid)
+            RedisSession::get(&state.redis, &state.postgres, &ulid)
                 .await
                 .unwrap_or_default()
         } else {
             None
         };
-        match &photoname {
-            PhotoName::Converted(_) => {
-                if photoname.is_dave() && user.is_none() {
-                    not_found()
-                } else {
-                    let cache = if photoname.is_dave() {
-                        HeaderValue::from_static("no-cache")
-                    } else {
-                        HeaderValue::from_static("max-age=8640000")
-                    };
-                    let file_path = state.photo_env.get_pathbuff(photoname);
-                    (Self::serve_photo(file_path, cache).await).map_or_else(
-                        |()| not_found(),
-                        axum::response::IntoResponse::into_response,
-                    )
-                }
-            }
-            PhotoName::Original(_) => {
-                if user.is_some() {
-                    let file_path = state.photo_env.get_pathbuff(photoname);
-                    (Self::serve_photo(file_path, HeaderValue::from_static("no-cache")).await)
-                        .map_or_else(
-                            |()| not_found(),
-                            axum::response::IntoResponse::into_response,
-                        )
-                } else {
-                    not_found()
-                }
-            }
+
+        // A photo that isn't used by any meal is only visible to admin users, and original photos are never public
+        let Ok(required) =
+            MealResponse::photo_visibility(&state.postgres, &state.redis, &photoname).await
+        else {
+            return not_found();
+        };
+        let required = required.unwrap_or(Visibility::Admin);
+        let required = match &photoname {
+            PhotoName::Converted(_) => required,
+            PhotoName::Original(_) => required.max(Visibility::Authenticated),
+        };
+
+        if required > Visibility::from(user.as_ref()) {
+            return not_found();
         }
+        let cache = if required == Visibility::Public {
+            HeaderValue::from_static("max-age=8640000")
+        } else {
+            HeaderValue::from_static("no-cache")
+        };
+        let file_path = state.photo_env.get_pathbuff(photoname);
+        (Self::serve_photo(file_path, cache).await).map_or_else(
+            |()| not_found(),
+            axum::response::IntoResponse::into_response,
+        )
     }
 }
 